@@ -4,7 +4,10 @@ use gpui::{App, Bounds, KeyBinding, WindowBounds, WindowOptions, prelude::*, px,
 use gpui_component::{Root, Theme, ThemeMode};
 
 use crate::models::GameModel;
-use crate::ui::views::{ChessBoardView, MoveBack, MoveForward, MoveToEnd, MoveToStart};
+use crate::ui::views::{
+    ChessBoardView, FlipBoard, MoveBack, MoveForward, MoveToEnd, MoveToStart, NewGame, OpenPgn,
+    SavePgn,
+};
 
 /// Initialize and run the chess application
 pub fn run(cx: &mut App) {
@@ -19,6 +22,10 @@ pub fn run(cx: &mut App) {
         KeyBinding::new("right", MoveForward, None),
         KeyBinding::new("home", MoveToStart, None),
         KeyBinding::new("end", MoveToEnd, None),
+        KeyBinding::new("secondary-n", NewGame, None),
+        KeyBinding::new("secondary-o", OpenPgn, None),
+        KeyBinding::new("secondary-s", SavePgn, None),
+        KeyBinding::new("secondary-f", FlipBoard, None),
     ]);
 
     // Create the game model
@@ -1,7 +1,15 @@
 //! Pure chess domain types and utilities.
 //! No GPUI dependencies - this is the domain layer.
 
-use shakmaty::{Color as SColor, File, Rank, Role, Square};
+use std::fmt;
+
+use shakmaty::fen::{Fen, ParseFenError};
+use shakmaty::san::San;
+use shakmaty::uci::UciMove;
+use shakmaty::{
+    CastlingMode, Chess, Color as SColor, EnPassantMode, File, Position, PositionErrorKinds, Rank,
+    Role, Square,
+};
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum PieceKind {
@@ -19,6 +27,16 @@ pub enum PieceColor {
     Black,
 }
 
+impl PieceColor {
+    /// The other side
+    pub fn opposite(self) -> Self {
+        match self {
+            PieceColor::White => PieceColor::Black,
+            PieceColor::Black => PieceColor::White,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Piece {
     pub kind: PieceKind,
@@ -32,6 +50,11 @@ pub fn to_square(row: usize, col: usize) -> Square {
     Square::from_coords(file, rank)
 }
 
+/// Convert shakmaty Square to row/col (0-indexed, row 0 = rank 8)
+pub fn square_to_row_col(sq: Square) -> (usize, usize) {
+    (7 - usize::from(sq.rank()), usize::from(sq.file()))
+}
+
 /// Convert shakmaty piece to our domain Piece
 pub fn shakmaty_to_piece(piece: shakmaty::Piece) -> Piece {
     let kind = match piece.role {
@@ -42,9 +65,304 @@ pub fn shakmaty_to_piece(piece: shakmaty::Piece) -> Piece {
         Role::Queen => PieceKind::Queen,
         Role::King => PieceKind::King,
     };
-    let color = match piece.color {
+    Piece {
+        kind,
+        color: shakmaty_to_color(piece.color),
+    }
+}
+
+/// Convert shakmaty Color to our domain PieceColor
+pub fn shakmaty_to_color(color: SColor) -> PieceColor {
+    match color {
         SColor::White => PieceColor::White,
         SColor::Black => PieceColor::Black,
-    };
-    Piece { kind, color }
+    }
+}
+
+/// Convert our domain PieceKind to a shakmaty Role
+pub fn piece_kind_to_role(kind: PieceKind) -> Role {
+    match kind {
+        PieceKind::Pawn => Role::Pawn,
+        PieceKind::Knight => Role::Knight,
+        PieceKind::Bishop => Role::Bishop,
+        PieceKind::Rook => Role::Rook,
+        PieceKind::Queen => Role::Queen,
+        PieceKind::King => Role::King,
+    }
+}
+
+/// Convert our domain PieceColor to a shakmaty Color
+pub fn color_to_shakmaty(color: PieceColor) -> SColor {
+    match color {
+        PieceColor::White => SColor::White,
+        PieceColor::Black => SColor::Black,
+    }
+}
+
+/// Convert our domain Piece to a shakmaty piece
+pub fn piece_to_shakmaty(piece: Piece) -> shakmaty::Piece {
+    shakmaty::Piece {
+        color: color_to_shakmaty(piece.color),
+        role: piece_kind_to_role(piece.kind),
+    }
+}
+
+/// Why a position can't be played from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PositionError {
+    EmptyBoard,
+    MissingKing,
+    TooManyKings,
+    PawnsOnBackRank,
+    InvalidCastlingRights,
+    InvalidEnPassant,
+    OppositeCheck,
+    ImpossibleCheck,
+    TooMuchMaterial,
+    Other,
+}
+
+impl PositionError {
+    /// Pick the most relevant reason from shakmaty's validation result
+    pub fn from_kinds(kinds: PositionErrorKinds) -> Self {
+        let reasons = [
+            (PositionErrorKinds::EMPTY_BOARD, PositionError::EmptyBoard),
+            (PositionErrorKinds::MISSING_KING, PositionError::MissingKing),
+            (
+                PositionErrorKinds::TOO_MANY_KINGS,
+                PositionError::TooManyKings,
+            ),
+            (
+                PositionErrorKinds::PAWNS_ON_BACKRANK,
+                PositionError::PawnsOnBackRank,
+            ),
+            (
+                PositionErrorKinds::INVALID_CASTLING_RIGHTS,
+                PositionError::InvalidCastlingRights,
+            ),
+            (
+                PositionErrorKinds::INVALID_EP_SQUARE,
+                PositionError::InvalidEnPassant,
+            ),
+            (
+                PositionErrorKinds::OPPOSITE_CHECK,
+                PositionError::OppositeCheck,
+            ),
+            (
+                PositionErrorKinds::IMPOSSIBLE_CHECK,
+                PositionError::ImpossibleCheck,
+            ),
+            (
+                PositionErrorKinds::TOO_MUCH_MATERIAL,
+                PositionError::TooMuchMaterial,
+            ),
+        ];
+        reasons
+            .into_iter()
+            .find(|(kind, _)| kinds.contains(*kind))
+            .map_or(PositionError::Other, |(_, error)| error)
+    }
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            PositionError::EmptyBoard => "the board is empty",
+            PositionError::MissingKing => "each side needs a king",
+            PositionError::TooManyKings => "each side can only have one king",
+            PositionError::PawnsOnBackRank => "pawns can't stand on the first or last rank",
+            PositionError::InvalidCastlingRights => {
+                "castling needs the king and rook on their starting squares"
+            }
+            PositionError::InvalidEnPassant => {
+                "the en passant square doesn't match a pawn that just advanced two squares"
+            }
+            PositionError::OppositeCheck => "the side not to move is in check",
+            PositionError::ImpossibleCheck => "the check on the board can't happen in a real game",
+            PositionError::TooMuchMaterial => "there is more material than a real game allows",
+            PositionError::Other => "illegal position",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+/// Error returned when a FEN can't be turned into a playable position
+#[derive(Clone, Debug, PartialEq)]
+pub enum FenError {
+    /// The FEN text is malformed
+    Syntax(ParseFenError),
+    /// The FEN is well-formed but describes an illegal position (e.g. a missing king)
+    IllegalPosition(PositionError),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::Syntax(e) => {
+                let field = match e {
+                    ParseFenError::InvalidBoard => "piece placement",
+                    ParseFenError::InvalidTurn => "side to move",
+                    ParseFenError::InvalidCastling => "castling rights",
+                    ParseFenError::InvalidEpSquare => "en passant square",
+                    ParseFenError::InvalidHalfmoveClock => "halfmove clock",
+                    ParseFenError::InvalidFullmoves => "fullmove number",
+                    _ => return write!(f, "Invalid FEN"),
+                };
+                write!(f, "Invalid FEN: bad {} field", field)
+            }
+            FenError::IllegalPosition(reason) => write!(f, "Invalid FEN: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+/// Parse and validate a FEN into a standard chess position
+pub fn parse_fen(fen: &str) -> Result<Chess, FenError> {
+    let fen: Fen = fen.trim().parse().map_err(FenError::Syntax)?;
+    fen.into_position(CastlingMode::Standard)
+        .map_err(|e| FenError::IllegalPosition(PositionError::from_kinds(e.kinds())))
+}
+
+/// Get the FEN of a position
+pub fn position_to_fen(position: &Chess) -> String {
+    Fen::from_position(position, EnPassantMode::Legal).to_string()
+}
+
+/// Get the from/to squares (as row/col) of a move in UCI notation (e.g. "e2e4").
+/// Castling is given as the king's move.
+pub fn uci_move_squares(uci: &str) -> Option<((usize, usize), (usize, usize))> {
+    match uci.parse::<UciMove>().ok()? {
+        UciMove::Normal { from, to, .. } => Some((square_to_row_col(from), square_to_row_col(to))),
+        UciMove::Put { .. } | UciMove::Null => None,
+    }
+}
+
+/// A move of a line played from a position (e.g. an engine's principal variation)
+#[derive(Clone, Debug)]
+pub struct LineMove {
+    /// Move number to show before the move ("12." or "12..."), if any
+    pub number: Option<String>,
+    /// The move in SAN
+    pub san: String,
+    /// The position after the move
+    pub position: Chess,
+}
+
+/// Convert a line of UCI moves (e.g. "e2e4") played from a position to SAN.
+/// Move numbers are given for white's moves and for a first move by black.
+/// Stops at the first move that isn't legal.
+pub fn uci_line_to_san(position: &Chess, moves: &[String]) -> Vec<LineMove> {
+    let mut line = Vec::new();
+    let mut position = position.clone();
+    for (i, uci) in moves.iter().enumerate() {
+        let Some(m) = uci
+            .parse::<UciMove>()
+            .ok()
+            .and_then(|uci| uci.to_move(&position).ok())
+        else {
+            break;
+        };
+        let number = match position.turn() {
+            SColor::White => Some(format!("{}.", position.fullmoves())),
+            SColor::Black if i == 0 => Some(format!("{}...", position.fullmoves())),
+            SColor::Black => None,
+        };
+        let san = San::from_move(&position, m).to_string();
+        position.play_unchecked(m);
+        line.push(LineMove {
+            number,
+            san,
+            position: position.clone(),
+        });
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(line: &[&str]) -> Vec<String> {
+        line.iter().map(|m| m.to_string()).collect()
+    }
+
+    #[test]
+    fn test_uci_move_squares() {
+        assert_eq!(uci_move_squares("e2e4"), Some(((6, 4), (4, 4))));
+        assert_eq!(uci_move_squares("e1g1"), Some(((7, 4), (7, 6))));
+        assert_eq!(uci_move_squares("a7a8q"), Some(((1, 0), (0, 0))));
+        assert_eq!(uci_move_squares("0000"), None);
+        assert_eq!(uci_move_squares("junk"), None);
+    }
+
+    #[test]
+    fn test_uci_line_to_san() {
+        let line = uci_line_to_san(&Chess::default(), &moves(&["e2e4", "e7e5", "g1f3"]));
+        let sans: Vec<&str> = line.iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(sans, vec!["e4", "e5", "Nf3"]);
+        let numbers: Vec<Option<&str>> = line.iter().map(|m| m.number.as_deref()).collect();
+        assert_eq!(numbers, vec![Some("1."), None, Some("2.")]);
+        assert_eq!(
+            position_to_fen(&line[2].position),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+    }
+
+    #[test]
+    fn test_uci_line_to_san_black_to_move() {
+        let position =
+            parse_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4")
+                .unwrap();
+        let line = uci_line_to_san(&position, &moves(&["e1g1", "f8c5", "d2d3"]));
+        let sans: Vec<&str> = line.iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(sans, vec!["O-O", "Bc5", "d3"]);
+
+        let line = uci_line_to_san(&line[0].position, &moves(&["f8c5", "d2d3"]));
+        assert_eq!(line[0].number.as_deref(), Some("4..."));
+        assert_eq!(line[1].number.as_deref(), Some("5."));
+    }
+
+    #[test]
+    fn test_uci_line_to_san_stops_at_illegal_move() {
+        let line = uci_line_to_san(&Chess::default(), &moves(&["e2e4", "e2e4", "d7d5"]));
+        assert_eq!(line.len(), 1);
+        assert!(uci_line_to_san(&Chess::default(), &moves(&["junk"])).is_empty());
+    }
+
+    #[test]
+    fn test_square_round_trip() {
+        assert_eq!(to_square(0, 0), Square::A8);
+        assert_eq!(to_square(7, 4), Square::E1);
+        assert_eq!(square_to_row_col(Square::E1), (7, 4));
+        for row in 0..8 {
+            for col in 0..8 {
+                assert_eq!(square_to_row_col(to_square(row, col)), (row, col));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_fen() {
+        let fen = "8/8/4k3/8/8/4K3/4P3/8 w - - 0 57";
+        let position = parse_fen(fen).unwrap();
+        assert_eq!(position.fullmoves().get(), 57);
+        assert_eq!(position_to_fen(&position), fen);
+    }
+
+    #[test]
+    fn test_parse_fen_syntax_error() {
+        let err = parse_fen("8/8/8 x - - 0 1").unwrap_err();
+        assert!(matches!(err, FenError::Syntax(_)));
+        assert!(err.to_string().starts_with("Invalid FEN"));
+    }
+
+    #[test]
+    fn test_parse_fen_illegal_position() {
+        let err = parse_fen("8/8/4k3/8/8/8/8/8 w - - 0 1").unwrap_err();
+        assert_eq!(err, FenError::IllegalPosition(PositionError::MissingKing));
+        assert_eq!(err.to_string(), "Invalid FEN: each side needs a king");
+    }
 }
@@ -0,0 +1,539 @@
+//! Time controls and the chess clock for over-the-board and engine games.
+//! Pure domain logic: the caller supplies the time.
+
+use std::time::Instant;
+
+use super::PieceColor;
+use super::uci::GoParams;
+
+/// How a time control adds time for each move
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimeBonus {
+    /// Fischer increment: the bonus is added after every move
+    Increment,
+    /// Bronstein delay: the time used is given back after the move, up to the bonus
+    BronsteinDelay,
+    /// Simple (US) delay: the clock waits for the bonus before it starts running
+    SimpleDelay,
+}
+
+impl TimeBonus {
+    /// All bonus kinds, in the order offered
+    pub const ALL: [TimeBonus; 3] = [
+        TimeBonus::Increment,
+        TimeBonus::BronsteinDelay,
+        TimeBonus::SimpleDelay,
+    ];
+
+    /// Name for display
+    pub fn label(self) -> &'static str {
+        match self {
+            TimeBonus::Increment => "Increment",
+            TimeBonus::BronsteinDelay => "Bronstein delay",
+            TimeBonus::SimpleDelay => "Simple delay",
+        }
+    }
+}
+
+/// One period of a time control: time for a number of moves (or the rest of the
+/// game) plus a bonus per move
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimeStage {
+    /// Moves to play in this period; None for the rest of the game
+    pub moves: Option<u32>,
+    /// Time added at the start of the period
+    pub base_ms: u64,
+    /// Increment or delay per move
+    pub bonus_ms: u64,
+}
+
+impl TimeStage {
+    /// A period of whole minutes plus a bonus in seconds
+    pub const fn minutes(moves: Option<u32>, base: u64, bonus_secs: u64) -> Self {
+        Self {
+            moves,
+            base_ms: base * 60_000,
+            bonus_ms: bonus_secs * 1000,
+        }
+    }
+
+    /// Label in the usual "minutes+bonus" form, e.g. "5+3" or "40/90+30"
+    fn label(&self) -> String {
+        let moves = self.moves.map(|moves| format!("{}/", moves));
+        let base = if self.base_ms.is_multiple_of(60_000) {
+            (self.base_ms / 60_000).to_string()
+        } else {
+            format!("{}s", self.base_ms / 1000)
+        };
+        format!(
+            "{}{}+{}",
+            moves.unwrap_or_default(),
+            base,
+            self.bonus_ms / 1000
+        )
+    }
+}
+
+/// Used by a time control without stages: no time at all
+const NO_TIME: TimeStage = TimeStage::minutes(None, 0, 0);
+
+/// The time each player gets: one or more periods and how the bonus is applied.
+/// If the last period has a move count it repeats.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TimeControl {
+    stages: Vec<TimeStage>,
+    bonus: TimeBonus,
+}
+
+impl TimeControl {
+    /// A single-period time control of whole minutes plus an increment in seconds
+    pub fn minutes(base: u64, increment_secs: u64) -> Self {
+        Self::from_stages(vec![TimeStage::minutes(None, base, increment_secs)])
+    }
+
+    /// A time control made of periods, with a Fischer increment
+    pub fn from_stages(stages: Vec<TimeStage>) -> Self {
+        Self {
+            stages,
+            bonus: TimeBonus::Increment,
+        }
+    }
+
+    /// Time controls offered when starting a clock
+    pub fn presets() -> Vec<TimeControl> {
+        let classical = TimeControl::from_stages(vec![
+            TimeStage::minutes(Some(40), 90, 30),
+            TimeStage::minutes(None, 30, 30),
+        ]);
+        [
+            (1, 0),
+            (3, 0),
+            (3, 2),
+            (5, 0),
+            (5, 3),
+            (10, 0),
+            (15, 10),
+            (30, 0),
+        ]
+        .into_iter()
+        .map(|(base, increment)| TimeControl::minutes(base, increment))
+        .chain([classical])
+        .collect()
+    }
+
+    /// The same periods with a different kind of bonus
+    pub fn with_bonus(self, bonus: TimeBonus) -> Self {
+        Self { bonus, ..self }
+    }
+
+    /// Get how the bonus is applied
+    pub fn bonus(&self) -> TimeBonus {
+        self.bonus
+    }
+
+    /// Whether two time controls have the same periods, whatever their bonus
+    pub fn same_stages(&self, other: &TimeControl) -> bool {
+        self.stages == other.stages
+    }
+
+    /// Short label of the periods, e.g. "5+3" or "40/90+30, 30+30"
+    pub fn stages_label(&self) -> String {
+        self.stages
+            .iter()
+            .map(TimeStage::label)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Label including the kind of delay, e.g. "5+3" or "5+3 Bronstein delay"
+    pub fn label(&self) -> String {
+        match self.bonus {
+            TimeBonus::Increment => self.stages_label(),
+            bonus => format!("{} {}", self.stages_label(), bonus.label()),
+        }
+    }
+
+    /// The time control as a PGN `TimeControl` tag, e.g. "40/5400+30:1800+30"
+    pub fn pgn_tag(&self) -> String {
+        self.stages
+            .iter()
+            .map(|stage| {
+                let moves = stage.moves.map(|moves| format!("{}/", moves));
+                let bonus = (stage.bonus_ms > 0).then(|| format!("+{}", stage.bonus_ms / 1000));
+                format!(
+                    "{}{}{}",
+                    moves.unwrap_or_default(),
+                    stage.base_ms / 1000,
+                    bonus.unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
+    /// Get the period a player's next move falls in after `moves_made` moves,
+    /// and whether that move completes the period
+    fn stage_of_move(&self, moves_made: u32) -> (usize, bool) {
+        let mut first_move = 0;
+        let mut index = 0;
+        loop {
+            let Some(stage) = self.stages.get(index) else {
+                return (index, false);
+            };
+            let Some(moves) = stage.moves.filter(|&moves| moves > 0) else {
+                return (index, false);
+            };
+            if moves_made < first_move + moves {
+                return (index, moves_made + 1 == first_move + moves);
+            }
+            first_move += moves;
+            // The last period repeats
+            index = (index + 1).min(self.stages.len() - 1);
+        }
+    }
+
+    /// Get a period by index (the last one repeats)
+    fn stage(&self, index: usize) -> &TimeStage {
+        self.stages
+            .get(index)
+            .or(self.stages.last())
+            .unwrap_or(&NO_TIME)
+    }
+}
+
+/// The clock reading for a move: the time it took and the time left after it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MoveClock {
+    /// Time left on the mover's clock after the move, including any bonus
+    pub remaining_ms: u64,
+    /// Time spent on the move, if known
+    pub elapsed_ms: Option<u64>,
+}
+
+/// A two-sided game clock. Only the side to move's time runs.
+#[derive(Clone, Debug)]
+pub struct GameClock {
+    time_control: TimeControl,
+    /// Time left for white and black at the start of their current turn
+    remaining_ms: [u64; 2],
+    /// Moves made by white and black
+    moves: [u32; 2],
+    /// The side whose time is running and when its turn started
+    running: Option<(PieceColor, Instant)>,
+}
+
+impl GameClock {
+    pub fn new(time_control: TimeControl) -> Self {
+        let base_ms = time_control.stage(0).base_ms;
+        Self {
+            time_control,
+            remaining_ms: [base_ms; 2],
+            moves: [0; 2],
+            running: None,
+        }
+    }
+
+    /// Get the time control the clock was set to
+    pub fn time_control(&self) -> &TimeControl {
+        &self.time_control
+    }
+
+    /// Start (or restart) a side's time without adding a bonus
+    pub fn start(&mut self, color: PieceColor, now: Instant) {
+        self.stop(now);
+        self.running = Some((color, now));
+    }
+
+    /// The running side has moved: charge its time, add the bonus and the time
+    /// of the next period if one starts, and start the other side's time.
+    /// A side that has run out of time gets nothing added.
+    /// Returns the clock reading for the move.
+    pub fn press(&mut self, now: Instant) -> Option<MoveClock> {
+        let (color, started) = self.running?;
+        let elapsed = elapsed_ms(started, now);
+        let (stage_index, completes_stage) =
+            self.time_control.stage_of_move(self.moves[side(color)]);
+        let stage = *self.time_control.stage(stage_index);
+
+        let mut remaining = self.remaining(color, now);
+        if remaining > 0 {
+            remaining += match self.time_control.bonus {
+                TimeBonus::Increment => stage.bonus_ms,
+                TimeBonus::BronsteinDelay => elapsed.min(stage.bonus_ms),
+                TimeBonus::SimpleDelay => 0,
+            };
+            if completes_stage {
+                remaining += self.time_control.stage(stage_index + 1).base_ms;
+            }
+        }
+        self.remaining_ms[side(color)] = remaining;
+        self.moves[side(color)] += 1;
+        self.running = Some((color.opposite(), now));
+        Some(MoveClock {
+            remaining_ms: remaining,
+            elapsed_ms: Some(elapsed),
+        })
+    }
+
+    /// Stop the clock, charging the running side for its time so far
+    pub fn stop(&mut self, now: Instant) {
+        if let Some((color, _)) = self.running {
+            self.remaining_ms[side(color)] = self.remaining(color, now);
+            self.running = None;
+        }
+    }
+
+    /// Get the side whose time is running
+    pub fn running_side(&self) -> Option<PieceColor> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// Get a side's time left in milliseconds
+    pub fn remaining(&self, color: PieceColor, now: Instant) -> u64 {
+        let remaining = self.remaining_ms[side(color)];
+        match self.running {
+            Some((running, started)) if running == color => {
+                let mut charged = elapsed_ms(started, now);
+                if self.time_control.bonus == TimeBonus::SimpleDelay {
+                    charged = charged.saturating_sub(self.current_stage(color).bonus_ms);
+                }
+                remaining.saturating_sub(charged)
+            }
+            _ => remaining,
+        }
+    }
+
+    /// Get the side that has run out of time, if any
+    pub fn flagged(&self, now: Instant) -> Option<PieceColor> {
+        [PieceColor::White, PieceColor::Black]
+            .into_iter()
+            .find(|&color| self.remaining(color, now) == 0)
+    }
+
+    /// `go` parameters telling the engine how much time both sides have.
+    /// A delay is passed as an increment, the nearest UCI has.
+    pub fn go_params(&self, now: Instant) -> GoParams {
+        let bonus = |color| {
+            let bonus_ms = self.current_stage(color).bonus_ms;
+            (bonus_ms > 0).then_some(bonus_ms)
+        };
+        let movestogo = self.running_side().and_then(|color| {
+            let moves_made = self.moves[side(color)];
+            let (index, _) = self.time_control.stage_of_move(moves_made);
+            let moves = self.time_control.stage(index).moves?;
+            // Moves left in the period, counting the one about to be made
+            let period_end = (0..=moves)
+                .map(|played| moves_made + played)
+                .find(|&made| self.time_control.stage_of_move(made).1)?;
+            Some(period_end - moves_made + 1)
+        });
+        GoParams {
+            wtime: Some(self.remaining(PieceColor::White, now)),
+            btime: Some(self.remaining(PieceColor::Black, now)),
+            winc: bonus(PieceColor::White),
+            binc: bonus(PieceColor::Black),
+            movestogo,
+            ..GoParams::default()
+        }
+    }
+
+    /// Get the period a side's next move falls in
+    fn current_stage(&self, color: PieceColor) -> &TimeStage {
+        let (index, _) = self.time_control.stage_of_move(self.moves[side(color)]);
+        self.time_control.stage(index)
+    }
+}
+
+/// Format a clock reading as "m:ss", with tenths under ten seconds
+pub fn format_clock(ms: u64) -> String {
+    let secs = ms / 1000;
+    if secs < 10 {
+        format!("0:{:02}.{}", secs, (ms % 1000) / 100)
+    } else if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Milliseconds from `started` to `now`
+fn elapsed_ms(started: Instant, now: Instant) -> u64 {
+    now.saturating_duration_since(started).as_millis() as u64
+}
+
+/// Index of a side in the per-side arrays
+fn side(color: PieceColor) -> usize {
+    match color {
+        PieceColor::White => 0,
+        PieceColor::Black => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(start: Instant, ms: u64) -> Instant {
+        start + Duration::from_millis(ms)
+    }
+
+    /// Play moves that take the given times, alternating sides from white
+    fn play(clock: &mut GameClock, start: Instant, move_times: &[u64]) -> u64 {
+        let mut now = 0;
+        for time in move_times {
+            now += time;
+            clock.press(at(start, now));
+        }
+        now
+    }
+
+    #[test]
+    fn test_time_control_labels() {
+        assert_eq!(TimeControl::minutes(5, 3).label(), "5+3");
+        assert_eq!(TimeControl::minutes(1, 0).label(), "1+0");
+        let fractional = TimeControl::from_stages(vec![TimeStage {
+            moves: None,
+            base_ms: 30_000,
+            bonus_ms: 0,
+        }]);
+        assert_eq!(fractional.label(), "30s+0");
+
+        let classical = TimeControl::from_stages(vec![
+            TimeStage::minutes(Some(40), 90, 30),
+            TimeStage::minutes(None, 30, 30),
+        ]);
+        assert_eq!(classical.label(), "40/90+30, 30+30");
+        assert_eq!(classical.pgn_tag(), "40/5400+30:1800+30");
+        let delay = TimeControl::minutes(5, 3).with_bonus(TimeBonus::SimpleDelay);
+        assert_eq!(delay.label(), "5+3 Simple delay");
+        assert_eq!(TimeControl::minutes(1, 0).pgn_tag(), "60");
+    }
+
+    #[test]
+    fn test_clock_runs_for_side_to_move() {
+        let start = Instant::now();
+        let mut clock = GameClock::new(TimeControl::minutes(1, 2));
+        assert_eq!(clock.remaining(PieceColor::White, at(start, 5000)), 60_000);
+
+        clock.start(PieceColor::White, start);
+        assert_eq!(clock.remaining(PieceColor::White, at(start, 5000)), 55_000);
+        assert_eq!(clock.remaining(PieceColor::Black, at(start, 5000)), 60_000);
+
+        // White moves after 5s and gets the increment; black's time starts
+        let reading = clock.press(at(start, 5000)).unwrap();
+        assert_eq!(reading.remaining_ms, 57_000);
+        assert_eq!(reading.elapsed_ms, Some(5000));
+        assert_eq!(clock.running_side(), Some(PieceColor::Black));
+        assert_eq!(clock.remaining(PieceColor::White, at(start, 9000)), 57_000);
+        assert_eq!(clock.remaining(PieceColor::Black, at(start, 9000)), 56_000);
+
+        clock.stop(at(start, 10_000));
+        assert_eq!(clock.running_side(), None);
+        assert_eq!(clock.press(at(start, 11_000)), None);
+        assert_eq!(
+            clock.remaining(PieceColor::Black, at(start, 60_000)),
+            55_000
+        );
+    }
+
+    #[test]
+    fn test_clock_delays() {
+        let start = Instant::now();
+
+        // Bronstein: the time used is given back, up to the delay
+        let control = TimeControl::minutes(1, 3).with_bonus(TimeBonus::BronsteinDelay);
+        let mut clock = GameClock::new(control);
+        clock.start(PieceColor::White, start);
+        assert_eq!(clock.press(at(start, 2000)).unwrap().remaining_ms, 60_000);
+        assert_eq!(clock.press(at(start, 7000)).unwrap().remaining_ms, 58_000);
+
+        // Simple delay: the clock only runs once the delay has passed
+        let control = TimeControl::minutes(1, 3).with_bonus(TimeBonus::SimpleDelay);
+        let mut clock = GameClock::new(control);
+        clock.start(PieceColor::White, start);
+        assert_eq!(clock.remaining(PieceColor::White, at(start, 2000)), 60_000);
+        assert_eq!(clock.remaining(PieceColor::White, at(start, 5000)), 58_000);
+        assert_eq!(clock.press(at(start, 5000)).unwrap().remaining_ms, 58_000);
+        assert_eq!(clock.go_params(at(start, 5000)).binc, Some(3000));
+    }
+
+    #[test]
+    fn test_clock_stages() {
+        let start = Instant::now();
+        let control = TimeControl::from_stages(vec![
+            TimeStage::minutes(Some(2), 1, 0),
+            TimeStage::minutes(None, 2, 5),
+        ]);
+        let mut clock = GameClock::new(control);
+        clock.start(PieceColor::White, start);
+        assert_eq!(clock.go_params(start).movestogo, Some(2));
+
+        // White's second move completes the first period and adds the second one
+        let now = play(&mut clock, start, &[1000, 1000]);
+        assert_eq!(clock.remaining(PieceColor::White, at(start, now)), 59_000);
+        assert_eq!(clock.go_params(at(start, now)).movestogo, Some(1));
+        let reading = clock.press(at(start, now + 1000)).unwrap();
+        assert_eq!(reading.remaining_ms, 58_000 + 120_000);
+
+        // The increment of the second period applies from then on
+        let now = play(&mut clock, at(start, now + 1000), &[1000, 1000]);
+        assert_eq!(
+            clock.remaining(PieceColor::White, at(start, now + 5000)),
+            178_000 - 1000 + 5000
+        );
+        assert_eq!(clock.go_params(at(start, now + 5000)).movestogo, None);
+    }
+
+    #[test]
+    fn test_repeating_stage() {
+        let start = Instant::now();
+        let control = TimeControl::from_stages(vec![TimeStage::minutes(Some(1), 1, 0)]);
+        let mut clock = GameClock::new(control);
+        clock.start(PieceColor::White, start);
+        // Every move completes the repeating one-move period
+        assert_eq!(clock.press(at(start, 1000)).unwrap().remaining_ms, 119_000);
+        clock.press(at(start, 2000));
+        assert_eq!(clock.press(at(start, 3000)).unwrap().remaining_ms, 178_000);
+    }
+
+    #[test]
+    fn test_clock_flag() {
+        let start = Instant::now();
+        let mut clock = GameClock::new(TimeControl::minutes(1, 5));
+        clock.start(PieceColor::Black, start);
+        assert_eq!(clock.flagged(at(start, 59_999)), None);
+        assert_eq!(clock.flagged(at(start, 61_000)), Some(PieceColor::Black));
+
+        // Moving after the flag fell doesn't bring the time back
+        clock.press(at(start, 61_000));
+        assert_eq!(clock.remaining(PieceColor::Black, at(start, 61_000)), 0);
+        assert_eq!(clock.flagged(at(start, 61_000)), Some(PieceColor::Black));
+    }
+
+    #[test]
+    fn test_clock_go_params() {
+        let start = Instant::now();
+        let mut clock = GameClock::new(TimeControl::minutes(3, 2));
+        clock.start(PieceColor::White, start);
+        let go = clock.go_params(at(start, 1500));
+        assert_eq!(go.wtime, Some(178_500));
+        assert_eq!(go.btime, Some(180_000));
+        assert_eq!(go.winc, Some(2000));
+        assert_eq!(go.binc, Some(2000));
+        assert_eq!(go.movestogo, None);
+        assert!(!go.infinite);
+
+        let go = GameClock::new(TimeControl::minutes(5, 0)).go_params(start);
+        assert_eq!((go.winc, go.binc), (None, None));
+    }
+
+    #[test]
+    fn test_format_clock() {
+        assert_eq!(format_clock(300_000), "5:00");
+        assert_eq!(format_clock(61_500), "1:01");
+        assert_eq!(format_clock(9_870), "0:09.8");
+        assert_eq!(format_clock(0), "0:00.0");
+        assert_eq!(format_clock(3_723_000), "1:02:03");
+    }
+}
@@ -0,0 +1,687 @@
+//! Engine-vs-engine matches: openings, the game schedule, playing out a game,
+//! and the statistics of the result (Elo difference and SPRT).
+//! Pure domain logic: the caller runs the engines and supplies the time.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use shakmaty::{Chess, Position};
+
+use super::pgn::{PgnError, parse_pgn_games, write_pgn};
+use super::uci::{AnalysisLimit, GoParams, UciCommand};
+use super::{
+    FenError, GameClock, GameHeaders, GameOutcome, GameResult, MoveTree, PieceColor, Termination,
+    TimeControl, history_outcome, parse_fen, position_to_fen, shakmaty_to_color, uci_line_to_san,
+};
+
+/// Two-sided normal quantile for 95% confidence
+const CONFIDENCE_95: f64 = 1.959964;
+
+/// Time an engine gets past its fixed move time before it is taken to have hung
+const MOVE_TIME_MARGIN: Duration = Duration::from_secs(5);
+
+/// Longest an engine may search a move with a fixed number of nodes
+const NODES_MOVE_DEADLINE: Duration = Duration::from_secs(60);
+
+/// How long the engines may think in a match game
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MatchLimit {
+    /// Both engines play on a game clock and lose if their time runs out
+    Clock(TimeControl),
+    /// A fixed time per move in milliseconds
+    MoveTime(u64),
+    /// A fixed number of nodes per move
+    Nodes(u64),
+}
+
+impl MatchLimit {
+    /// The limits offered when starting a match
+    pub fn presets() -> Vec<MatchLimit> {
+        vec![
+            MatchLimit::Clock(TimeControl::minutes(1, 0)),
+            MatchLimit::Clock(TimeControl::minutes(3, 2)),
+            MatchLimit::MoveTime(100),
+            MatchLimit::MoveTime(1_000),
+            MatchLimit::Nodes(10_000),
+            MatchLimit::Nodes(100_000),
+            MatchLimit::Nodes(1_000_000),
+        ]
+    }
+
+    /// How long an engine may take over a move before it forfeits the game.
+    /// None on a clock, where an engine that stops moving loses on time.
+    pub fn move_deadline(&self) -> Option<Duration> {
+        match self {
+            MatchLimit::Clock(_) => None,
+            MatchLimit::MoveTime(ms) => Some(Duration::from_millis(*ms) + MOVE_TIME_MARGIN),
+            MatchLimit::Nodes(_) => Some(NODES_MOVE_DEADLINE),
+        }
+    }
+
+    /// Short label for display (e.g., "3+2", "1 s", "100K nodes")
+    pub fn label(&self) -> String {
+        match self {
+            MatchLimit::Clock(time_control) => time_control.label(),
+            MatchLimit::MoveTime(ms) => AnalysisLimit::MoveTime(*ms).label(),
+            MatchLimit::Nodes(nodes) => AnalysisLimit::Nodes(*nodes).label(),
+        }
+    }
+}
+
+/// The settings of a match between two engines
+#[derive(Clone, PartialEq, Debug)]
+pub struct MatchSettings {
+    /// Most games to play
+    pub games: usize,
+    pub limit: MatchLimit,
+    /// Stop early once this test is decided
+    pub sprt: Option<Sprt>,
+}
+
+/// A starting point for match games: a position plus moves played from it
+#[derive(Clone, Debug)]
+pub struct Opening {
+    pub start: Chess,
+    /// Opening moves in UCI notation
+    pub moves: Vec<String>,
+}
+
+impl Default for Opening {
+    /// The standard starting position with no moves
+    fn default() -> Self {
+        Self {
+            start: Chess::default(),
+            moves: Vec::new(),
+        }
+    }
+}
+
+/// An error reading a list of openings
+#[derive(Clone, Debug, PartialEq)]
+pub enum OpeningsError {
+    /// A FEN or EPD line doesn't describe a valid position
+    InvalidFen { line: usize, error: FenError },
+    /// The openings look like PGN but can't be parsed
+    Pgn(PgnError),
+}
+
+impl fmt::Display for OpeningsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpeningsError::InvalidFen { line, error } => write!(f, "line {}: {}", line, error),
+            OpeningsError::Pgn(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for OpeningsError {}
+
+/// Parse a list of openings: either one FEN or EPD position per line, or PGN
+/// games whose main lines are played as the opening moves
+pub fn parse_openings(text: &str) -> Result<Vec<Opening>, OpeningsError> {
+    let is_position_list = text
+        .split_whitespace()
+        .next()
+        .is_some_and(|board| board.matches('/').count() == 7);
+    if !is_position_list {
+        let games = parse_pgn_games(text).map_err(OpeningsError::Pgn)?;
+        return Ok(games
+            .into_iter()
+            .map(|game| {
+                let tree = game.tree;
+                let end = tree.main_line().last().copied().unwrap_or(tree.root().id);
+                Opening {
+                    start: tree.root().position.clone(),
+                    moves: tree.uci_moves_to(end).unwrap_or_default(),
+                }
+            })
+            .collect());
+    }
+
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // EPD has no move counters, and may have operations after the 4 position fields
+            let has_counters = fields.len() >= 6
+                && fields[4].parse::<u32>().is_ok()
+                && fields[5].parse::<u32>().is_ok();
+            let fen = if has_counters {
+                fields[..6].join(" ")
+            } else {
+                format!("{} 0 1", fields[..fields.len().min(4)].join(" "))
+            };
+            parse_fen(&fen)
+                .map(|start| Opening {
+                    start,
+                    moves: Vec::new(),
+                })
+                .map_err(|error| OpeningsError::InvalidFen { line: i + 1, error })
+        })
+        .collect()
+}
+
+/// Get the opening for a game (0-based) and whether the first engine plays white.
+/// Each opening is played twice in a row, with the engines swapping colours.
+pub fn match_pairing(game_index: usize, opening_count: usize) -> (usize, bool) {
+    let opening = (game_index / 2) % opening_count.max(1);
+    (opening, game_index.is_multiple_of(2))
+}
+
+/// A game being played between two engines
+#[derive(Clone, Debug)]
+pub struct MatchGame {
+    /// The moves so far, including the opening moves
+    tree: MoveTree,
+    /// Every position of the game, for detecting repetitions
+    history: Vec<Chess>,
+    limit: MatchLimit,
+    /// The game clock, if the limit is a time control
+    clock: Option<GameClock>,
+    outcome: Option<GameOutcome>,
+}
+
+impl MatchGame {
+    /// Set up a game from an opening. The side to move's clock starts at `now`.
+    pub fn new(opening: &Opening, limit: MatchLimit, now: Instant) -> Self {
+        let mut tree = MoveTree::from_position(opening.start.clone());
+        let mut history = vec![opening.start.clone()];
+        for line_move in uci_line_to_san(&opening.start, &opening.moves) {
+            history.push(line_move.position.clone());
+            tree.add_move(line_move.position, line_move.san);
+        }
+        let clock = match &limit {
+            MatchLimit::Clock(time_control) => {
+                let mut clock = GameClock::new(time_control.clone());
+                clock.start(shakmaty_to_color(tree.current().position.turn()), now);
+                Some(clock)
+            }
+            MatchLimit::MoveTime(_) | MatchLimit::Nodes(_) => None,
+        };
+        let outcome = history_outcome(&history);
+        Self {
+            tree,
+            history,
+            limit,
+            clock,
+            outcome,
+        }
+    }
+
+    /// Get the current position
+    pub fn position(&self) -> &Chess {
+        &self.tree.current().position
+    }
+
+    /// Get the side to move
+    pub fn turn(&self) -> PieceColor {
+        shakmaty_to_color(self.position().turn())
+    }
+
+    /// Get the FEN of the current position
+    pub fn fen(&self) -> String {
+        position_to_fen(self.position())
+    }
+
+    /// Get the UCI `position` command for the current position, with the game's moves
+    pub fn uci_position(&self) -> UciCommand {
+        let (fen, moves) = self.tree.uci_history_to(self.tree.current_id());
+        UciCommand::Position { fen, moves }
+    }
+
+    /// `go` parameters for the side to move
+    pub fn go_params(&self, now: Instant) -> GoParams {
+        match (&self.limit, &self.clock) {
+            (_, Some(clock)) => clock.go_params(now),
+            (MatchLimit::MoveTime(ms), None) => AnalysisLimit::MoveTime(*ms).go_params(),
+            (MatchLimit::Nodes(nodes), None) => AnalysisLimit::Nodes(*nodes).go_params(),
+            (MatchLimit::Clock(_), None) => GoParams::default(),
+        }
+    }
+
+    /// Get the clock, if the game is played on time
+    pub fn clock(&self) -> Option<&GameClock> {
+        self.clock.as_ref()
+    }
+
+    /// Get how the game ended, if it is over
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        self.outcome
+    }
+
+    /// Play the side to move's move (in UCI notation). A move that isn't legal, or
+    /// one made after the flag fell, loses the game.
+    pub fn play(&mut self, uci: &str, now: Instant) {
+        if self.outcome.is_some() {
+            return;
+        }
+        let mover = self.turn();
+        if self.check_time(now).is_some() {
+            return;
+        }
+        let Some(line_move) = uci_line_to_san(self.position(), &[uci.to_string()]).pop() else {
+            self.outcome = Some(GameOutcome::forfeit(mover));
+            return;
+        };
+        let reading = self.clock.as_mut().and_then(|clock| clock.press(now));
+        self.history.push(line_move.position.clone());
+        let id = self.tree.add_move(line_move.position, line_move.san);
+        self.tree.set_clock(id, reading);
+        self.outcome = history_outcome(&self.history);
+    }
+
+    /// End the game if the side to move has run out of time
+    pub fn check_time(&mut self, now: Instant) -> Option<GameOutcome> {
+        if self.outcome.is_none()
+            && let Some(color) = self.clock.as_ref().and_then(|clock| clock.flagged(now))
+        {
+            self.outcome = Some(GameOutcome::time_forfeit(self.position(), color));
+            if let Some(clock) = self.clock.as_mut() {
+                clock.stop(now);
+            }
+        }
+        self.outcome
+    }
+
+    /// End the game if the side to move has searched since `search_started`
+    /// for longer than the limit allows, forfeiting it
+    pub fn check_move_deadline(
+        &mut self,
+        search_started: Instant,
+        now: Instant,
+    ) -> Option<GameOutcome> {
+        if self.outcome.is_none()
+            && let Some(deadline) = self.limit.move_deadline()
+            && now.saturating_duration_since(search_started) > deadline
+        {
+            self.outcome = Some(GameOutcome::forfeit(self.turn()));
+        }
+        self.outcome
+    }
+
+    /// End the game early, e.g. when an engine stops responding
+    pub fn finish(&mut self, outcome: GameOutcome) {
+        if self.outcome.is_none() {
+            self.outcome = Some(outcome);
+        }
+    }
+
+    /// Write the game as PGN, naming the engines and the round (1-based)
+    pub fn to_pgn(&self, white: &str, black: &str, round: usize) -> String {
+        let result = self
+            .outcome
+            .map_or("*", |outcome| outcome.result.pgn_token());
+        let mut headers = GameHeaders::new();
+        headers.set("Event", "Engine match");
+        headers.set("Round", &round.to_string());
+        headers.set("White", white);
+        headers.set("Black", black);
+        if let MatchLimit::Clock(time_control) = &self.limit {
+            headers.set("TimeControl", &time_control.pgn_tag());
+        }
+        if let Some(outcome) = self.outcome {
+            let termination = match outcome.termination {
+                Termination::TimeForfeit => "time forfeit",
+                Termination::Forfeit => "rules infraction",
+                _ => "normal",
+            };
+            headers.set("Termination", termination);
+        }
+        let headers = headers.pgn_tags(&self.tree, result);
+
+        write_pgn(&self.tree, &headers, result)
+    }
+}
+
+/// Games won, drawn and lost by the first engine of a match
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+/// An Elo difference with the half-width of its 95% confidence interval
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EloEstimate {
+    pub elo: f64,
+    /// Infinite while too few games have been played to bound the interval
+    pub margin: f64,
+}
+
+impl fmt::Display for EloEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.margin.is_finite() {
+            write!(f, "{:+.1} ± {:.1}", self.elo, self.margin)
+        } else {
+            write!(f, "{:+.1} ± ∞", self.elo)
+        }
+    }
+}
+
+impl MatchScore {
+    /// Count a finished game
+    pub fn record(&mut self, result: GameResult, first_engine_white: bool) {
+        let first_color = if first_engine_white {
+            PieceColor::White
+        } else {
+            PieceColor::Black
+        };
+        if result == GameResult::Draw {
+            self.draws += 1;
+        } else if result == GameResult::win_for(first_color) {
+            self.wins += 1;
+        } else {
+            self.losses += 1;
+        }
+    }
+
+    /// Get the number of games counted
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Get the first engine's score as a fraction of the points (draws count half)
+    pub fn score(&self) -> Option<f64> {
+        let games = self.games();
+        (games > 0).then(|| (self.wins as f64 + self.draws as f64 / 2.0) / games as f64)
+    }
+
+    /// Estimate how much stronger the first engine is, in Elo.
+    /// None until both engines have scored.
+    pub fn elo(&self) -> Option<EloEstimate> {
+        let mu = self.score()?;
+        if mu <= 0.0 || mu >= 1.0 {
+            return None;
+        }
+        let games = self.games() as f64;
+        let deviation = |points: f64, count: u32| count as f64 / games * (points - mu).powi(2);
+        let variance =
+            deviation(1.0, self.wins) + deviation(0.5, self.draws) + deviation(0.0, self.losses);
+        let stdev = (variance / games).sqrt();
+        let low = mu - CONFIDENCE_95 * stdev;
+        let high = mu + CONFIDENCE_95 * stdev;
+        let margin = if low > 0.0 && high < 1.0 {
+            (elo_from_score(high) - elo_from_score(low)) / 2.0
+        } else {
+            f64::INFINITY
+        };
+        Some(EloEstimate {
+            elo: elo_from_score(mu),
+            margin,
+        })
+    }
+}
+
+/// The Elo difference expected to produce a score (a fraction between 0 and 1)
+fn elo_from_score(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// The score expected from an Elo difference
+fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// A sequential probability ratio test of whether the first engine is stronger:
+/// H0 says it is `elo0` Elo stronger, H1 that it is `elo1` Elo stronger
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// Chance of accepting H1 when H0 is true
+    pub alpha: f64,
+    /// Chance of accepting H0 when H1 is true
+    pub beta: f64,
+}
+
+/// What an SPRT concludes from the games so far
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SprtVerdict {
+    /// Not enough evidence yet
+    Continue,
+    AcceptH0,
+    AcceptH1,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Self {
+            elo0: 0.0,
+            elo1: 5.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+impl Sprt {
+    /// Get the log-likelihood ratio of H1 against H0 for a score, using the
+    /// normal approximation of the game results
+    pub fn llr(&self, score: &MatchScore) -> f64 {
+        let games = score.games() as f64;
+        let Some(mu) = score.score() else {
+            return 0.0;
+        };
+        let win_rate = score.wins as f64 / games;
+        let draw_rate = score.draws as f64 / games;
+        let variance = win_rate + draw_rate / 4.0 - mu * mu;
+        if variance <= 0.0 {
+            return 0.0;
+        }
+        let s0 = score_from_elo(self.elo0);
+        let s1 = score_from_elo(self.elo1);
+        (s1 - s0) * (2.0 * mu - s0 - s1) / (2.0 * variance / games)
+    }
+
+    /// Get the LLR below which H0 is accepted and above which H1 is accepted
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Decide the test for a score
+    pub fn verdict(&self, score: &MatchScore) -> SprtVerdict {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            SprtVerdict::AcceptH0
+        } else if llr >= upper {
+            SprtVerdict::AcceptH1
+        } else {
+            SprtVerdict::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_position_openings() {
+        let text = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1\n\n\
+                    rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - bm Nf3; id \"e4e5\";\n";
+        let openings = parse_openings(text).unwrap();
+        assert_eq!(openings.len(), 2);
+        assert!(openings.iter().all(|opening| opening.moves.is_empty()));
+        assert_eq!(
+            position_to_fen(&openings[1].start),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1"
+        );
+
+        let bad = parse_openings(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\n8/8/8/8/8/8/8/8 w - -",
+        );
+        assert!(matches!(
+            bad,
+            Err(OpeningsError::InvalidFen { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_pgn_openings() {
+        let text = "[Event \"Ruy Lopez\"]\n\n1. e4 e5 2. Nf3 Nc6 3. Bb5 *\n\n\
+                    [Event \"Sicilian\"]\n\n1. e4 c5 (1... e5) *\n";
+        let openings = parse_openings(text).unwrap();
+        assert_eq!(openings.len(), 2);
+        assert_eq!(openings[0].moves, ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5"]);
+        assert_eq!(openings[1].moves, ["e2e4", "c7c5"]);
+    }
+
+    #[test]
+    fn test_match_pairing() {
+        assert_eq!(match_pairing(0, 2), (0, true));
+        assert_eq!(match_pairing(1, 2), (0, false));
+        assert_eq!(match_pairing(2, 2), (1, true));
+        assert_eq!(match_pairing(4, 2), (0, true));
+        assert_eq!(match_pairing(3, 0), (0, false));
+    }
+
+    #[test]
+    fn test_match_game() {
+        let now = Instant::now();
+        let opening = Opening {
+            start: Chess::default(),
+            moves: vec!["f2f3".to_string(), "e7e5".to_string()],
+        };
+        let mut game = MatchGame::new(&opening, MatchLimit::Nodes(1000), now);
+        assert_eq!(game.turn(), PieceColor::White);
+        assert_eq!(game.go_params(now).nodes, Some(1000));
+        let UciCommand::Position { fen, moves } = game.uci_position() else {
+            panic!("expected a position command");
+        };
+        assert_eq!(
+            (fen, moves),
+            (None, vec!["f2f3".to_string(), "e7e5".to_string()])
+        );
+
+        game.play("g2g4", now);
+        game.play("d8h4", now);
+        let outcome = game.outcome().unwrap();
+        assert_eq!(outcome.result, GameResult::BlackWins);
+        assert_eq!(outcome.termination, Termination::Checkmate);
+
+        let pgn = game.to_pgn("Engine A", "Engine B", 3);
+        assert!(pgn.contains("[Round \"3\"]"));
+        assert!(pgn.contains("[White \"Engine A\"]"));
+        assert!(pgn.contains("[Termination \"normal\"]"));
+        assert!(pgn.contains("1. f3 e5 2. g4 Qh4# 0-1"));
+    }
+
+    #[test]
+    fn test_match_game_forfeits() {
+        let start = Instant::now();
+        let limit = MatchLimit::Clock(TimeControl::minutes(1, 0));
+        let mut game = MatchGame::new(&Opening::default(), limit.clone(), start);
+        assert_eq!(game.go_params(start).wtime, Some(60_000));
+
+        game.play("e2e5", start);
+        let outcome = game.outcome().unwrap();
+        assert_eq!(outcome, GameOutcome::forfeit(PieceColor::White));
+
+        let mut game = MatchGame::new(&Opening::default(), limit.clone(), start);
+        // On a clock an engine only loses on time
+        assert_eq!(
+            game.check_move_deadline(start, start + Duration::from_secs(61)),
+            None
+        );
+        game.play("e2e4", start + Duration::from_secs(1));
+        let outcome = game.check_time(start + Duration::from_secs(62)).unwrap();
+        assert_eq!(outcome.termination, Termination::TimeForfeit);
+        assert_eq!(outcome.result, GameResult::WhiteWins);
+        let pgn = game.to_pgn("A", "B", 1);
+        assert!(pgn.contains("[TimeControl \"60\"]"));
+        assert!(pgn.contains("[Termination \"time forfeit\"]"));
+    }
+
+    #[test]
+    fn test_match_game_move_deadline() {
+        let start = Instant::now();
+        let mut game = MatchGame::new(&Opening::default(), MatchLimit::MoveTime(1_000), start);
+        assert_eq!(
+            game.check_move_deadline(start, start + Duration::from_secs(5)),
+            None
+        );
+        let outcome = game
+            .check_move_deadline(start, start + Duration::from_secs(7))
+            .unwrap();
+        assert_eq!(outcome, GameOutcome::forfeit(PieceColor::White));
+
+        let mut game = MatchGame::new(&Opening::default(), MatchLimit::Nodes(1_000), start);
+        game.play("e2e4", start);
+        let later = start + Duration::from_secs(61);
+        assert_eq!(
+            game.check_move_deadline(start, later),
+            Some(GameOutcome::forfeit(PieceColor::Black))
+        );
+    }
+
+    #[test]
+    fn test_match_score_elo() {
+        let mut score = MatchScore::default();
+        assert_eq!(score.elo(), None);
+        score.record(GameResult::WhiteWins, true);
+        score.record(GameResult::WhiteWins, false);
+        score.record(GameResult::Draw, true);
+        assert_eq!(
+            score,
+            MatchScore {
+                wins: 1,
+                draws: 1,
+                losses: 1
+            }
+        );
+
+        let score = MatchScore {
+            wins: 60,
+            draws: 20,
+            losses: 20,
+        };
+        let estimate = score.elo().unwrap();
+        assert!((estimate.elo - 147.19).abs() < 0.01);
+        assert!(estimate.margin > 50.0 && estimate.margin < 100.0);
+
+        let all_wins = MatchScore {
+            wins: 5,
+            draws: 0,
+            losses: 0,
+        };
+        assert_eq!(all_wins.elo(), None);
+    }
+
+    #[test]
+    fn test_sprt() {
+        let sprt = Sprt::default();
+        let (lower, upper) = sprt.bounds();
+        assert!((lower + 2.944).abs() < 0.001);
+        assert!((upper - 2.944).abs() < 0.001);
+
+        let stronger = MatchScore {
+            wins: 300,
+            draws: 100,
+            losses: 100,
+        };
+        assert!((sprt.llr(&stronger) - 4.41).abs() < 0.01);
+        assert_eq!(sprt.verdict(&stronger), SprtVerdict::AcceptH1);
+
+        let even = MatchScore {
+            wins: 100,
+            draws: 100,
+            losses: 100,
+        };
+        assert_eq!(sprt.verdict(&even), SprtVerdict::Continue);
+
+        let equal = MatchScore {
+            wins: 10_000,
+            draws: 10_000,
+            losses: 10_000,
+        };
+        assert_eq!(sprt.verdict(&equal), SprtVerdict::AcceptH0);
+        assert_eq!(sprt.llr(&MatchScore::default()), 0.0);
+    }
+}
@@ -0,0 +1,360 @@
+//! Engine profiles - the installed UCI engines and how to launch them.
+//!
+//! The registry is stored as JSON in the user's config directory; this module
+//! only handles the data and its (de)serialization, not the file itself.
+//! This is a pure domain module with no GPUI dependencies.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// A UCI option value sent to the engine with `setoption` after the handshake
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OptionSetting {
+    pub name: String,
+    pub value: String,
+}
+
+/// How to launch one UCI engine
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineProfile {
+    /// Display name, unique within the registry
+    pub name: String,
+    /// Engine executable (a bare name is looked up on PATH)
+    pub path: PathBuf,
+    /// Command line arguments
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Directory to run the engine in (defaults to the app's working directory)
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+    /// Options applied every time the engine is started, in order
+    #[serde(default)]
+    pub options: Vec<OptionSetting>,
+}
+
+impl EngineProfile {
+    /// A profile with just a name and an executable
+    pub fn new(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            path: path.into(),
+            args: Vec::new(),
+            working_dir: None,
+            options: Vec::new(),
+        }
+    }
+
+    /// The value this profile sets an option to (option names are case-insensitive)
+    pub fn option_value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+            .map(|option| option.value.as_str())
+    }
+
+    /// Set an option value, replacing any earlier value for the same option
+    pub fn set_option(&mut self, name: &str, value: &str) {
+        match self
+            .options
+            .iter_mut()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+        {
+            Some(option) => option.value = value.to_string(),
+            None => self.options.push(OptionSetting {
+                name: name.to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
+/// Error returned when the registry or a new engine profile is invalid
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EngineRegistryError {
+    /// The config file couldn't be parsed
+    InvalidConfig(String),
+    /// An engine was given no name
+    EmptyName,
+    /// An engine with this name already exists
+    DuplicateName(String),
+    /// An engine was given no executable
+    EmptyPath,
+    /// An option setting is not of the form `Name=value`
+    InvalidOption(String),
+}
+
+impl fmt::Display for EngineRegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineRegistryError::InvalidConfig(e) => write!(f, "Invalid engine config: {}", e),
+            EngineRegistryError::EmptyName => write!(f, "Engine name is empty"),
+            EngineRegistryError::DuplicateName(name) => {
+                write!(f, "An engine named \"{}\" already exists", name)
+            }
+            EngineRegistryError::EmptyPath => write!(f, "Engine path is empty"),
+            EngineRegistryError::InvalidOption(option) => {
+                write!(f, "Invalid option \"{}\" (expected Name=value)", option)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EngineRegistryError {}
+
+/// The list of installed engines and which one is used for analysis
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineRegistry {
+    #[serde(default)]
+    engines: Vec<EngineProfile>,
+    /// Name of the selected engine (the first engine if unset)
+    #[serde(default)]
+    selected: Option<String>,
+}
+
+impl EngineRegistry {
+    /// Parse a registry from its JSON config
+    pub fn from_json(json: &str) -> Result<Self, EngineRegistryError> {
+        serde_json::from_str(json).map_err(|e| EngineRegistryError::InvalidConfig(e.to_string()))
+    }
+
+    /// Serialize the registry as JSON for the config file
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("engine registry serializes to JSON")
+    }
+
+    /// All installed engines, in the order they were added
+    pub fn engines(&self) -> &[EngineProfile] {
+        &self.engines
+    }
+
+    /// Look up an engine by name
+    pub fn get(&self, name: &str) -> Option<&EngineProfile> {
+        self.engines.iter().find(|engine| engine.name == name)
+    }
+
+    /// The engine to use for analysis
+    pub fn selected(&self) -> Option<&EngineProfile> {
+        self.selected
+            .as_deref()
+            .and_then(|name| self.get(name))
+            .or_else(|| self.engines.first())
+    }
+
+    /// Select an engine by name. Returns false if there is no such engine.
+    pub fn select(&mut self, name: &str) -> bool {
+        if self.get(name).is_none() {
+            return false;
+        }
+        self.selected = Some(name.to_string());
+        true
+    }
+
+    /// Set an option value on an engine's profile. Returns false if there is no such engine.
+    pub fn set_option(&mut self, engine: &str, name: &str, value: &str) -> bool {
+        match self.engines.iter_mut().find(|e| e.name == engine) {
+            Some(profile) => {
+                profile.set_option(name, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add an engine and select it
+    pub fn add(&mut self, profile: EngineProfile) -> Result<(), EngineRegistryError> {
+        if profile.name.trim().is_empty() {
+            return Err(EngineRegistryError::EmptyName);
+        }
+        if profile.path.as_os_str().is_empty() {
+            return Err(EngineRegistryError::EmptyPath);
+        }
+        if self.get(&profile.name).is_some() {
+            return Err(EngineRegistryError::DuplicateName(profile.name));
+        }
+        self.selected = Some(profile.name.clone());
+        self.engines.push(profile);
+        Ok(())
+    }
+}
+
+impl Default for EngineRegistry {
+    /// Stockfish from PATH, used until the user configures their own engines
+    fn default() -> Self {
+        Self {
+            engines: vec![EngineProfile::new("Stockfish", "stockfish")],
+            selected: None,
+        }
+    }
+}
+
+/// Split a command line argument string on whitespace. Double or single quotes
+/// keep spaces within an argument (e.g. `--weights="My Nets/t1.pb"`).
+pub fn parse_engine_args(args: &str) -> Vec<String> {
+    let mut parsed = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote = None;
+    for c in args.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.get_or_insert_default().push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                current.get_or_insert_default();
+            }
+            None if c.is_whitespace() => parsed.extend(current.take()),
+            None => current.get_or_insert_default().push(c),
+        }
+    }
+    parsed.extend(current);
+    parsed
+}
+
+/// Parse a comma-separated list of `Name=value` option settings
+pub fn parse_option_settings(settings: &str) -> Result<Vec<OptionSetting>, EngineRegistryError> {
+    settings
+        .split(',')
+        .map(str::trim)
+        .filter(|setting| !setting.is_empty())
+        .map(|setting| {
+            let (name, value) = setting
+                .split_once('=')
+                .filter(|(name, _)| !name.trim().is_empty())
+                .ok_or_else(|| EngineRegistryError::InvalidOption(setting.to_string()))?;
+            Ok(OptionSetting {
+                name: name.trim().to_string(),
+                value: value.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_registry_uses_stockfish_from_path() {
+        let registry = EngineRegistry::default();
+        let engine = registry.selected().unwrap();
+        assert_eq!(engine.name, "Stockfish");
+        assert_eq!(engine.path, PathBuf::from("stockfish"));
+    }
+
+    #[test]
+    fn test_add_and_select() {
+        let mut registry = EngineRegistry::from_json("{}").unwrap();
+        assert!(registry.selected().is_none());
+
+        registry
+            .add(EngineProfile::new("Stockfish", "/usr/bin/stockfish"))
+            .unwrap();
+        registry
+            .add(EngineProfile::new("Lc0", "/usr/bin/lc0"))
+            .unwrap();
+        assert_eq!(registry.selected().unwrap().name, "Lc0");
+
+        assert!(registry.select("Stockfish"));
+        assert_eq!(registry.selected().unwrap().name, "Stockfish");
+        assert!(!registry.select("Komodo"));
+        assert_eq!(registry.selected().unwrap().name, "Stockfish");
+    }
+
+    #[test]
+    fn test_add_rejects_invalid_profiles() {
+        let mut registry = EngineRegistry::default();
+        assert_eq!(
+            registry.add(EngineProfile::new("Stockfish", "/usr/bin/stockfish")),
+            Err(EngineRegistryError::DuplicateName("Stockfish".to_string()))
+        );
+        assert_eq!(
+            registry.add(EngineProfile::new(" ", "/usr/bin/lc0")),
+            Err(EngineRegistryError::EmptyName)
+        );
+        assert_eq!(
+            registry.add(EngineProfile::new("Lc0", "")),
+            Err(EngineRegistryError::EmptyPath)
+        );
+        assert_eq!(registry.engines().len(), 1);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut registry = EngineRegistry::default();
+        let mut profile = EngineProfile::new("Lc0", "/opt/lc0/lc0");
+        profile.args = vec!["--backend=cuda".to_string()];
+        profile.working_dir = Some(PathBuf::from("/opt/lc0"));
+        profile.options = parse_option_settings("Threads=2").unwrap();
+        registry.add(profile).unwrap();
+
+        let parsed = EngineRegistry::from_json(&registry.to_json()).unwrap();
+        assert_eq!(parsed, registry);
+    }
+
+    #[test]
+    fn test_from_json_fills_in_missing_fields() {
+        let json = r#"{"engines": [{"name": "Stockfish", "path": "stockfish"}]}"#;
+        let registry = EngineRegistry::from_json(json).unwrap();
+        let engine = registry.selected().unwrap();
+        assert!(engine.args.is_empty());
+        assert!(engine.working_dir.is_none());
+        assert!(engine.options.is_empty());
+
+        assert!(matches!(
+            EngineRegistry::from_json("not json"),
+            Err(EngineRegistryError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_set_option_replaces_earlier_value() {
+        let mut registry = EngineRegistry::default();
+        assert!(registry.set_option("Stockfish", "Hash", "64"));
+        assert!(registry.set_option("Stockfish", "hash", "128"));
+        assert!(registry.set_option("Stockfish", "Threads", "4"));
+        assert!(!registry.set_option("Lc0", "Threads", "4"));
+
+        let profile = registry.get("Stockfish").unwrap();
+        assert_eq!(profile.options.len(), 2);
+        assert_eq!(profile.option_value("HASH"), Some("128"));
+        assert_eq!(profile.option_value("Threads"), Some("4"));
+        assert_eq!(profile.option_value("MultiPV"), None);
+    }
+
+    #[test]
+    fn test_parse_option_settings() {
+        let options = parse_option_settings(" Hash = 256, SyzygyPath=/tb ,").unwrap();
+        assert_eq!(
+            options,
+            vec![
+                OptionSetting {
+                    name: "Hash".to_string(),
+                    value: "256".to_string(),
+                },
+                OptionSetting {
+                    name: "SyzygyPath".to_string(),
+                    value: "/tb".to_string(),
+                },
+            ]
+        );
+        assert!(parse_option_settings("").unwrap().is_empty());
+        assert_eq!(
+            parse_option_settings("Hash"),
+            Err(EngineRegistryError::InvalidOption("Hash".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_engine_args() {
+        assert_eq!(
+            parse_engine_args(" --weights x.pb  -v "),
+            vec!["--weights", "x.pb", "-v"]
+        );
+        assert_eq!(
+            parse_engine_args(r#"--weights="My Nets/t1.pb" 'two words' """#),
+            vec!["--weights=My Nets/t1.pb", "two words", ""]
+        );
+        assert!(parse_engine_args("  ").is_empty());
+    }
+}
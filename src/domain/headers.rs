@@ -0,0 +1,234 @@
+//! PGN tag pairs of a game: the Seven Tag Roster plus any other tags.
+//!
+//! This is a pure domain module with no GPUI dependencies.
+
+use super::MoveTree;
+
+/// The Seven Tag Roster, in the order PGN export writes it
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// Common optional tags offered when editing a game's headers
+pub const COMMON_TAGS: [&str; 2] = ["ECO", "TimeControl"];
+
+/// Tags describing the starting position. They follow the move tree's root,
+/// so they are written on export rather than stored.
+const SETUP_TAGS: [&str; 2] = ["SetUp", "FEN"];
+
+/// The metadata of a game as PGN tag pairs.
+/// The Seven Tag Roster is always present (unknown values are "?"), followed by
+/// any other tags in the order they were added.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GameHeaders {
+    tags: Vec<(String, String)>,
+}
+
+impl GameHeaders {
+    /// Headers with every roster tag unknown
+    pub fn new() -> Self {
+        Self {
+            tags: SEVEN_TAG_ROSTER
+                .iter()
+                .map(|name| (name.to_string(), unknown_value(name).to_string()))
+                .collect(),
+        }
+    }
+
+    /// Headers read from a PGN game's tag pairs. Roster tags are put first;
+    /// the starting position tags are left out.
+    pub fn from_tags(tags: &[(String, String)]) -> Self {
+        let mut headers = Self::new();
+        for (name, value) in tags {
+            if !SETUP_TAGS.contains(&name.as_str()) {
+                headers.set(name, value);
+            }
+        }
+        headers
+    }
+
+    /// Get a tag's value
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Set a tag, adding it after the others if it is new. A blank value
+    /// removes the tag, or makes a roster tag unknown.
+    pub fn set(&mut self, name: &str, value: &str) {
+        let name = name.trim();
+        let value = value.trim();
+        if !is_valid_tag_name(name) {
+            return;
+        }
+        if value.is_empty() {
+            self.remove(name);
+            return;
+        }
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Remove a tag. Roster tags can't be removed and become unknown instead.
+    pub fn remove(&mut self, name: &str) {
+        if SEVEN_TAG_ROSTER.contains(&name) {
+            self.set(name, unknown_value(name));
+        } else {
+            self.tags.retain(|(tag, _)| tag != name);
+        }
+    }
+
+    /// Get the tags that are not in the Seven Tag Roster
+    pub fn extra_tags(&self) -> impl Iterator<Item = &(String, String)> {
+        self.tags
+            .iter()
+            .filter(|(name, _)| !SEVEN_TAG_ROSTER.contains(&name.as_str()))
+    }
+
+    /// Get the tag pairs to export for a game: these tags with the given result,
+    /// then SetUp/FEN if the game doesn't start from the standard position
+    pub fn pgn_tags(&self, tree: &MoveTree, result: &str) -> Vec<(String, String)> {
+        let mut tags: Vec<(String, String)> = self
+            .tags
+            .iter()
+            .map(|(name, value)| match name.as_str() {
+                "Result" => (name.clone(), result.to_string()),
+                _ => (name.clone(), value.clone()),
+            })
+            .collect();
+        if let Some(fen) = tree.setup_fen() {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), fen));
+        }
+        tags
+    }
+
+    /// Get a tag's value, or None if it is missing or unknown ("?")
+    pub fn known(&self, name: &str) -> Option<&str> {
+        self.get(name)
+            .filter(|value| !value.is_empty() && *value != unknown_value(name))
+    }
+}
+
+impl Default for GameHeaders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a tag name is valid in PGN: letters, digits and underscores,
+/// starting with a letter or digit
+pub fn is_valid_tag_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The value of a roster tag when it is not known
+fn unknown_value(name: &str) -> &'static str {
+    match name {
+        "Date" => "????.??.??",
+        "Result" => "*",
+        _ => "?",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::pgn::{parse_pgn, write_pgn};
+
+    fn tags(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_new_has_unknown_roster() {
+        let headers = GameHeaders::new();
+        let names: Vec<&str> = headers.tags.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, SEVEN_TAG_ROSTER);
+        assert_eq!(headers.get("Date"), Some("????.??.??"));
+        assert_eq!(headers.get("Result"), Some("*"));
+        assert_eq!(headers.known("White"), None);
+        assert_eq!(headers.extra_tags().count(), 0);
+    }
+
+    #[test]
+    fn test_from_tags_orders_roster_first() {
+        let headers = GameHeaders::from_tags(&tags(&[
+            ("ECO", "C60"),
+            ("White", "Morphy"),
+            ("SetUp", "1"),
+            ("FEN", "8/8/8/8/8/8/8/8 w - - 0 1"),
+            ("Event", "Paris"),
+        ]));
+        assert_eq!(
+            headers.tags,
+            tags(&[
+                ("Event", "Paris"),
+                ("Site", "?"),
+                ("Date", "????.??.??"),
+                ("Round", "?"),
+                ("White", "Morphy"),
+                ("Black", "?"),
+                ("Result", "*"),
+                ("ECO", "C60"),
+            ])
+        );
+        assert_eq!(headers.known("White"), Some("Morphy"));
+    }
+
+    #[test]
+    fn test_set_and_remove() {
+        let mut headers = GameHeaders::new();
+        headers.set("Annotator", " Steinitz ");
+        headers.set("White", "Anderssen");
+        assert_eq!(headers.get("Annotator"), Some("Steinitz"));
+        assert_eq!(headers.get("White"), Some("Anderssen"));
+
+        // Blank values remove tags; roster tags become unknown
+        headers.set("Annotator", "");
+        headers.set("White", " ");
+        assert_eq!(headers.get("Annotator"), None);
+        assert_eq!(headers.get("White"), Some("?"));
+        headers.remove("Date");
+        assert_eq!(headers.get("Date"), Some("????.??.??"));
+
+        // Invalid names are ignored
+        headers.set("Bad Name", "x");
+        headers.set("", "x");
+        assert_eq!(headers.tags.len(), SEVEN_TAG_ROSTER.len());
+    }
+
+    #[test]
+    fn test_pgn_round_trip() {
+        let pgn = "[Event \"Casual\"]\n[White \"Morphy\"]\n[Result \"1-0\"]\n[ECO \"C41\"]\n\
+                   [SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e4 1-0";
+        let game = parse_pgn(pgn).unwrap();
+        let headers = GameHeaders::from_tags(&game.headers);
+        let result = game.result.as_deref().unwrap();
+        let written = write_pgn(&game.tree, &headers.pgn_tags(&game.tree, result), result);
+
+        let reparsed = parse_pgn(&written).unwrap();
+        assert_eq!(reparsed.result.as_deref(), Some("1-0"));
+        assert_eq!(GameHeaders::from_tags(&reparsed.headers), headers);
+        assert_eq!(reparsed.tree.setup_fen(), game.tree.setup_fen());
+        assert!(written.contains("[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]"));
+    }
+
+    #[test]
+    fn test_valid_tag_names() {
+        assert!(is_valid_tag_name("WhiteElo"));
+        assert!(is_valid_tag_name("Time_Control2"));
+        assert!(!is_valid_tag_name("_Hidden"));
+        assert!(!is_valid_tag_name("Two words"));
+        assert!(!is_valid_tag_name(""));
+    }
+}
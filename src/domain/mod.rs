@@ -1,6 +1,22 @@
 mod chess;
+mod clock;
+mod engine_match;
+mod engine_profile;
+mod headers;
 mod move_tree;
+mod nag;
+mod outcome;
+mod setup;
+pub mod pgn;
 pub mod uci;
+pub mod uci_session;
 
 pub use chess::*;
+pub use clock::*;
+pub use engine_match::*;
+pub use engine_profile::*;
+pub use headers::*;
 pub use move_tree::*;
+pub use nag::*;
+pub use outcome::*;
+pub use setup::*;
@@ -2,7 +2,10 @@
 //!
 //! This is a pure domain module with no GPUI dependencies.
 
-use shakmaty::Chess;
+use shakmaty::san::San;
+use shakmaty::{CastlingMode, Chess, Color as SColor, Position};
+
+use super::{FenError, MoveClock, nags_conflict, parse_fen, position_to_fen};
 
 /// Unique identifier for a node in the move tree
 pub type MoveNodeId = usize;
@@ -20,17 +23,29 @@ pub struct MoveNode {
     pub san: Option<String>,
     /// Child node IDs - first child is the main line continuation, rest are variations
     pub children: Vec<MoveNodeId>,
+    /// Text comment shown before the move (e.g. introducing a variation)
+    pub pre_comment: Option<String>,
+    /// Text comment following the move (on the root: a comment on the whole game)
+    pub comment: Option<String>,
+    /// Numeric Annotation Glyphs attached to the move (e.g. 1 = "!", 2 = "?")
+    pub nags: Vec<u8>,
+    /// The mover's clock after the move, if the game was played with a clock
+    pub clock: Option<MoveClock>,
 }
 
 impl MoveNode {
-    /// Create a new root node with the starting position
-    pub fn root() -> Self {
+    /// Create a new root node with the given starting position
+    pub fn root(position: Chess) -> Self {
         Self {
             id: 0,
             parent_id: None,
-            position: Chess::default(),
+            position,
             san: None,
             children: Vec::new(),
+            pre_comment: None,
+            comment: None,
+            nags: Vec::new(),
+            clock: None,
         }
     }
 
@@ -42,6 +57,10 @@ impl MoveNode {
             position,
             san: Some(san),
             children: Vec::new(),
+            pre_comment: None,
+            comment: None,
+            nags: Vec::new(),
+            clock: None,
         }
     }
 
@@ -73,15 +92,23 @@ impl MoveNode {
 
     /// Get the move number (1-based, for display)
     /// Returns (move_number, is_black_move)
+    ///
+    /// Numbering continues from the root position's fullmove number and side to move,
+    /// so a game started from a FEN with black to move begins with "n...".
     pub fn move_number(&self, tree: &MoveTree) -> (usize, bool) {
         let ply = self.ply(tree);
         if ply == 0 {
-            (0, false) // Root has no move number
-        } else {
-            let move_num = ply.div_ceil(2);
-            let is_black = ply % 2 == 0; // ply 2, 4, 6... are black's moves
-            (move_num, is_black)
+            return (0, false); // Root has no move number
         }
+
+        let root = &tree.root().position;
+        let first_move_num = root.fullmoves().get() as usize;
+        // Half-moves since white's move of the root's fullmove number
+        let offset = if root.turn() == SColor::Black { 1 } else { 0 };
+        let half_moves = ply - 1 + offset;
+        let move_num = first_move_num + half_moves / 2;
+        let is_black = !half_moves.is_multiple_of(2);
+        (move_num, is_black)
     }
 }
 
@@ -97,12 +124,34 @@ pub struct MoveTree {
 impl MoveTree {
     /// Create a new tree with just the root (starting position)
     pub fn new() -> Self {
+        Self::from_position(Chess::default())
+    }
+
+    /// Create a new tree rooted at an arbitrary position
+    pub fn from_position(position: Chess) -> Self {
         Self {
-            nodes: vec![MoveNode::root()],
+            nodes: vec![MoveNode::root(position)],
             current_id: 0,
         }
     }
 
+    /// Create a new tree rooted at the position described by a FEN
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        parse_fen(fen).map(Self::from_position)
+    }
+
+    /// Get the root node (the starting position)
+    pub fn root(&self) -> &MoveNode {
+        &self.nodes[0]
+    }
+
+    /// Get the FEN of the starting position, or None if it is the standard one.
+    /// FENs are compared so non-standard move counters count as a different start.
+    pub fn setup_fen(&self) -> Option<String> {
+        let fen = position_to_fen(&self.root().position);
+        (fen != position_to_fen(&Chess::default())).then_some(fen)
+    }
+
     /// Get a node by ID
     pub fn get(&self, id: MoveNodeId) -> Option<&MoveNode> {
         self.nodes.get(id)
@@ -177,12 +226,12 @@ impl MoveTree {
         // Check if this move already exists as a child
         let current = &self.nodes[self.current_id];
         for &child_id in &current.children {
-            if let Some(child) = self.nodes.get(child_id) {
-                if child.san.as_ref() == Some(&san) {
-                    // Move already exists, navigate to it
-                    self.current_id = child_id;
-                    return child_id;
-                }
+            if let Some(child) = self.nodes.get(child_id)
+                && child.san.as_ref() == Some(&san)
+            {
+                // Move already exists, navigate to it
+                self.current_id = child_id;
+                return child_id;
             }
         }
 
@@ -199,6 +248,83 @@ impl MoveTree {
         new_id
     }
 
+    /// Set (or clear) the comment on a node. Returns false if the node doesn't exist.
+    pub fn set_comment(&mut self, id: MoveNodeId, comment: Option<String>) -> bool {
+        match self.nodes.get_mut(id) {
+            Some(node) => {
+                node.comment = comment;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set (or clear) the comment shown before a node's move. Only a move that
+    /// starts a variation can have one: anywhere else PGN reads it as a comment
+    /// after the previous move. Returns false if the comment can't be set.
+    pub fn set_pre_comment(&mut self, id: MoveNodeId, comment: Option<String>) -> bool {
+        if comment.is_some() && !self.is_variation_start(id) {
+            return false;
+        }
+        match self.nodes.get_mut(id) {
+            Some(node) => {
+                node.pre_comment = comment;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check if a node's move is an alternative to its parent's main line move
+    pub fn is_variation_start(&self, id: MoveNodeId) -> bool {
+        self.get(id)
+            .and_then(|node| node.parent_id)
+            .is_some_and(|parent_id| self.nodes[parent_id].main_line_child() != Some(id))
+    }
+
+    /// Set (or clear) the clock reading after a node's move.
+    /// Returns false if the node doesn't exist.
+    pub fn set_clock(&mut self, id: MoveNodeId, clock: Option<MoveClock>) -> bool {
+        match self.nodes.get_mut(id) {
+            Some(node) => {
+                node.clock = clock;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Attach a NAG to a node (ignored if already present).
+    /// Returns false if the node doesn't exist.
+    pub fn add_nag(&mut self, id: MoveNodeId, nag: u8) -> bool {
+        match self.nodes.get_mut(id) {
+            Some(node) => {
+                if !node.nags.contains(&nag) {
+                    node.nags.push(nag);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Toggle a NAG on a node. Adding a NAG replaces any conflicting one
+    /// (e.g. "!" replaces "?"). Returns false if the node doesn't exist.
+    pub fn toggle_nag(&mut self, id: MoveNodeId, nag: u8) -> bool {
+        match self.nodes.get_mut(id) {
+            Some(node) => {
+                if node.nags.contains(&nag) {
+                    node.nags.retain(|&n| n != nag);
+                } else {
+                    node.nags.retain(|&n| !nags_conflict(n, nag));
+                    node.nags.push(nag);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Get the main line as a sequence of node IDs (from root to end)
     pub fn main_line(&self) -> Vec<MoveNodeId> {
         let mut line = vec![0]; // Start with root
@@ -210,6 +336,50 @@ impl MoveTree {
         line
     }
 
+    /// Get the path from the root to a node as a sequence of node IDs (root first).
+    /// Returns an empty path if the node doesn't exist.
+    pub fn path_to(&self, id: MoveNodeId) -> Vec<MoveNodeId> {
+        let mut path = Vec::new();
+        let mut current = self.get(id);
+        while let Some(node) = current {
+            path.push(node.id);
+            current = node.parent_id.and_then(|parent_id| self.get(parent_id));
+        }
+        path.reverse();
+        path
+    }
+
+    /// Get the moves from the root to a node in UCI notation (e.g. "e2e4", "e1g1").
+    /// Returns None if the node doesn't exist or a move can't be replayed.
+    pub fn uci_moves_to(&self, id: MoveNodeId) -> Option<Vec<String>> {
+        let path = self.path_to(id);
+        if path.is_empty() {
+            return None;
+        }
+        path.windows(2)
+            .map(|pair| {
+                let parent = &self.nodes[pair[0]];
+                let san: San = self.nodes[pair[1]].san.as_deref()?.parse().ok()?;
+                let m = san.to_move(&parent.position).ok()?;
+                Some(m.to_uci(CastlingMode::Standard).to_string())
+            })
+            .collect()
+    }
+
+    /// Get the history of the position after a node's move: the starting FEN
+    /// (None for the standard start) and the UCI moves leading there,
+    /// so an engine can see repetitions
+    pub fn uci_history_to(&self, id: MoveNodeId) -> (Option<String>, Vec<String>) {
+        match self.uci_moves_to(id) {
+            Some(moves) => (self.setup_fen(), moves),
+            // Moves in the tree are always legal, but fall back to the bare position
+            None => (
+                self.get(id).map(|node| position_to_fen(&node.position)),
+                vec![],
+            ),
+        }
+    }
+
     /// Delete a node and all its descendants.
     /// If the current position is within the deleted subtree, navigates to the parent.
     /// Returns true on success, false if node_id is root or invalid.
@@ -231,7 +401,16 @@ impl MoveTree {
         }
 
         // Remove from parent's children
-        self.nodes[parent_id].children.retain(|&id| id != node_id);
+        let children = &mut self.nodes[parent_id].children;
+        let old_main = children.first().copied();
+        children.retain(|&id| id != node_id);
+
+        // Deleting the main line move promotes the first variation in its place
+        if let Some(&new_main) = self.nodes[parent_id].children.first()
+            && Some(new_main) != old_main
+        {
+            self.move_pre_comment_to_parent(new_main, parent_id);
+        }
 
         // Note: We don't actually remove nodes from the vec (would invalidate IDs).
         // The nodes become orphaned but that's fine for our use case.
@@ -321,9 +500,23 @@ impl MoveTree {
         let id = children.remove(pos);
         children.insert(0, id);
 
+        self.move_pre_comment_to_parent(node_id, parent_id);
+
         true
     }
 
+    /// A main line move can't have a pre-comment, so append a node's pre-comment
+    /// to its parent's comment, where a PGN re-import would put it
+    fn move_pre_comment_to_parent(&mut self, node_id: MoveNodeId, parent_id: MoveNodeId) {
+        if let Some(pre_comment) = self.nodes[node_id].pre_comment.take() {
+            let parent = &mut self.nodes[parent_id];
+            parent.comment = Some(match parent.comment.take() {
+                Some(existing) => format!("{} {}", existing, pre_comment),
+                None => pre_comment,
+            });
+        }
+    }
+
     /// Promote a variation to be the global main line.
     /// This promotes at every branch point from this node up to the root.
     /// Returns true on success.
@@ -369,6 +562,91 @@ mod tests {
         assert!(tree.is_at_leaf());
     }
 
+    #[test]
+    fn test_move_numbers_from_start() {
+        let mut tree = MoveTree::new();
+        let pos = Chess::default();
+
+        let e4 = tree.add_move(pos.clone(), "e4".to_string());
+        let e5 = tree.add_move(pos.clone(), "e5".to_string());
+        let nf3 = tree.add_move(pos.clone(), "Nf3".to_string());
+
+        assert_eq!(tree.get(e4).unwrap().move_number(&tree), (1, false));
+        assert_eq!(tree.get(e5).unwrap().move_number(&tree), (1, true));
+        assert_eq!(tree.get(nf3).unwrap().move_number(&tree), (2, false));
+    }
+
+    #[test]
+    fn test_move_numbers_from_fen() {
+        // Black to move on move 40
+        let mut tree = MoveTree::from_fen("8/8/4k3/8/8/4K3/4P3/8 b - - 3 40").unwrap();
+        let pos = tree.root().position.clone();
+
+        let first = tree.add_move(pos.clone(), "Kd6".to_string());
+        let second = tree.add_move(pos.clone(), "Kd4".to_string());
+
+        assert_eq!(tree.get(first).unwrap().move_number(&tree), (40, true));
+        assert_eq!(tree.get(second).unwrap().move_number(&tree), (41, false));
+    }
+
+    #[test]
+    fn test_from_fen_error() {
+        assert!(MoveTree::from_fen("not a fen").is_err());
+    }
+
+    /// Play SAN moves from the current node, returning the last node's ID
+    fn play(tree: &mut MoveTree, moves: &[&str]) -> MoveNodeId {
+        for san in moves {
+            let position = tree.current().position.clone();
+            let m = san.parse::<San>().unwrap().to_move(&position).unwrap();
+            tree.add_move(position.play(m).unwrap(), san.to_string());
+        }
+        tree.current_id()
+    }
+
+    #[test]
+    fn test_uci_moves_to() {
+        let mut tree = MoveTree::new();
+        let castled = play(&mut tree, &["e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6", "O-O"]);
+        tree.go_to(2);
+        let variation = play(&mut tree, &["Qh5"]);
+
+        assert_eq!(
+            tree.uci_moves_to(castled).unwrap(),
+            vec!["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1"]
+        );
+        assert_eq!(tree.path_to(variation), vec![0, 1, 2, variation]);
+        assert_eq!(
+            tree.uci_moves_to(variation).unwrap(),
+            vec!["e2e4", "e7e5", "d1h5"]
+        );
+        assert!(tree.uci_moves_to(0).unwrap().is_empty());
+        assert!(tree.uci_moves_to(99).is_none());
+    }
+
+    #[test]
+    fn test_uci_history_to() {
+        let mut tree = MoveTree::new();
+        let e4 = play(&mut tree, &["e4"]);
+        assert_eq!(tree.uci_history_to(e4), (None, vec!["e2e4".to_string()]));
+
+        // Non-standard move counters are kept
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 5";
+        let mut tree = MoveTree::from_fen(fen).unwrap();
+        let e4 = play(&mut tree, &["e4"]);
+        assert_eq!(
+            tree.uci_history_to(e4),
+            (Some(fen.to_string()), vec!["e2e4".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_uci_moves_to_promotion() {
+        let mut tree = MoveTree::from_fen("8/P6k/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let promoted = play(&mut tree, &["a8=Q"]);
+        assert_eq!(tree.uci_moves_to(promoted).unwrap(), vec!["a7a8q"]);
+    }
+
     #[test]
     fn test_add_move() {
         let mut tree = MoveTree::new();
@@ -533,6 +811,98 @@ mod tests {
         assert_eq!(e4.children, vec![3, 2]);
     }
 
+    #[test]
+    fn test_toggle_nag() {
+        let mut tree = MoveTree::new();
+        let id = tree.add_move(Chess::default(), "e4".to_string());
+
+        assert!(tree.toggle_nag(id, 1)); // !
+        assert!(tree.toggle_nag(id, 16)); // ±
+        assert_eq!(tree.get(id).unwrap().nags, vec![1, 16]);
+
+        // A conflicting move assessment replaces the old one
+        tree.toggle_nag(id, 6); // ?!
+        assert_eq!(tree.get(id).unwrap().nags, vec![16, 6]);
+
+        // Toggling again removes it
+        tree.toggle_nag(id, 6);
+        assert_eq!(tree.get(id).unwrap().nags, vec![16]);
+
+        assert!(!tree.toggle_nag(99, 1));
+    }
+
+    #[test]
+    fn test_set_comments() {
+        let mut tree = MoveTree::new();
+        let e4 = play(&mut tree, &["e4"]);
+        tree.go_to_root();
+        let d4 = play(&mut tree, &["d4"]);
+
+        assert!(tree.set_pre_comment(d4, Some("Main idea".to_string())));
+        assert!(tree.set_comment(d4, Some("Best by test".to_string())));
+        let node = tree.get(d4).unwrap();
+        assert_eq!(node.pre_comment.as_deref(), Some("Main idea"));
+        assert_eq!(node.comment.as_deref(), Some("Best by test"));
+
+        assert!(tree.set_comment(d4, None));
+        assert_eq!(tree.get(d4).unwrap().comment, None);
+
+        // Main line moves can't have a pre-comment
+        assert!(!tree.set_pre_comment(e4, Some("Main idea".to_string())));
+        assert!(!tree.set_pre_comment(0, Some("Main idea".to_string())));
+        assert_eq!(tree.get(e4).unwrap().pre_comment, None);
+    }
+
+    #[test]
+    fn test_promote_keeps_pre_comment() {
+        let mut tree = MoveTree::new();
+        let e4 = play(&mut tree, &["e4"]);
+        play(&mut tree, &["e5"]);
+        tree.go_to(e4);
+        let c5 = play(&mut tree, &["c5"]);
+        tree.set_pre_comment(c5, Some("Sharper".to_string()));
+
+        assert!(tree.promote_variation(c5));
+        assert_eq!(tree.get(c5).unwrap().pre_comment, None);
+        assert_eq!(tree.get(e4).unwrap().comment.as_deref(), Some("Sharper"));
+    }
+
+    #[test]
+    fn test_delete_main_move_keeps_pre_comment() {
+        let mut tree = MoveTree::new();
+        let e4 = play(&mut tree, &["e4"]);
+        let e5 = play(&mut tree, &["e5"]);
+        tree.go_to(e4);
+        let c5 = play(&mut tree, &["c5"]);
+        tree.set_pre_comment(c5, Some("Sharper".to_string()));
+        tree.set_comment(e4, Some("Best".to_string()));
+
+        assert!(tree.delete_node(e5));
+        assert_eq!(tree.get(e4).unwrap().children, vec![c5]);
+        assert_eq!(tree.get(c5).unwrap().pre_comment, None);
+        assert_eq!(
+            tree.get(e4).unwrap().comment.as_deref(),
+            Some("Best Sharper")
+        );
+    }
+
+    #[test]
+    fn test_delete_variation_keeps_main_line() {
+        let mut tree = MoveTree::new();
+        let e4 = play(&mut tree, &["e4"]);
+        play(&mut tree, &["e5"]);
+        tree.go_to(e4);
+        let c5 = play(&mut tree, &["c5"]);
+        tree.go_to(e4);
+        let e6 = play(&mut tree, &["e6"]);
+        tree.set_pre_comment(e6, Some("French".to_string()));
+
+        // Deleting a variation leaves the main line move and other pre-comments alone
+        assert!(tree.delete_node(c5));
+        assert_eq!(tree.get(e6).unwrap().pre_comment.as_deref(), Some("French"));
+        assert_eq!(tree.get(e4).unwrap().comment, None);
+    }
+
     #[test]
     fn test_promote_to_main_line() {
         let mut tree = MoveTree::new();
@@ -0,0 +1,93 @@
+//! Numeric Annotation Glyphs (NAGs) - the "$n" annotations of PGN.
+//!
+//! This is a pure domain module with no GPUI dependencies.
+
+/// Move assessment NAGs: !, ?, !!, ??, !?, ?!
+pub const MOVE_NAGS: [u8; 6] = [1, 2, 3, 4, 5, 6];
+
+/// Position assessment NAGs: =, ∞, ⩲, ⩱, ±, ∓, +−, −+
+pub const POSITION_NAGS: [u8; 8] = [10, 13, 14, 15, 16, 17, 18, 19];
+
+/// Get the display glyph for a NAG, if it has a conventional one
+pub fn nag_glyph(nag: u8) -> Option<&'static str> {
+    let glyph = match nag {
+        1 => "!",
+        2 => "?",
+        3 => "!!",
+        4 => "??",
+        5 => "!?",
+        6 => "?!",
+        7 => "□",
+        10 => "=",
+        13 => "∞",
+        14 => "⩲",
+        15 => "⩱",
+        16 => "±",
+        17 => "∓",
+        18 => "+−",
+        19 => "−+",
+        22 | 23 => "⨀",
+        32 | 33 => "⟳",
+        36 | 37 => "→",
+        40 | 41 => "↑",
+        132 | 133 => "⇆",
+        138 | 139 => "⊕",
+        146 => "N",
+        _ => return None,
+    };
+    Some(glyph)
+}
+
+/// Get a short human-readable description of a NAG (for menus)
+pub fn nag_description(nag: u8) -> &'static str {
+    match nag {
+        1 => "Good move",
+        2 => "Mistake",
+        3 => "Brilliant move",
+        4 => "Blunder",
+        5 => "Interesting move",
+        6 => "Dubious move",
+        7 => "Only move",
+        10 => "Equal position",
+        13 => "Unclear position",
+        14 => "White is slightly better",
+        15 => "Black is slightly better",
+        16 => "White is better",
+        17 => "Black is better",
+        18 => "White is winning",
+        19 => "Black is winning",
+        _ => "Annotation",
+    }
+}
+
+/// Whether this NAG is a move assessment (shown directly after the SAN, e.g. "e4!?")
+pub fn is_move_nag(nag: u8) -> bool {
+    MOVE_NAGS.contains(&nag)
+}
+
+/// Whether two NAGs are mutually exclusive (a move has at most one move
+/// assessment and at most one position assessment)
+pub fn nags_conflict(a: u8, b: u8) -> bool {
+    (is_move_nag(a) && is_move_nag(b)) || (POSITION_NAGS.contains(&a) && POSITION_NAGS.contains(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glyphs() {
+        assert_eq!(nag_glyph(1), Some("!"));
+        assert_eq!(nag_glyph(5), Some("!?"));
+        assert_eq!(nag_glyph(16), Some("±"));
+        assert_eq!(nag_glyph(250), None);
+    }
+
+    #[test]
+    fn test_conflicts() {
+        assert!(nags_conflict(1, 2));
+        assert!(nags_conflict(16, 10));
+        assert!(!nags_conflict(1, 16));
+        assert!(!nags_conflict(146, 1));
+    }
+}
@@ -0,0 +1,363 @@
+//! Game results and the ways a game can end.
+
+use std::fmt;
+
+use shakmaty::{Chess, Position};
+
+use super::uci::Score;
+use super::{PieceColor, color_to_shakmaty, position_to_fen, shakmaty_to_color};
+
+/// Best score (in centipawns, from its own point of view) at which the engine
+/// still accepts a draw offer
+const DRAW_ACCEPT_MAX_CP: i32 = 0;
+
+/// Half-moves without a capture or pawn move after which a draw may be claimed
+const FIFTY_MOVE_HALFMOVES: u32 = 100;
+
+/// Half-moves without a capture or pawn move after which the game is drawn
+const SEVENTY_FIVE_MOVE_HALFMOVES: u32 = 150;
+
+/// The result of a finished game
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    /// A win for one side
+    pub fn win_for(color: PieceColor) -> Self {
+        match color {
+            PieceColor::White => GameResult::WhiteWins,
+            PieceColor::Black => GameResult::BlackWins,
+        }
+    }
+
+    /// The result as written in PGN, e.g. "1-0"
+    pub fn pgn_token(&self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+        }
+    }
+}
+
+/// Why a game ended
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    /// 50 moves by each side without a capture or pawn move (claimed)
+    FiftyMoveRule,
+    /// The same position occurred three times (claimed)
+    ThreefoldRepetition,
+    /// 75 moves by each side without a capture or pawn move
+    SeventyFiveMoveRule,
+    /// The same position occurred five times
+    FivefoldRepetition,
+    /// A player ran out of time
+    TimeForfeit,
+    Resignation,
+    DrawAgreement,
+    /// A player broke the rules of a match, e.g. an engine played an illegal move
+    Forfeit,
+}
+
+/// How a game ended: the result and the reason
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GameOutcome {
+    pub result: GameResult,
+    pub termination: Termination,
+}
+
+impl GameOutcome {
+    /// A draw, e.g. one claimed by threefold repetition
+    pub fn draw(termination: Termination) -> Self {
+        Self {
+            result: GameResult::Draw,
+            termination,
+        }
+    }
+
+    /// The player of `color` resigned
+    pub fn resignation(color: PieceColor) -> Self {
+        Self {
+            result: GameResult::win_for(color.opposite()),
+            termination: Termination::Resignation,
+        }
+    }
+
+    /// The players agreed to a draw
+    pub fn draw_agreed() -> Self {
+        Self::draw(Termination::DrawAgreement)
+    }
+
+    /// The player of `color` forfeited the game
+    pub fn forfeit(color: PieceColor) -> Self {
+        Self {
+            result: GameResult::win_for(color.opposite()),
+            termination: Termination::Forfeit,
+        }
+    }
+
+    /// The player of `color` ran out of time in `position`. It is a draw if the
+    /// opponent has no way to mate.
+    pub fn time_forfeit(position: &Chess, color: PieceColor) -> Self {
+        let opponent = color.opposite();
+        let result = if position.has_insufficient_material(color_to_shakmaty(opponent)) {
+            GameResult::Draw
+        } else {
+            GameResult::win_for(opponent)
+        };
+        Self {
+            result,
+            termination: Termination::TimeForfeit,
+        }
+    }
+}
+
+impl fmt::Display for GameOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let winner = match self.result {
+            GameResult::WhiteWins => "White wins",
+            GameResult::BlackWins => "Black wins",
+            GameResult::Draw => "Draw",
+        };
+        let reason = match (self.termination, self.result) {
+            (Termination::Checkmate, _) => "by checkmate",
+            (Termination::Stalemate, _) => "by stalemate",
+            (Termination::InsufficientMaterial, _) => "by insufficient material",
+            (Termination::FiftyMoveRule, _) => "by the fifty-move rule",
+            (Termination::ThreefoldRepetition, _) => "by threefold repetition",
+            (Termination::SeventyFiveMoveRule, _) => "by the seventy-five-move rule",
+            (Termination::FivefoldRepetition, _) => "by fivefold repetition",
+            (Termination::TimeForfeit, GameResult::Draw) => {
+                "on time with insufficient mating material"
+            }
+            (Termination::TimeForfeit, _) => "on time",
+            (Termination::Resignation, _) => "by resignation",
+            (Termination::DrawAgreement, _) => "by agreement",
+            (Termination::Forfeit, _) => "by forfeit",
+        };
+        write!(f, "{} {}", winner, reason)
+    }
+}
+
+/// How a game stands on the board after a sequence of positions
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BoardStatus {
+    /// The game goes on
+    Ongoing,
+    /// The game goes on, but a draw may be claimed (by threefold repetition
+    /// or the fifty-move rule)
+    DrawClaimable(Termination),
+    /// The rules end the game without a claim
+    Over(GameOutcome),
+}
+
+/// Get how the game ends in a position, if it is over on the board
+pub fn position_outcome(position: &Chess) -> Option<GameOutcome> {
+    if position.is_checkmate() {
+        // The side to move has been mated
+        let winner = shakmaty_to_color(position.turn()).opposite();
+        return Some(GameOutcome {
+            result: GameResult::win_for(winner),
+            termination: Termination::Checkmate,
+        });
+    }
+    if position.is_stalemate() {
+        Some(GameOutcome::draw(Termination::Stalemate))
+    } else if position.is_insufficient_material() {
+        Some(GameOutcome::draw(Termination::InsufficientMaterial))
+    } else {
+        None
+    }
+}
+
+/// Get how a game stands after a sequence of positions (the last is the current one).
+/// Mate, stalemate, insufficient material, fivefold repetition and the seventy-five-move
+/// rule end the game; threefold repetition and the fifty-move rule allow a claim.
+pub fn history_status(history: &[Chess]) -> BoardStatus {
+    let Some(position) = history.last() else {
+        return BoardStatus::Ongoing;
+    };
+    if let Some(outcome) = position_outcome(position) {
+        return BoardStatus::Over(outcome);
+    }
+
+    // Positions before the last capture or pawn move can't be repeated
+    let key = position_key(position);
+    let repetitions = history
+        .iter()
+        .rev()
+        .take(position.halfmoves() as usize + 1)
+        .filter(|earlier| position_key(earlier) == key)
+        .count();
+    let halfmoves = position.halfmoves();
+
+    if repetitions >= 5 {
+        BoardStatus::Over(GameOutcome::draw(Termination::FivefoldRepetition))
+    } else if halfmoves >= SEVENTY_FIVE_MOVE_HALFMOVES {
+        BoardStatus::Over(GameOutcome::draw(Termination::SeventyFiveMoveRule))
+    } else if repetitions >= 3 {
+        BoardStatus::DrawClaimable(Termination::ThreefoldRepetition)
+    } else if halfmoves >= FIFTY_MOVE_HALFMOVES {
+        BoardStatus::DrawClaimable(Termination::FiftyMoveRule)
+    } else {
+        BoardStatus::Ongoing
+    }
+}
+
+/// Get how a game ends after a sequence of positions (the last is the current one),
+/// treating a draw that may be claimed as claimed
+pub fn history_outcome(history: &[Chess]) -> Option<GameOutcome> {
+    match history_status(history) {
+        BoardStatus::Ongoing => None,
+        BoardStatus::DrawClaimable(termination) => Some(GameOutcome::draw(termination)),
+        BoardStatus::Over(outcome) => Some(outcome),
+    }
+}
+
+/// What makes positions the same for repetition: pieces, side to move, castling
+/// rights and en passant (only if a capture is possible), but not the move counters
+fn position_key(position: &Chess) -> String {
+    let fen = position_to_fen(position);
+    fen.split_whitespace().take(4).collect::<Vec<_>>().join(" ")
+}
+
+/// Whether an engine with this evaluation (from its own point of view) accepts a draw
+pub fn engine_accepts_draw(score: Score) -> bool {
+    match score {
+        Score::Centipawns(cp) => cp <= DRAW_ACCEPT_MAX_CP,
+        Score::Mate(moves) => moves < 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{parse_fen, uci_line_to_san};
+
+    #[test]
+    fn test_position_outcome() {
+        assert_eq!(position_outcome(&Chess::default()), None);
+
+        // Fool's mate
+        let mated =
+            parse_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+        let outcome = position_outcome(&mated).unwrap();
+        assert_eq!(outcome.result, GameResult::BlackWins);
+        assert_eq!(outcome.termination, Termination::Checkmate);
+        assert_eq!(outcome.to_string(), "Black wins by checkmate");
+
+        let stalemate = parse_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(
+            position_outcome(&stalemate).map(|outcome| outcome.termination),
+            Some(Termination::Stalemate)
+        );
+
+        let bare_kings = parse_fen("8/8/4k3/8/8/3K4/8/8 w - - 0 1").unwrap();
+        let outcome = position_outcome(&bare_kings).unwrap();
+        assert_eq!(outcome.result, GameResult::Draw);
+        assert_eq!(outcome.termination, Termination::InsufficientMaterial);
+    }
+
+    #[test]
+    fn test_history_outcome() {
+        let mut history = vec![Chess::default()];
+        assert_eq!(history_outcome(&history), None);
+
+        // Knights out and back twice: the start position occurs a third time
+        for _ in 0..2 {
+            for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                let line = uci_line_to_san(history.last().unwrap(), &[uci.to_string()]);
+                history.push(line[0].position.clone());
+            }
+        }
+        assert_eq!(history_outcome(&history[..8]), None);
+        assert_eq!(
+            history_outcome(&history).map(|outcome| outcome.termination),
+            Some(Termination::ThreefoldRepetition)
+        );
+
+        let fifty_moves = parse_fen("8/8/4k3/8/8/3K4/3R4/8 w - - 100 80").unwrap();
+        let outcome = history_outcome(&[fifty_moves]).unwrap();
+        assert_eq!(outcome.termination, Termination::FiftyMoveRule);
+        assert_eq!(outcome.to_string(), "Draw by the fifty-move rule");
+    }
+
+    #[test]
+    fn test_history_status() {
+        let mut history = vec![Chess::default()];
+        assert_eq!(history_status(&history), BoardStatus::Ongoing);
+
+        // Each round of knight moves repeats the start position once more
+        for round in 1..=4 {
+            for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                let line = uci_line_to_san(history.last().unwrap(), &[uci.to_string()]);
+                history.push(line[0].position.clone());
+            }
+            let expected = match round {
+                1 => BoardStatus::Ongoing,
+                2 | 3 => BoardStatus::DrawClaimable(Termination::ThreefoldRepetition),
+                _ => BoardStatus::Over(GameOutcome::draw(Termination::FivefoldRepetition)),
+            };
+            assert_eq!(history_status(&history), expected, "round {}", round);
+        }
+
+        let fifty = parse_fen("8/8/4k3/8/8/3K4/3R4/8 w - - 120 90").unwrap();
+        assert_eq!(
+            history_status(&[fifty]),
+            BoardStatus::DrawClaimable(Termination::FiftyMoveRule)
+        );
+        let seventy_five = parse_fen("8/8/4k3/8/8/3K4/3R4/8 w - - 150 100").unwrap();
+        let status = history_status(&[seventy_five]);
+        assert_eq!(
+            status,
+            BoardStatus::Over(GameOutcome::draw(Termination::SeventyFiveMoveRule))
+        );
+
+        // Mate on the last move counts even after 75 moves
+        let mated = parse_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 150 100").unwrap();
+        assert!(matches!(
+            history_status(&[mated]),
+            BoardStatus::Over(GameOutcome {
+                termination: Termination::Checkmate,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_time_forfeit() {
+        let outcome = GameOutcome::time_forfeit(&Chess::default(), PieceColor::White);
+        assert_eq!(outcome.result, GameResult::BlackWins);
+        assert_eq!(outcome.to_string(), "Black wins on time");
+
+        // Black has only a king left, so white flagging can't lose
+        let lone_king = parse_fen("8/8/4k3/8/8/3K4/3Q4/8 w - - 0 1").unwrap();
+        let outcome = GameOutcome::time_forfeit(&lone_king, PieceColor::White);
+        assert_eq!(outcome.result, GameResult::Draw);
+        assert_eq!(outcome.result.pgn_token(), "1/2-1/2");
+    }
+
+    #[test]
+    fn test_resignation_and_agreement() {
+        let outcome = GameOutcome::resignation(PieceColor::Black);
+        assert_eq!(outcome.result.pgn_token(), "1-0");
+        assert_eq!(outcome.to_string(), "White wins by resignation");
+        assert_eq!(GameOutcome::draw_agreed().to_string(), "Draw by agreement");
+    }
+
+    #[test]
+    fn test_engine_accepts_draw() {
+        assert!(engine_accepts_draw(Score::Centipawns(0)));
+        assert!(engine_accepts_draw(Score::Centipawns(-150)));
+        assert!(!engine_accepts_draw(Score::Centipawns(40)));
+        assert!(engine_accepts_draw(Score::Mate(-3)));
+        assert!(!engine_accepts_draw(Score::Mate(2)));
+    }
+}
@@ -0,0 +1,1262 @@
+//! PGN (Portable Game Notation) import and export.
+//!
+//! Parses PGN text into a `MoveTree`, including the main line, nested
+//! variations (RAVs), comments, NAGs and `[%clk]`/`[%emt]` clock readings,
+//! and writes a `MoveTree` back out.
+//! This is a pure domain module with no GPUI dependencies.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::iter::Peekable;
+
+use shakmaty::san::{San, SanPlus};
+use shakmaty::{Chess, Color as SColor, Position};
+
+use super::{FenError, MoveClock, MoveNodeId, MoveTree, parse_fen};
+
+/// A single game parsed from PGN
+#[derive(Clone, Debug)]
+pub struct PgnGame {
+    /// Tag pairs in the order they appeared (e.g. ("White", "Carlsen"))
+    pub headers: Vec<(String, String)>,
+    /// The move tree, navigated to the root
+    pub tree: MoveTree,
+    /// Game termination marker ("1-0", "0-1", "1/2-1/2" or "*"), if present
+    pub result: Option<String>,
+}
+
+/// An error encountered while parsing PGN
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnError {
+    /// 1-based line number in the input where the error occurred
+    pub line: usize,
+    /// What went wrong
+    pub kind: PgnErrorKind,
+}
+
+/// The kind of PGN parse error
+#[derive(Clone, Debug, PartialEq)]
+pub enum PgnErrorKind {
+    /// The input contains no tags and no moves
+    Empty,
+    /// A tag pair could not be parsed
+    MalformedTag,
+    /// A `{` comment was never closed
+    UnterminatedComment,
+    /// A token could not be parsed as a move
+    InvalidMove(String),
+    /// A syntactically valid move that is not legal in the position
+    IllegalMove {
+        san: String,
+        move_number: u32,
+        is_black: bool,
+    },
+    /// A variation was opened before any move it could be an alternative to
+    VariationWithoutMove,
+    /// Parentheses don't balance
+    UnbalancedVariation,
+    /// A character that can't start any PGN token
+    UnexpectedChar(char),
+    /// The `FEN` tag doesn't describe a valid starting position
+    InvalidFen(FenError),
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            PgnErrorKind::Empty => write!(f, "no game found"),
+            PgnErrorKind::MalformedTag => write!(f, "malformed tag pair"),
+            PgnErrorKind::UnterminatedComment => write!(f, "unterminated comment"),
+            PgnErrorKind::InvalidMove(token) => write!(f, "invalid move '{}'", token),
+            PgnErrorKind::IllegalMove {
+                san,
+                move_number,
+                is_black,
+            } => {
+                let dots = if *is_black { "..." } else { "." };
+                write!(f, "illegal move {}{}{}", move_number, dots, san)
+            }
+            PgnErrorKind::VariationWithoutMove => write!(f, "variation before any move"),
+            PgnErrorKind::UnbalancedVariation => write!(f, "unbalanced parentheses"),
+            PgnErrorKind::UnexpectedChar(c) => write!(f, "unexpected character '{}'", c),
+            PgnErrorKind::InvalidFen(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+/// Lexical PGN tokens
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    OpenVariation,
+    CloseVariation,
+    Result(String),
+    San(String),
+}
+
+/// Parse the first game in a PGN string.
+/// The input is tokenized lazily, so only the first game has to be valid.
+pub fn parse_pgn(input: &str) -> Result<PgnGame, PgnError> {
+    parse_game(&mut Lexer::new(input).peekable())
+}
+
+/// Parse every game in a PGN string (e.g. an opening book)
+pub fn parse_pgn_games(input: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut tokens = Lexer::new(input).peekable();
+    let mut games = Vec::new();
+    while tokens.peek().is_some() {
+        games.push(parse_game(&mut tokens)?);
+    }
+    if games.is_empty() {
+        return Err(PgnError {
+            line: 1,
+            kind: PgnErrorKind::Empty,
+        });
+    }
+    Ok(games)
+}
+
+/// Parse the game at the start of a token stream, leaving the stream at the next game
+fn parse_game(tokens: &mut Peekable<Lexer>) -> Result<PgnGame, PgnError> {
+    let mut headers = Vec::new();
+    let mut builder = TreeBuilder::new();
+    let mut result = None;
+    let mut seen_any = false;
+    let mut last_line = 1;
+
+    // A tag after movetext starts the next game
+    while let Some(next) =
+        tokens.next_if(|next| !(builder.has_moves() && matches!(next, Ok((Token::Tag(..), _)))))
+    {
+        let (token, line) = next?;
+        last_line = line;
+        seen_any = true;
+        match token {
+            Token::Tag(name, value) => {
+                // Games that don't start from the initial position carry a FEN tag
+                // (normally together with [SetUp "1"])
+                if name == "FEN" {
+                    let position = parse_fen(&value).map_err(|e| PgnError {
+                        line,
+                        kind: PgnErrorKind::InvalidFen(e),
+                    })?;
+                    builder.set_start_position(position);
+                }
+                headers.push((name, value));
+            }
+            Token::Comment(text) => builder.comment(text),
+            Token::Nag(nag) => builder.nag(nag),
+            Token::OpenVariation => builder.open_variation(line)?,
+            Token::CloseVariation => builder.close_variation(line)?,
+            Token::San(san) => builder.play(&san, line)?,
+            Token::Result(r) => {
+                // Some PGNs put results inside variations; only the top level ends the game
+                if builder.depth() == 0 {
+                    result = Some(r);
+                    break;
+                }
+            }
+        }
+    }
+
+    if !seen_any {
+        return Err(PgnError {
+            line: last_line,
+            kind: PgnErrorKind::Empty,
+        });
+    }
+    if builder.depth() != 0 {
+        return Err(PgnError {
+            line: last_line,
+            kind: PgnErrorKind::UnbalancedVariation,
+        });
+    }
+
+    let mut tree = builder.tree;
+    tree.go_to_root();
+
+    Ok(PgnGame {
+        headers,
+        tree,
+        result,
+    })
+}
+
+/// Incrementally builds a `MoveTree` from a stream of movetext tokens
+struct TreeBuilder {
+    tree: MoveTree,
+    /// The node of the last move played in the current line (None at line start)
+    last_move: Option<MoveNodeId>,
+    /// Saved `last_move` for each open variation
+    stack: Vec<Option<MoveNodeId>>,
+    /// Comment seen at the start of a variation, becomes its first move's pre-comment
+    pending_comment: Option<String>,
+}
+
+impl TreeBuilder {
+    fn new() -> Self {
+        Self {
+            tree: MoveTree::new(),
+            last_move: None,
+            stack: Vec::new(),
+            pending_comment: None,
+        }
+    }
+
+    /// Re-root the tree at a different starting position (only valid before any move)
+    fn set_start_position(&mut self, position: Chess) {
+        let comment = self.tree.get(0).and_then(|root| root.comment.clone());
+        self.tree = MoveTree::from_position(position);
+        self.tree.set_comment(0, comment);
+    }
+
+    fn has_moves(&self) -> bool {
+        self.tree
+            .get(0)
+            .is_some_and(|root| !root.children.is_empty())
+    }
+
+    fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    fn comment(&mut self, text: String) {
+        // Clock readings after a move are stored on the move, not in its comment
+        let text = match self.last_move {
+            Some(id) => {
+                let (text, clock) = take_clock_commands(&text);
+                if clock.is_some() {
+                    self.tree.set_clock(id, clock);
+                }
+                text
+            }
+            None => text,
+        };
+        if text.is_empty() {
+            return;
+        }
+        match self.last_move {
+            Some(id) => self.append_comment(id, text),
+            // Before the first move of the game: a comment on the game itself
+            None if self.stack.is_empty() => self.append_comment(0, text),
+            None => {
+                self.pending_comment = Some(match self.pending_comment.take() {
+                    Some(existing) => format!("{} {}", existing, text),
+                    None => text,
+                });
+            }
+        }
+    }
+
+    fn append_comment(&mut self, id: MoveNodeId, text: String) {
+        let existing = self.tree.get(id).and_then(|n| n.comment.clone());
+        let comment = match existing {
+            Some(existing) => format!("{} {}", existing, text),
+            None => text,
+        };
+        self.tree.set_comment(id, Some(comment));
+    }
+
+    fn nag(&mut self, nag: u8) {
+        if let Some(id) = self.last_move {
+            self.tree.add_nag(id, nag);
+        }
+    }
+
+    fn open_variation(&mut self, line: usize) -> Result<(), PgnError> {
+        // A variation is an alternative to the last move, so it starts from its parent
+        let parent_id = self
+            .last_move
+            .and_then(|id| self.tree.get(id))
+            .and_then(|node| node.parent_id)
+            .ok_or(PgnError {
+                line,
+                kind: PgnErrorKind::VariationWithoutMove,
+            })?;
+
+        self.stack.push(self.last_move);
+        self.tree.go_to(parent_id);
+        self.last_move = None;
+        Ok(())
+    }
+
+    fn close_variation(&mut self, line: usize) -> Result<(), PgnError> {
+        let saved = self.stack.pop().ok_or(PgnError {
+            line,
+            kind: PgnErrorKind::UnbalancedVariation,
+        })?;
+
+        // Resume the enclosing line after its last move
+        if let Some(id) = saved {
+            self.tree.go_to(id);
+        }
+        self.last_move = saved;
+
+        // A comment in a variation without moves follows the move it was an alternative to
+        if let Some(comment) = self.pending_comment.take() {
+            self.append_comment(saved.unwrap_or(0), comment);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, token: &str, line: usize) -> Result<(), PgnError> {
+        let san_plus = SanPlus::from_ascii(token.as_bytes()).map_err(|_| PgnError {
+            line,
+            kind: PgnErrorKind::InvalidMove(token.to_string()),
+        })?;
+
+        let position = self.tree.current().position.clone();
+        let m = san_plus.san.to_move(&position).map_err(|_| PgnError {
+            line,
+            kind: PgnErrorKind::IllegalMove {
+                san: token.to_string(),
+                move_number: position.fullmoves().get(),
+                is_black: position.turn() == SColor::Black,
+            },
+        })?;
+
+        // Normalize the SAN (e.g. strip redundant disambiguation)
+        let san = San::from_move(&position, m).to_string();
+        let new_position = position.play(m).map_err(|_| PgnError {
+            line,
+            kind: PgnErrorKind::InvalidMove(token.to_string()),
+        })?;
+
+        let id = self.tree.add_move(new_position, san);
+        self.last_move = Some(id);
+
+        if let Some(comment) = self.pending_comment.take() {
+            let existing = self.tree.get(id).and_then(|n| n.pre_comment.clone());
+            let comment = match existing {
+                Some(existing) => format!("{} {}", existing, comment),
+                None => comment,
+            };
+            self.tree.set_pre_comment(id, Some(comment));
+        }
+        Ok(())
+    }
+}
+
+/// Splits PGN text into tokens on demand, each paired with its 1-based line number.
+/// Nothing is read after a lexical error.
+struct Lexer {
+    chars: Vec<char>,
+    i: usize,
+    line: usize,
+    /// Tokens read but not yet returned (a move can carry a suffix annotation)
+    queued: VecDeque<(Token, usize)>,
+}
+
+impl Lexer {
+    fn new(input: &str) -> Self {
+        Self {
+            chars: input.chars().collect(),
+            i: 0,
+            line: 1,
+            queued: VecDeque::new(),
+        }
+    }
+
+    /// Read from the current character: skip whitespace and escaped lines,
+    /// or queue the token starting there
+    fn read(&mut self) -> Result<(), PgnError> {
+        let chars = &self.chars;
+        let c = chars[self.i];
+        match c {
+            '\n' => {
+                self.line += 1;
+                self.i += 1;
+                // "%" at the start of a line escapes the whole line
+                if chars.get(self.i) == Some(&'%') {
+                    while self.i < chars.len() && chars[self.i] != '\n' {
+                        self.i += 1;
+                    }
+                }
+            }
+            '%' if self.i == 0 => {
+                while self.i < chars.len() && chars[self.i] != '\n' {
+                    self.i += 1;
+                }
+            }
+            c if c.is_whitespace() => self.i += 1,
+            '{' => {
+                let start_line = self.line;
+                let start = self.i + 1;
+                self.i = start;
+                while self.i < chars.len() && chars[self.i] != '}' {
+                    if chars[self.i] == '\n' {
+                        self.line += 1;
+                    }
+                    self.i += 1;
+                }
+                if self.i >= chars.len() {
+                    return Err(PgnError {
+                        line: start_line,
+                        kind: PgnErrorKind::UnterminatedComment,
+                    });
+                }
+                let text: String = chars[start..self.i].iter().collect();
+                self.queued
+                    .push_back((Token::Comment(normalize_whitespace(&text)), start_line));
+                self.i += 1;
+            }
+            ';' => {
+                let start = self.i + 1;
+                self.i = start;
+                while self.i < chars.len() && chars[self.i] != '\n' {
+                    self.i += 1;
+                }
+                let text: String = chars[start..self.i].iter().collect();
+                self.queued
+                    .push_back((Token::Comment(text.trim().to_string()), self.line));
+            }
+            '[' => {
+                let (name, value, end) = read_tag(chars, self.i + 1).ok_or(PgnError {
+                    line: self.line,
+                    kind: PgnErrorKind::MalformedTag,
+                })?;
+                self.queued.push_back((Token::Tag(name, value), self.line));
+                self.i = end;
+            }
+            '(' => {
+                self.queued.push_back((Token::OpenVariation, self.line));
+                self.i += 1;
+            }
+            ')' => {
+                self.queued.push_back((Token::CloseVariation, self.line));
+                self.i += 1;
+            }
+            '$' => {
+                let start = self.i + 1;
+                self.i = start;
+                while self.i < chars.len() && chars[self.i].is_ascii_digit() {
+                    self.i += 1;
+                }
+                let digits: String = chars[start..self.i].iter().collect();
+                let nag = digits.parse().map_err(|_| PgnError {
+                    line: self.line,
+                    kind: PgnErrorKind::InvalidMove(format!("${}", digits)),
+                })?;
+                self.queued.push_back((Token::Nag(nag), self.line));
+            }
+            '*' => {
+                self.queued
+                    .push_back((Token::Result("*".to_string()), self.line));
+                self.i += 1;
+            }
+            c if is_symbol_char(c) => {
+                let start = self.i;
+                while self.i < chars.len() && is_symbol_char(chars[self.i]) {
+                    self.i += 1;
+                }
+                let symbol: String = chars[start..self.i].iter().collect();
+                push_symbol(&mut self.queued, &symbol, self.line);
+            }
+            other => {
+                return Err(PgnError {
+                    line: self.line,
+                    kind: PgnErrorKind::UnexpectedChar(other),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for Lexer {
+    type Item = Result<(Token, usize), PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.queued.is_empty() && self.i < self.chars.len() {
+            if let Err(e) = self.read() {
+                self.i = self.chars.len();
+                return Some(Err(e));
+            }
+        }
+        self.queued.pop_front().map(Ok)
+    }
+}
+
+/// Characters that can appear in move numbers, SAN moves, results and suffix annotations
+fn is_symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+        || matches!(c, '.' | '+' | '#' | '=' | ':' | '-' | '/' | '!' | '?' | '_')
+}
+
+/// Classify a symbol as a result, move number and/or SAN move (with suffix annotations)
+fn push_symbol(tokens: &mut VecDeque<(Token, usize)>, symbol: &str, line: usize) {
+    if matches!(symbol, "1-0" | "0-1" | "1/2-1/2") {
+        tokens.push_back((Token::Result(symbol.to_string()), line));
+        return;
+    }
+
+    // Stand-alone dots ("1. ... e5")
+    if symbol.chars().all(|c| c == '.') {
+        return;
+    }
+
+    // Move number indication ("12." or "12...") possibly glued to the move ("12.e4")
+    let mut rest = symbol;
+    let digits_end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    if digits_end > 0 && rest[digits_end..].starts_with('.') {
+        rest = rest[digits_end..].trim_start_matches('.');
+    } else if digits_end == rest.len() {
+        // A bare number without dots is also a move number
+        return;
+    }
+    if rest.is_empty() {
+        return;
+    }
+
+    // Suffix annotations ("!", "?!", ...) map to NAGs 1-6
+    let (san, nag) = split_suffix_annotation(rest);
+
+    // Some PGN writers use zeros for castling
+    let san = san.replace('0', "O");
+    tokens.push_back((Token::San(san), line));
+    if let Some(nag) = nag {
+        tokens.push_back((Token::Nag(nag), line));
+    }
+}
+
+/// Split a trailing "!", "?", "!!", "??", "!?" or "?!" from a move
+fn split_suffix_annotation(symbol: &str) -> (&str, Option<u8>) {
+    const SUFFIXES: [(&str, u8); 6] = [
+        ("!!", 3),
+        ("??", 4),
+        ("!?", 5),
+        ("?!", 6),
+        ("!", 1),
+        ("?", 2),
+    ];
+    for (suffix, nag) in SUFFIXES {
+        if let Some(san) = symbol.strip_suffix(suffix) {
+            return (san, Some(nag));
+        }
+    }
+    (symbol, None)
+}
+
+/// Read a tag pair body starting just after `[`.
+/// Returns the name, value and the index just past the closing `]`.
+fn read_tag(chars: &[char], mut i: usize) -> Option<(String, String, usize)> {
+    let skip_spaces = |i: &mut usize| {
+        while *i < chars.len() && (chars[*i] == ' ' || chars[*i] == '\t') {
+            *i += 1;
+        }
+    };
+
+    skip_spaces(&mut i);
+    let name_start = i;
+    while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+        i += 1;
+    }
+    if i == name_start {
+        return None;
+    }
+    let name: String = chars[name_start..i].iter().collect();
+
+    skip_spaces(&mut i);
+    if chars.get(i) != Some(&'"') {
+        return None;
+    }
+    i += 1;
+
+    let mut value = String::new();
+    loop {
+        match chars.get(i)? {
+            '"' => break,
+            '\\' => {
+                value.push(*chars.get(i + 1)?);
+                i += 2;
+            }
+            '\n' => return None,
+            c => {
+                value.push(*c);
+                i += 1;
+            }
+        }
+    }
+    i += 1;
+
+    skip_spaces(&mut i);
+    if chars.get(i) != Some(&']') {
+        return None;
+    }
+    Some((name, value, i + 1))
+}
+
+/// Collapse runs of whitespace (including newlines) in a comment into single spaces
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Remove the `[%clk ...]` and `[%emt ...]` commands from a comment.
+/// Returns the rest of the comment and the clock reading, if it has a `[%clk]`.
+fn take_clock_commands(text: &str) -> (String, Option<MoveClock>) {
+    let mut rest = String::new();
+    let mut remaining_ms = None;
+    let mut elapsed_ms = None;
+    let mut text = text;
+
+    while let Some(start) = text.find("[%") {
+        let Some(len) = text[start..].find(']') else {
+            break;
+        };
+        let command = &text[start + 2..start + len];
+        let (name, value) = command.split_once(' ').unwrap_or((command, ""));
+        let time = parse_clock_time(value.trim());
+        match name {
+            "clk" if time.is_some() => remaining_ms = time,
+            "emt" if time.is_some() => elapsed_ms = time,
+            _ => {
+                rest.push_str(&text[..start + len + 1]);
+                text = &text[start + len + 1..];
+                continue;
+            }
+        }
+        rest.push_str(&text[..start]);
+        text = &text[start + len + 1..];
+    }
+    rest.push_str(text);
+
+    let clock = remaining_ms.map(|remaining_ms| MoveClock {
+        remaining_ms,
+        elapsed_ms,
+    });
+    (normalize_whitespace(&rest), clock)
+}
+
+/// Parse a clock time in "h:mm:ss" form (minutes and hours optional,
+/// fractions of a second allowed) into milliseconds.
+/// Returns None for unreadable, negative or out of range times.
+fn parse_clock_time(text: &str) -> Option<u64> {
+    let mut parts = text.rsplit(':');
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    let seconds_ms = (seconds * 1000.0).round();
+    if !seconds_ms.is_finite() || !(0.0..u64::MAX as f64).contains(&seconds_ms) {
+        return None;
+    }
+    let mut ms = seconds_ms as u64;
+    for unit in [60_000, 3_600_000] {
+        if let Some(part) = parts.next() {
+            let part_ms = part.parse::<u64>().ok()?.checked_mul(unit)?;
+            ms = ms.checked_add(part_ms)?;
+        }
+    }
+    parts.next().is_none().then_some(ms)
+}
+
+/// Format milliseconds as a clock time, "h:mm:ss" with tenths if there are any
+fn format_clock_time(ms: u64) -> String {
+    let secs = ms / 1000;
+    let tenths = (ms % 1000) / 100;
+    let time = format!("{}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60);
+    if tenths > 0 {
+        format!("{}.{}", time, tenths)
+    } else {
+        time
+    }
+}
+
+/// Maximum line length for exported movetext (as recommended by the PGN standard)
+const MAX_LINE_LENGTH: usize = 80;
+
+/// Write a game as PGN: tag pairs, movetext with nested variations, and the result.
+///
+/// Headers are written in the given order. The result token is appended to
+/// the movetext and should match the "Result" header, if any.
+pub fn write_pgn(tree: &MoveTree, headers: &[(String, String)], result: &str) -> String {
+    let mut out = String::new();
+
+    for (name, value) in headers {
+        let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+        out.push_str(&format!("[{} \"{}\"]\n", name, escaped));
+    }
+    if !headers.is_empty() {
+        out.push('\n');
+    }
+
+    let mut movetext = MovetextWriter::default();
+    if let Some(comment) = tree.get(0).and_then(|root| root.comment.as_deref()) {
+        movetext.push_comment(comment);
+    }
+    movetext.write_line(tree, 0, true);
+    movetext.push(result.to_string());
+
+    out.push_str(&wrap_words(&movetext.tokens.join(" "), MAX_LINE_LENGTH));
+    out.push('\n');
+    out
+}
+
+/// Accumulates movetext tokens while walking the move tree
+#[derive(Default)]
+struct MovetextWriter {
+    tokens: Vec<String>,
+    /// Whether the next token opens a variation
+    open_variation: bool,
+}
+
+impl MovetextWriter {
+    fn push(&mut self, token: String) {
+        if self.open_variation {
+            self.open_variation = false;
+            self.tokens.push(format!("({}", token));
+        } else {
+            self.tokens.push(token);
+        }
+    }
+
+    fn push_comment(&mut self, comment: &str) {
+        // Comments can't contain their own terminator
+        self.push(format!("{{{}}}", comment.replace('}', ")")));
+    }
+
+    /// Write the line continuing from `parent_id`, with variations nested after
+    /// the main line move they are alternatives to.
+    fn write_line(&mut self, tree: &MoveTree, parent_id: MoveNodeId, mut force_number: bool) {
+        let mut parent_id = parent_id;
+
+        while let Some(parent) = tree.get(parent_id) {
+            let Some(main_id) = parent.main_line_child() else {
+                break;
+            };
+
+            force_number = self.write_move(tree, main_id, force_number);
+
+            for &variation_id in parent.variation_children() {
+                self.open_variation = true;
+                let force = self.write_move(tree, variation_id, true);
+                self.write_line(tree, variation_id, force);
+                if let Some(last) = self.tokens.last_mut() {
+                    last.push(')');
+                }
+                // Black's reply after a variation needs its number repeated ("3...")
+                force_number = true;
+            }
+
+            parent_id = main_id;
+        }
+    }
+
+    /// Write a single move with its comments, number (if needed) and NAGs.
+    /// Returns whether the following move needs an explicit move number.
+    fn write_move(&mut self, tree: &MoveTree, node_id: MoveNodeId, force_number: bool) -> bool {
+        let Some(node) = tree.get(node_id) else {
+            return force_number;
+        };
+        let Some(san) = &node.san else {
+            return force_number;
+        };
+
+        let mut force_number = force_number;
+        if let Some(pre_comment) = &node.pre_comment {
+            self.push_comment(pre_comment);
+            force_number = true;
+        }
+
+        let (move_num, is_black) = node.move_number(tree);
+        if !is_black {
+            self.push(format!("{}.", move_num));
+        } else if force_number {
+            self.push(format!("{}...", move_num));
+        }
+
+        let mut text = san.clone();
+        if node.position.is_checkmate() {
+            text.push('#');
+        } else if node.position.is_check() {
+            text.push('+');
+        }
+        self.push(text);
+
+        for nag in &node.nags {
+            self.push(format!("${}", nag));
+        }
+
+        let clock = node.clock.map(|clock| {
+            let emt = clock
+                .elapsed_ms
+                .map(|ms| format!(" [%emt {}]", format_clock_time(ms)));
+            format!(
+                "[%clk {}]{}",
+                format_clock_time(clock.remaining_ms),
+                emt.unwrap_or_default()
+            )
+        });
+        let comment = match (clock, &node.comment) {
+            (Some(clock), Some(comment)) => Some(format!("{} {}", clock, comment)),
+            (clock, comment) => clock.or_else(|| comment.clone()),
+        };
+        match comment {
+            Some(comment) => {
+                self.push_comment(&comment);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Greedily wrap space-separated words into lines of at most `width` characters
+fn wrap_words(text: &str, width: usize) -> String {
+    let mut out = String::new();
+    let mut line_len = 0;
+
+    for word in text.split(' ') {
+        if line_len > 0 && line_len + 1 + word.len() > width {
+            out.push('\n');
+            line_len = 0;
+        } else if line_len > 0 {
+            out.push(' ');
+            line_len += 1;
+        }
+        out.push_str(word);
+        line_len += word.len();
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Collect the SAN of the main line
+    fn main_line_sans(tree: &MoveTree) -> Vec<String> {
+        tree.main_line()
+            .iter()
+            .filter_map(|&id| tree.get(id).and_then(|n| n.san.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_main_line() {
+        let game = parse_pgn("1. e4 e5 2. Nf3 Nc6 3. Bb5 *").unwrap();
+        assert_eq!(
+            main_line_sans(&game.tree),
+            vec!["e4", "e5", "Nf3", "Nc6", "Bb5"]
+        );
+        assert_eq!(game.result.as_deref(), Some("*"));
+        assert!(game.tree.is_at_root());
+    }
+
+    #[test]
+    fn test_parse_multiple_games() {
+        let pgn =
+            "[Event \"A\"]\n\n1. e4 e5 *\n\n[Event \"B\"]\n\n1. d4 d5 2. c4 1/2-1/2\n\n1. c4 *";
+        let games = parse_pgn_games(pgn).unwrap();
+        assert_eq!(games.len(), 3);
+        assert_eq!(main_line_sans(&games[0].tree), vec!["e4", "e5"]);
+        assert_eq!(games[1].headers[0].1, "B");
+        assert_eq!(main_line_sans(&games[1].tree), vec!["d4", "d5", "c4"]);
+        assert_eq!(games[1].result.as_deref(), Some("1/2-1/2"));
+        // A game without tags directly after the previous result
+        assert_eq!(main_line_sans(&games[2].tree), vec!["c4"]);
+
+        assert!(parse_pgn_games("  \n").is_err());
+    }
+
+    #[test]
+    fn test_parse_headers() {
+        let pgn = "[Event \"Casual\"]\n[White \"A \\\"B\\\" C\"]\n\n1. d4 1-0";
+        let game = parse_pgn(pgn).unwrap();
+        assert_eq!(
+            game.headers,
+            vec![
+                ("Event".to_string(), "Casual".to_string()),
+                ("White".to_string(), "A \"B\" C".to_string()),
+            ]
+        );
+        assert_eq!(game.result.as_deref(), Some("1-0"));
+    }
+
+    #[test]
+    fn test_parse_variation() {
+        // 1.e4 e5 (1...c5 2.Nf3) 2.Nf3
+        let game = parse_pgn("1. e4 e5 (1... c5 2. Nf3) 2. Nf3 *").unwrap();
+        let tree = &game.tree;
+        assert_eq!(main_line_sans(tree), vec!["e4", "e5", "Nf3"]);
+
+        let e4 = tree.get(tree.main_line()[1]).unwrap();
+        assert_eq!(e4.children.len(), 2);
+        let c5 = tree.get(e4.variation_children()[0]).unwrap();
+        assert_eq!(c5.san.as_deref(), Some("c5"));
+        let nf3 = tree.get(c5.main_line_child().unwrap()).unwrap();
+        assert_eq!(nf3.san.as_deref(), Some("Nf3"));
+    }
+
+    #[test]
+    fn test_parse_nested_variations() {
+        let pgn = "1. e4 (1. d4 d5 (1... Nf6 2. c4) 2. c4) (1. c4) 1... e5 *";
+        let game = parse_pgn(pgn).unwrap();
+        let tree = &game.tree;
+        assert_eq!(main_line_sans(tree), vec!["e4", "e5"]);
+
+        let root = tree.get(0).unwrap();
+        let alternatives: Vec<_> = root
+            .children
+            .iter()
+            .map(|&id| tree.get(id).unwrap().san.clone().unwrap())
+            .collect();
+        assert_eq!(alternatives, vec!["e4", "d4", "c4"]);
+
+        let d4 = tree.get(root.children[1]).unwrap();
+        let d4_replies: Vec<_> = d4
+            .children
+            .iter()
+            .map(|&id| tree.get(id).unwrap().san.clone().unwrap())
+            .collect();
+        assert_eq!(d4_replies, vec!["d5", "Nf6"]);
+    }
+
+    #[test]
+    fn test_parse_comments_and_nags() {
+        let pgn = "{Game comment} 1. e4 $1 {Best by test} e5?! ; rest of line\n2. Nf3 *";
+        let game = parse_pgn(pgn).unwrap();
+        let tree = &game.tree;
+        let line = tree.main_line();
+
+        assert_eq!(
+            tree.get(0).unwrap().comment.as_deref(),
+            Some("Game comment")
+        );
+
+        let e4 = tree.get(line[1]).unwrap();
+        assert_eq!(e4.nags, vec![1]);
+        assert_eq!(e4.comment.as_deref(), Some("Best by test"));
+
+        let e5 = tree.get(line[2]).unwrap();
+        assert_eq!(e5.nags, vec![6]);
+        assert_eq!(e5.comment.as_deref(), Some("rest of line"));
+    }
+
+    #[test]
+    fn test_comment_at_variation_start() {
+        let game = parse_pgn("1. e4 ({Also good} 1. d4) *").unwrap();
+        let tree = &game.tree;
+        let d4 = tree.get(tree.get(0).unwrap().children[1]).unwrap();
+        assert_eq!(d4.pre_comment.as_deref(), Some("Also good"));
+        assert_eq!(d4.comment, None);
+        assert_eq!(tree.get(0).unwrap().comment, None);
+    }
+
+    #[test]
+    fn test_comment_in_empty_variation() {
+        let game = parse_pgn("1. e4 {Main} ({Or anything}) e5 *").unwrap();
+        let tree = &game.tree;
+        let e4 = tree.get(tree.main_line()[1]).unwrap();
+        assert_eq!(e4.comment.as_deref(), Some("Main Or anything"));
+        assert_eq!(e4.children.len(), 1);
+    }
+
+    #[test]
+    fn test_normalizes_san_and_castling() {
+        let pgn = "1. e4 e5 2. Ng1f3 Nc6 3. Bc4 Nf6 4. 0-0 *";
+        let game = parse_pgn(pgn).unwrap();
+        assert_eq!(
+            main_line_sans(&game.tree),
+            vec!["e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6", "O-O"]
+        );
+    }
+
+    #[test]
+    fn test_check_suffix_is_not_stored() {
+        let pgn = "1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0";
+        let game = parse_pgn(pgn).unwrap();
+        assert_eq!(main_line_sans(&game.tree).last().unwrap(), "Qxf7");
+    }
+
+    #[test]
+    fn test_move_number_glued_to_move() {
+        let game = parse_pgn("1.e4 e5 2.Nf3 *").unwrap();
+        assert_eq!(main_line_sans(&game.tree), vec!["e4", "e5", "Nf3"]);
+    }
+
+    #[test]
+    fn test_stops_at_first_game() {
+        let pgn = "[Event \"One\"]\n1. e4 *\n\n[Event \"Two\"]\n1. d4 *";
+        let game = parse_pgn(pgn).unwrap();
+        assert_eq!(main_line_sans(&game.tree), vec!["e4"]);
+        assert_eq!(game.headers.len(), 1);
+    }
+
+    #[test]
+    fn test_error_in_later_game() {
+        let pgn = "[Event \"One\"]\n1. e4 *\n\n[Event \"Two\"]\n1. d4 {never closed\n";
+        let game = parse_pgn(pgn).unwrap();
+        assert_eq!(main_line_sans(&game.tree), vec!["e4"]);
+
+        let err = parse_pgn_games(pgn).unwrap_err();
+        assert_eq!(err.line, 5);
+        assert_eq!(err.kind, PgnErrorKind::UnterminatedComment);
+    }
+
+    #[test]
+    fn test_illegal_move_error() {
+        let err = parse_pgn("1. e4 e5\n2. Ke3 *").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(
+            err.kind,
+            PgnErrorKind::IllegalMove {
+                san: "Ke3".to_string(),
+                move_number: 2,
+                is_black: false,
+            }
+        );
+        assert_eq!(err.to_string(), "line 2: illegal move 2.Ke3");
+    }
+
+    #[test]
+    fn test_invalid_move_error() {
+        let err = parse_pgn("1. e4 Zz9 *").unwrap_err();
+        assert_eq!(err.kind, PgnErrorKind::InvalidMove("Zz9".to_string()));
+    }
+
+    #[test]
+    fn test_unbalanced_variation_errors() {
+        let err = parse_pgn("1. e4 (1. d4 *").unwrap_err();
+        assert_eq!(err.kind, PgnErrorKind::UnbalancedVariation);
+
+        let err = parse_pgn("1. e4 ) *").unwrap_err();
+        assert_eq!(err.kind, PgnErrorKind::UnbalancedVariation);
+
+        let err = parse_pgn("(1. e4) *").unwrap_err();
+        assert_eq!(err.kind, PgnErrorKind::VariationWithoutMove);
+    }
+
+    #[test]
+    fn test_unterminated_comment_error() {
+        let err = parse_pgn("1. e4 {never closed\n\n").unwrap_err();
+        assert_eq!(err.line, 1);
+        assert_eq!(err.kind, PgnErrorKind::UnterminatedComment);
+    }
+
+    #[test]
+    fn test_malformed_tag_error() {
+        let err = parse_pgn("[Event Casual]\n1. e4 *").unwrap_err();
+        assert_eq!(err.kind, PgnErrorKind::MalformedTag);
+    }
+
+    #[test]
+    fn test_parse_from_fen() {
+        let pgn =
+            "[SetUp \"1\"]\n[FEN \"8/8/4k3/8/8/4K3/4P3/8 b - - 3 40\"]\n\n40... Kd6 41. Kd4 *";
+        let game = parse_pgn(pgn).unwrap();
+        assert_eq!(main_line_sans(&game.tree), vec!["Kd6", "Kd4"]);
+        assert_eq!(game.tree.root().position.fullmoves().get(), 40);
+    }
+
+    #[test]
+    fn test_invalid_fen_error() {
+        let err = parse_pgn("[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"]\n*").unwrap_err();
+        assert_eq!(err.line, 1);
+        assert!(matches!(err.kind, PgnErrorKind::InvalidFen(_)));
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let err = parse_pgn("  \n ").unwrap_err();
+        assert_eq!(err.kind, PgnErrorKind::Empty);
+    }
+    // Export tests
+
+    /// Parse PGN movetext and write it back without headers
+    fn rewrite(pgn: &str) -> String {
+        let game = parse_pgn(pgn).unwrap();
+        write_pgn(&game.tree, &[], game.result.as_deref().unwrap_or("*"))
+    }
+
+    #[test]
+    fn test_write_from_black_to_move() {
+        let pgn = "[FEN \"8/8/4k3/8/8/4K3/4P3/8 b - - 3 40\"]\n40... Kd6 41. Kd4 Ke6 *";
+        assert_eq!(rewrite(pgn), "40... Kd6 41. Kd4 Ke6 *\n");
+    }
+
+    #[test]
+    fn test_write_main_line() {
+        assert_eq!(rewrite("1. e4 e5 2. Nf3 *"), "1. e4 e5 2. Nf3 *\n");
+    }
+
+    #[test]
+    fn test_write_variation_on_black_move() {
+        let pgn = "1. e4 e5 (1... c5 2. Nf3) 2. Nf3 Nc6 *";
+        assert_eq!(rewrite(pgn), format!("{}\n", pgn));
+    }
+
+    #[test]
+    fn test_write_variation_on_white_move() {
+        let pgn = "1. e4 (1. d4 d5 (1... Nf6)) 1... e5 2. Nf3 *";
+        assert_eq!(rewrite(pgn), format!("{}\n", pgn));
+    }
+
+    #[test]
+    fn test_write_headers_comments_and_nags() {
+        let mut tree = parse_pgn("{Start} 1. e4 $1 {Best by test} e5 *")
+            .unwrap()
+            .tree;
+        tree.go_to_root();
+        let headers = vec![
+            ("Event".to_string(), "Club \"Open\"".to_string()),
+            ("Result".to_string(), "*".to_string()),
+        ];
+        assert_eq!(
+            write_pgn(&tree, &headers, "*"),
+            "[Event \"Club \\\"Open\\\"\"]\n[Result \"*\"]\n\n\
+             {Start} 1. e4 $1 {Best by test} 1... e5 *\n"
+        );
+    }
+
+    #[test]
+    fn test_write_pre_comment() {
+        let pgn = "1. e4 e5 ({Sharper is} 1... c5 {Sicilian}) 2. Nf3 *";
+        assert_eq!(rewrite(pgn), format!("{}\n", pgn));
+    }
+
+    #[test]
+    fn test_pre_comment_round_trip() {
+        let mut tree = parse_pgn("1. e4 e5 (1... c5) 2. Nf3 *").unwrap().tree;
+        let e4 = tree.main_line()[1];
+        let [e5, c5] = tree.get(e4).unwrap().children[..] else {
+            panic!("expected two replies");
+        };
+        assert!(!tree.set_pre_comment(e5, Some("Classical".to_string())));
+        assert!(tree.set_pre_comment(c5, Some("Sharper".to_string())));
+
+        let written = write_pgn(&tree, &[], "*");
+        assert_eq!(written, "1. e4 e5 ({Sharper} 1... c5) 2. Nf3 *\n");
+        let reparsed = parse_pgn(&written).unwrap().tree;
+        let e4 = reparsed.get(reparsed.main_line()[1]).unwrap();
+        assert_eq!(e4.comment, None);
+        let c5 = reparsed.get(e4.variation_children()[0]).unwrap();
+        assert_eq!(c5.pre_comment.as_deref(), Some("Sharper"));
+    }
+
+    #[test]
+    fn test_delete_main_move_pre_comment_round_trip() {
+        let mut tree = parse_pgn("1. e4 e5 ({Sharper} 1... c5) 2. Nf3 *")
+            .unwrap()
+            .tree;
+        let e4 = tree.main_line()[1];
+        let e5 = tree.main_line()[2];
+        assert!(tree.delete_node(e5));
+
+        let written = write_pgn(&tree, &[], "*");
+        assert_eq!(written, "1. e4 {Sharper} 1... c5 *\n");
+        let reparsed = parse_pgn(&written).unwrap().tree;
+        let e4_node = reparsed.get(reparsed.main_line()[1]).unwrap();
+        assert_eq!(tree.get(e4).unwrap().comment.as_deref(), Some("Sharper"));
+        assert_eq!(e4_node.comment.as_deref(), Some("Sharper"));
+        let c5 = reparsed.get(reparsed.main_line()[2]).unwrap();
+        assert_eq!(c5.pre_comment, None);
+    }
+
+    #[test]
+    fn test_write_check_and_mate_suffixes() {
+        let pgn = "1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0";
+        assert_eq!(rewrite(pgn), format!("{}\n", pgn));
+    }
+
+    #[test]
+    fn test_write_wraps_long_lines() {
+        let pgn = "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 \
+                   8. c3 O-O 9. h3 Nb8 10. d4 Nbd7 11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 *";
+        let written = rewrite(pgn);
+        assert!(written.lines().count() > 1);
+        assert!(written.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+    }
+
+    #[test]
+    fn test_parse_clock_comments() {
+        let game = parse_pgn(
+            "1. e4 {[%clk 0:05:02.5] [%emt 0:00:01]} e5 {Solid [%clk 1:05:00]} \
+             2. Nf3 {[%eval 0.3] [%clk x]} *",
+        )
+        .unwrap();
+        let tree = &game.tree;
+        let main_line = tree.main_line();
+        let [_, e4, e5, nf3] = main_line[..] else {
+            panic!("expected three moves");
+        };
+
+        assert_eq!(
+            tree.get(e4).unwrap().clock,
+            Some(MoveClock {
+                remaining_ms: 302_500,
+                elapsed_ms: Some(1000),
+            })
+        );
+        assert_eq!(tree.get(e4).unwrap().comment, None);
+        assert_eq!(
+            tree.get(e5).unwrap().clock,
+            Some(MoveClock {
+                remaining_ms: 3_900_000,
+                elapsed_ms: None,
+            })
+        );
+        assert_eq!(tree.get(e5).unwrap().comment.as_deref(), Some("Solid"));
+        // Other commands and unreadable times are kept in the comment
+        assert_eq!(tree.get(nf3).unwrap().clock, None);
+        assert_eq!(
+            tree.get(nf3).unwrap().comment.as_deref(),
+            Some("[%eval 0.3] [%clk x]")
+        );
+    }
+
+    #[test]
+    fn test_parse_clock_time() {
+        assert_eq!(parse_clock_time("1:05:02.5"), Some(3_902_500));
+        assert_eq!(parse_clock_time("02"), Some(2000));
+        assert_eq!(parse_clock_time("-1"), None);
+        assert_eq!(parse_clock_time("1:2:3:4"), None);
+        // Out of range and non-finite times are rejected instead of overflowing
+        assert_eq!(parse_clock_time("99999999999999:00:00"), None);
+        assert_eq!(parse_clock_time("1:1e30"), None);
+        assert_eq!(parse_clock_time("1e30"), None);
+        assert_eq!(parse_clock_time("inf"), None);
+        assert_eq!(parse_clock_time("NaN"), None);
+    }
+
+    #[test]
+    fn test_parse_huge_clock_comment() {
+        let game = parse_pgn("1. e4 {[%clk 99999999999999:00:00]} e5 {[%clk 1:1e30]} *").unwrap();
+        let main_line = game.tree.main_line();
+        let e4 = game.tree.get(main_line[1]).unwrap();
+        assert_eq!(e4.clock, None);
+        assert_eq!(e4.comment.as_deref(), Some("[%clk 99999999999999:00:00]"));
+        let e5 = game.tree.get(main_line[2]).unwrap();
+        assert_eq!(e5.clock, None);
+        assert_eq!(e5.comment.as_deref(), Some("[%clk 1:1e30]"));
+    }
+
+    #[test]
+    fn test_write_clock_comments() {
+        let pgn = "1. e4 {[%clk 0:05:02.5] [%emt 0:00:01]} 1... e5 {[%clk 1:05:00] Solid} *";
+        assert_eq!(rewrite(pgn), format!("{}\n", pgn));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let pgn = "[Event \"Round trip\"]\n\n\
+                   {Intro} 1. e4 $1 e5 (1... c5 {Sicilian} 2. Nf3 (2. c3 d5) 2... d6) \
+                   (1... e6 2. d4) 2. Nf3 Nc6 3. Bb5 $5 a6 1/2-1/2";
+        let game = parse_pgn(pgn).unwrap();
+        let written = write_pgn(&game.tree, &game.headers, "1/2-1/2");
+        let reparsed = parse_pgn(&written).unwrap();
+
+        assert_eq!(reparsed.headers, game.headers);
+        assert_eq!(reparsed.result.as_deref(), Some("1/2-1/2"));
+        assert_eq!(
+            write_pgn(&reparsed.tree, &reparsed.headers, "1/2-1/2"),
+            written
+        );
+        assert_eq!(main_line_sans(&reparsed.tree), main_line_sans(&game.tree));
+    }
+}
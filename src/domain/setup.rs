@@ -0,0 +1,289 @@
+//! Position setup - an editable board for the position editor.
+//!
+//! Unlike `Chess`, a `PositionSetup` may be illegal while it is being edited;
+//! it is only validated when turned into a playable position.
+//! This is a pure domain module with no GPUI dependencies.
+
+use shakmaty::{
+    Bitboard, CastlingMode, Chess, Color as SColor, EnPassantMode, File, FromSetup, Position, Rank,
+    Role, Setup, Square,
+};
+
+use super::{
+    Piece, PieceColor, PositionError, color_to_shakmaty, piece_to_shakmaty, shakmaty_to_color,
+    shakmaty_to_piece, square_to_row_col, to_square,
+};
+
+/// Which side of the board a castling right refers to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CastlingSide {
+    KingSide,
+    QueenSide,
+}
+
+/// An editable position: pieces, side to move, castling rights and en passant square
+#[derive(Clone, Debug)]
+pub struct PositionSetup {
+    setup: Setup,
+}
+
+impl PositionSetup {
+    /// Start editing from an existing position
+    pub fn from_position(position: &Chess) -> Self {
+        let mut setup = position.to_setup(EnPassantMode::Legal);
+        setup.halfmoves = 0;
+        Self { setup }
+    }
+
+    /// Get the piece on a square (row 0 = rank 8)
+    pub fn piece_at(&self, row: usize, col: usize) -> Option<Piece> {
+        self.setup
+            .board
+            .piece_at(to_square(row, col))
+            .map(shakmaty_to_piece)
+    }
+
+    /// Put a piece on a square, replacing whatever was there
+    pub fn put_piece(&mut self, row: usize, col: usize, piece: Piece) {
+        self.setup
+            .board
+            .set_piece_at(to_square(row, col), piece_to_shakmaty(piece));
+        self.prune_ep_square();
+    }
+
+    /// Remove the piece on a square, returning it
+    pub fn remove_piece(&mut self, row: usize, col: usize) -> Option<Piece> {
+        let piece = self.setup.board.remove_piece_at(to_square(row, col));
+        self.prune_ep_square();
+        piece.map(shakmaty_to_piece)
+    }
+
+    /// Move a piece between squares, capturing anything on the destination
+    pub fn move_piece(&mut self, from: (usize, usize), to: (usize, usize)) {
+        if from != to
+            && let Some(piece) = self.remove_piece(from.0, from.1)
+        {
+            self.put_piece(to.0, to.1, piece);
+        }
+    }
+
+    /// Remove all pieces, castling rights and the en passant square
+    pub fn clear(&mut self) {
+        self.setup.board = shakmaty::Board::empty();
+        self.setup.castling_rights = Bitboard::EMPTY;
+        self.setup.ep_square = None;
+    }
+
+    /// Get the side to move
+    pub fn turn(&self) -> PieceColor {
+        shakmaty_to_color(self.setup.turn)
+    }
+
+    /// Set the side to move
+    pub fn set_turn(&mut self, color: PieceColor) {
+        self.setup.turn = color_to_shakmaty(color);
+        self.prune_ep_square();
+    }
+
+    /// Whether a side has the given castling right
+    pub fn can_castle(&self, color: PieceColor, side: CastlingSide) -> bool {
+        self.setup
+            .castling_rights
+            .contains(castling_rook_square(color, side))
+    }
+
+    /// Grant or revoke a castling right
+    pub fn set_castling(&mut self, color: PieceColor, side: CastlingSide, allowed: bool) {
+        let rook_square = castling_rook_square(color, side);
+        if allowed {
+            self.setup.castling_rights.add(rook_square);
+        } else {
+            self.setup.castling_rights.discard(rook_square);
+        }
+    }
+
+    /// Get the en passant target square as row/col
+    pub fn ep_square(&self) -> Option<(usize, usize)> {
+        self.setup.ep_square.map(square_to_row_col)
+    }
+
+    /// Set the en passant target square. Squares that aren't candidates are ignored.
+    pub fn set_ep_square(&mut self, square: Option<(usize, usize)>) {
+        match square {
+            Some(sq) if !self.ep_candidates().contains(&sq) => {}
+            _ => self.setup.ep_square = square.map(|(row, col)| to_square(row, col)),
+        }
+    }
+
+    /// Squares that could be the en passant target: behind an enemy pawn
+    /// that could just have advanced two squares
+    pub fn ep_candidates(&self) -> Vec<(usize, usize)> {
+        let (pawn_rank, target_rank, origin_rank) = match self.setup.turn {
+            SColor::White => (Rank::Fifth, Rank::Sixth, Rank::Seventh),
+            SColor::Black => (Rank::Fourth, Rank::Third, Rank::Second),
+        };
+        let enemy_pawn = shakmaty::Piece {
+            color: !self.setup.turn,
+            role: Role::Pawn,
+        };
+        let board = &self.setup.board;
+
+        File::ALL
+            .into_iter()
+            .filter(|&file| {
+                board.piece_at(Square::from_coords(file, pawn_rank)) == Some(enemy_pawn)
+                    && board
+                        .piece_at(Square::from_coords(file, target_rank))
+                        .is_none()
+                    && board
+                        .piece_at(Square::from_coords(file, origin_rank))
+                        .is_none()
+            })
+            .map(|file| square_to_row_col(Square::from_coords(file, target_rank)))
+            .collect()
+    }
+
+    /// Validate the setup and turn it into a playable position
+    pub fn to_position(&self) -> Result<Chess, PositionError> {
+        Chess::from_setup(self.setup.clone(), CastlingMode::Standard)
+            .map_err(|e| PositionError::from_kinds(e.kinds()))
+    }
+
+    /// Drop the en passant square once it no longer makes sense
+    fn prune_ep_square(&mut self) {
+        if let Some(sq) = self.ep_square()
+            && !self.ep_candidates().contains(&sq)
+        {
+            self.setup.ep_square = None;
+        }
+    }
+}
+
+impl Default for PositionSetup {
+    fn default() -> Self {
+        Self::from_position(&Chess::default())
+    }
+}
+
+/// Castling rights are stored as the square of the rook involved
+fn castling_rook_square(color: PieceColor, side: CastlingSide) -> Square {
+    let rank = match color {
+        PieceColor::White => Rank::First,
+        PieceColor::Black => Rank::Eighth,
+    };
+    let file = match side {
+        CastlingSide::KingSide => File::H,
+        CastlingSide::QueenSide => File::A,
+    };
+    Square::from_coords(file, rank)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{PieceKind, position_to_fen};
+
+    const WHITE_KING: Piece = Piece {
+        kind: PieceKind::King,
+        color: PieceColor::White,
+    };
+    const BLACK_KING: Piece = Piece {
+        kind: PieceKind::King,
+        color: PieceColor::Black,
+    };
+
+    #[test]
+    fn test_from_start_position() {
+        let setup = PositionSetup::default();
+        assert!(setup.can_castle(PieceColor::White, CastlingSide::KingSide));
+        assert!(setup.can_castle(PieceColor::Black, CastlingSide::QueenSide));
+        assert_eq!(setup.turn(), PieceColor::White);
+        assert!(setup.to_position().is_ok());
+    }
+
+    #[test]
+    fn test_build_position() {
+        let mut setup = PositionSetup::default();
+        setup.clear();
+        setup.put_piece(7, 4, WHITE_KING); // e1
+        setup.put_piece(0, 4, BLACK_KING); // e8
+        setup.move_piece((7, 4), (6, 4)); // e1 -> e2
+        setup.set_turn(PieceColor::Black);
+
+        let position = setup.to_position().unwrap();
+        assert_eq!(position_to_fen(&position), "4k3/8/8/8/8/8/4K3/8 b - - 0 1");
+    }
+
+    #[test]
+    fn test_missing_king() {
+        let mut setup = PositionSetup::default();
+        setup.clear();
+        setup.put_piece(7, 4, WHITE_KING);
+        assert_eq!(setup.to_position().unwrap_err(), PositionError::MissingKing);
+
+        setup.remove_piece(7, 4);
+        assert_eq!(setup.to_position().unwrap_err(), PositionError::EmptyBoard);
+    }
+
+    #[test]
+    fn test_castling_rights_need_pieces_at_home() {
+        let mut setup = PositionSetup::default();
+        setup.remove_piece(7, 7); // h1 rook
+        assert_eq!(
+            setup.to_position().unwrap_err(),
+            PositionError::InvalidCastlingRights
+        );
+
+        setup.set_castling(PieceColor::White, CastlingSide::KingSide, false);
+        assert!(setup.to_position().is_ok());
+    }
+
+    #[test]
+    fn test_opposite_check() {
+        let mut setup = PositionSetup::default();
+        setup.clear();
+        setup.put_piece(7, 4, WHITE_KING);
+        setup.put_piece(0, 4, BLACK_KING);
+        setup.put_piece(
+            4,
+            4,
+            Piece {
+                kind: PieceKind::Rook,
+                color: PieceColor::White,
+            },
+        );
+        // Black is in check, so white can't be the side to move
+        assert_eq!(
+            setup.to_position().unwrap_err(),
+            PositionError::OppositeCheck
+        );
+
+        setup.set_turn(PieceColor::Black);
+        assert!(setup.to_position().is_ok());
+    }
+
+    #[test]
+    fn test_ep_candidates() {
+        let mut setup = PositionSetup::default();
+        setup.clear();
+        setup.put_piece(7, 4, WHITE_KING);
+        setup.put_piece(0, 4, BLACK_KING);
+        let black_pawn = Piece {
+            kind: PieceKind::Pawn,
+            color: PieceColor::Black,
+        };
+        setup.put_piece(3, 3, black_pawn); // d5
+
+        assert_eq!(setup.ep_candidates(), vec![(2, 3)]); // d6
+        setup.set_ep_square(Some((2, 3)));
+        assert_eq!(setup.ep_square(), Some((2, 3)));
+
+        // Non-candidates are ignored
+        setup.set_ep_square(Some((2, 4)));
+        assert_eq!(setup.ep_square(), Some((2, 3)));
+
+        // Changing the side to move invalidates it
+        setup.set_turn(PieceColor::Black);
+        assert_eq!(setup.ep_square(), None);
+    }
+}
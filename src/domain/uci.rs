@@ -16,12 +16,14 @@ pub enum UciCommand {
     UciNewGame,
     /// Set an engine option
     SetOption { name: String, value: String },
+    /// Trigger a button option
+    PushButton(String),
     /// Set position (startpos or FEN, with optional moves)
     Position { fen: Option<String>, moves: Vec<String> },
-    /// Start infinite analysis
-    GoInfinite,
-    /// Start analysis with depth limit
-    GoDepth(u32),
+    /// Start searching the current position
+    Go(GoParams),
+    /// The opponent played the move the engine was pondering on
+    PonderHit,
     /// Stop analysis
     Stop,
     /// Quit the engine
@@ -38,6 +40,7 @@ impl UciCommand {
             UciCommand::SetOption { name, value } => {
                 format!("setoption name {} value {}", name, value)
             }
+            UciCommand::PushButton(name) => format!("setoption name {}", name),
             UciCommand::Position { fen, moves } => {
                 let mut cmd = String::from("position ");
                 match fen {
@@ -53,14 +56,162 @@ impl UciCommand {
                 }
                 cmd
             }
-            UciCommand::GoInfinite => "go infinite".to_string(),
-            UciCommand::GoDepth(d) => format!("go depth {}", d),
+            UciCommand::Go(params) => params.to_uci_string(),
+            UciCommand::PonderHit => "ponderhit".to_string(),
             UciCommand::Stop => "stop".to_string(),
             UciCommand::Quit => "quit".to_string(),
         }
     }
 }
 
+/// Parameters of a `go` command. With no limits set the engine picks its
+/// own search time; `infinite` searches until `stop`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GoParams {
+    /// Only consider these moves (UCI notation)
+    pub searchmoves: Vec<String>,
+    /// Search in ponder mode (on the move the engine expects the opponent to play)
+    pub ponder: bool,
+    /// White's remaining time in milliseconds
+    pub wtime: Option<u64>,
+    /// Black's remaining time in milliseconds
+    pub btime: Option<u64>,
+    /// White's increment per move in milliseconds
+    pub winc: Option<u64>,
+    /// Black's increment per move in milliseconds
+    pub binc: Option<u64>,
+    /// Moves left until the next time control
+    pub movestogo: Option<u32>,
+    /// Search to this depth in plies
+    pub depth: Option<u32>,
+    /// Search this many nodes
+    pub nodes: Option<u64>,
+    /// Search for a mate in this many moves
+    pub mate: Option<u32>,
+    /// Search for exactly this many milliseconds
+    pub movetime: Option<u64>,
+    /// Search until told to stop
+    pub infinite: bool,
+}
+
+impl GoParams {
+    /// Search until told to stop
+    pub fn infinite() -> Self {
+        Self {
+            infinite: true,
+            ..Self::default()
+        }
+    }
+
+    /// Convert to a `go` command string
+    fn to_uci_string(&self) -> String {
+        let mut cmd = String::from("go");
+        if !self.searchmoves.is_empty() {
+            cmd.push_str(" searchmoves ");
+            cmd.push_str(&self.searchmoves.join(" "));
+        }
+        if self.ponder {
+            cmd.push_str(" ponder");
+        }
+        let limits = [
+            ("wtime", self.wtime),
+            ("btime", self.btime),
+            ("winc", self.winc),
+            ("binc", self.binc),
+            ("movestogo", self.movestogo.map(u64::from)),
+            ("depth", self.depth.map(u64::from)),
+            ("nodes", self.nodes),
+            ("mate", self.mate.map(u64::from)),
+            ("movetime", self.movetime),
+        ];
+        for (name, value) in limits {
+            if let Some(value) = value {
+                cmd.push_str(&format!(" {} {}", name, value));
+            }
+        }
+        if self.infinite {
+            cmd.push_str(" infinite");
+        }
+        cmd
+    }
+}
+
+/// How long to analyze each position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalysisLimit {
+    /// Until the position changes or analysis is stopped
+    Infinite,
+    /// To a fixed depth in plies
+    Depth(u32),
+    /// For a fixed time in milliseconds
+    MoveTime(u64),
+    /// For a fixed number of nodes
+    Nodes(u64),
+    /// Until a mate in this many moves is found
+    Mate(u32),
+}
+
+impl AnalysisLimit {
+    /// The limits offered in the engine pane
+    pub const PRESETS: &[AnalysisLimit] = &[
+        AnalysisLimit::Infinite,
+        AnalysisLimit::Depth(16),
+        AnalysisLimit::Depth(20),
+        AnalysisLimit::Depth(25),
+        AnalysisLimit::Depth(30),
+        AnalysisLimit::MoveTime(1_000),
+        AnalysisLimit::MoveTime(5_000),
+        AnalysisLimit::MoveTime(10_000),
+        AnalysisLimit::MoveTime(30_000),
+        AnalysisLimit::Nodes(100_000),
+        AnalysisLimit::Nodes(1_000_000),
+        AnalysisLimit::Nodes(10_000_000),
+        AnalysisLimit::Mate(3),
+        AnalysisLimit::Mate(5),
+    ];
+
+    /// The `go` parameters for this limit
+    pub fn go_params(self) -> GoParams {
+        match self {
+            AnalysisLimit::Infinite => GoParams::infinite(),
+            AnalysisLimit::Depth(depth) => GoParams {
+                depth: Some(depth),
+                ..GoParams::default()
+            },
+            AnalysisLimit::MoveTime(ms) => GoParams {
+                movetime: Some(ms),
+                ..GoParams::default()
+            },
+            AnalysisLimit::Nodes(nodes) => GoParams {
+                nodes: Some(nodes),
+                ..GoParams::default()
+            },
+            AnalysisLimit::Mate(moves) => GoParams {
+                mate: Some(moves),
+                ..GoParams::default()
+            },
+        }
+    }
+
+    /// Short label for display (e.g., "Infinite", "Depth 20", "5 s", "1M nodes")
+    pub fn label(self) -> String {
+        match self {
+            AnalysisLimit::Infinite => "Infinite".to_string(),
+            AnalysisLimit::Depth(depth) => format!("Depth {}", depth),
+            AnalysisLimit::MoveTime(ms) if ms % 1_000 == 0 => format!("{} s", ms / 1_000),
+            AnalysisLimit::MoveTime(ms) => format!("{} ms", ms),
+            AnalysisLimit::Nodes(nodes) if nodes % 1_000_000 == 0 => {
+                format!("{}M nodes", nodes / 1_000_000)
+            }
+            AnalysisLimit::Nodes(nodes) if nodes % 1_000 == 0 => {
+                format!("{}K nodes", nodes / 1_000)
+            }
+            AnalysisLimit::Nodes(nodes) => format!("{} nodes", nodes),
+            AnalysisLimit::Mate(moves) => format!("Mate in {}", moves),
+        }
+    }
+}
+
 /// Raw UCI output line types (for Phase 1 - just categorization)
 #[derive(Debug, Clone)]
 #[allow(dead_code)] // Some variants reserved for future use
@@ -74,9 +225,9 @@ pub enum UciOutputKind {
     /// "bestmove ..." - best move found
     BestMove(String),
     /// Engine identification
-    Id(String),
+    Id(UciId),
     /// Option definition
-    Option(String),
+    Option(UciOption),
     /// Unknown/other output
     Other(String),
 }
@@ -94,16 +245,171 @@ impl UciOutputKind {
             UciOutputKind::Info(rest.to_string())
         } else if let Some(rest) = line.strip_prefix("bestmove ") {
             UciOutputKind::BestMove(rest.to_string())
-        } else if let Some(rest) = line.strip_prefix("id ") {
-            UciOutputKind::Id(rest.to_string())
-        } else if let Some(rest) = line.strip_prefix("option ") {
-            UciOutputKind::Option(rest.to_string())
+        } else if let Some(id) = line.strip_prefix("id ").and_then(UciId::parse) {
+            UciOutputKind::Id(id)
+        } else if let Some(option) = line.strip_prefix("option ").and_then(UciOption::parse) {
+            UciOutputKind::Option(option)
         } else {
             UciOutputKind::Other(line.to_string())
         }
     }
 }
 
+/// An `id` line identifying the engine
+#[derive(Debug, Clone, PartialEq)]
+pub enum UciId {
+    /// `id name <name>` - usually includes the version
+    Name(String),
+    /// `id author <author>`
+    Author(String),
+}
+
+impl UciId {
+    /// Parse an id line (the part after "id ")
+    pub fn parse(id_str: &str) -> Option<Self> {
+        let (field, value) = id_str.trim().split_once(char::is_whitespace)?;
+        let value = value.trim().to_string();
+        match field {
+            "name" => Some(UciId::Name(value)),
+            "author" => Some(UciId::Author(value)),
+            _ => None,
+        }
+    }
+}
+
+/// What the engine told us about itself during the handshake
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EngineIdentity {
+    pub name: Option<String>,
+    pub author: Option<String>,
+}
+
+impl EngineIdentity {
+    /// Record an id line
+    pub fn update(&mut self, id: &UciId) {
+        match id {
+            UciId::Name(name) => self.name = Some(name.clone()),
+            UciId::Author(author) => self.author = Some(author.clone()),
+        }
+    }
+}
+
+/// The type of an engine option, with its default and constraints
+#[derive(Debug, Clone, PartialEq)]
+pub enum UciOptionType {
+    /// A boolean toggle
+    Check { default: bool },
+    /// An integer in a range
+    Spin { default: i64, min: i64, max: i64 },
+    /// One of a fixed set of strings
+    Combo { default: String, vars: Vec<String> },
+    /// An action without a value
+    Button,
+    /// Free text (often a path)
+    String { default: String },
+}
+
+/// An option declared by the engine with `option name ... type ...`
+#[derive(Debug, Clone, PartialEq)]
+pub struct UciOption {
+    pub name: String,
+    pub option_type: UciOptionType,
+}
+
+impl UciOption {
+    /// Parse an option declaration (the part after "option ").
+    /// Returns None for malformed declarations or unknown types.
+    pub fn parse(option_str: &str) -> Option<Self> {
+        // Names, defaults and vars may contain spaces, so each field runs
+        // until the next keyword
+        let mut fields: Vec<(&str, Vec<&str>)> = Vec::new();
+        for token in option_str.split_whitespace() {
+            let is_keyword = match token {
+                "name" => fields.is_empty(),
+                "type" | "default" | "min" | "max" | "var" => !fields.is_empty(),
+                _ => false,
+            };
+            if is_keyword {
+                fields.push((token, Vec::new()));
+            } else if let Some((_, words)) = fields.last_mut() {
+                words.push(token);
+            } else {
+                return None;
+            }
+        }
+
+        let field = |keyword: &str| {
+            fields
+                .iter()
+                .find(|(k, _)| *k == keyword)
+                .map(|(_, words)| words.join(" "))
+        };
+        // Engines print "<empty>" for an empty string default
+        let default = field("default")
+            .filter(|d| d != "<empty>")
+            .unwrap_or_default();
+
+        let name = field("name").filter(|name| !name.is_empty())?;
+        let option_type = match field("type")?.as_str() {
+            "check" => UciOptionType::Check {
+                default: default == "true",
+            },
+            "spin" => UciOptionType::Spin {
+                default: default.parse().ok()?,
+                min: field("min")?.parse().ok()?,
+                max: field("max")?.parse().ok()?,
+            },
+            "combo" => UciOptionType::Combo {
+                default,
+                vars: fields
+                    .iter()
+                    .filter(|(k, _)| *k == "var")
+                    .map(|(_, words)| words.join(" "))
+                    .collect(),
+            },
+            "button" => UciOptionType::Button,
+            "string" => UciOptionType::String { default },
+            _ => return None,
+        };
+
+        Some(Self { name, option_type })
+    }
+
+    /// The default value as it would be sent with `setoption` (None for buttons)
+    pub fn default_value(&self) -> Option<String> {
+        match &self.option_type {
+            UciOptionType::Check { default } => Some(default.to_string()),
+            UciOptionType::Spin { default, .. } => Some(default.to_string()),
+            UciOptionType::Combo { default, .. } | UciOptionType::String { default } => {
+                Some(default.clone())
+            }
+            UciOptionType::Button => None,
+        }
+    }
+
+    /// Check a value against the option's type and constraints, returning it
+    /// in the form to send with `setoption`
+    pub fn validate(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+        match &self.option_type {
+            UciOptionType::Check { .. } => {
+                matches!(value, "true" | "false").then(|| value.to_string())
+            }
+            UciOptionType::Spin { min, max, .. } => value
+                .parse::<i64>()
+                .ok()
+                .filter(|v| (*min..=*max).contains(v))
+                .map(|v| v.to_string()),
+            UciOptionType::Combo { vars, .. } => vars
+                .iter()
+                .find(|var| var.eq_ignore_ascii_case(value))
+                .cloned(),
+            UciOptionType::Button => None,
+            UciOptionType::String { .. } => Some(value.to_string()),
+        }
+    }
+}
+
 /// A timestamped UCI output line (for display in the UI)
 #[derive(Debug, Clone)]
 pub struct UciOutput {
@@ -131,7 +437,6 @@ pub enum Score {
 
 impl Score {
     /// Format score for display (e.g., "+0.35" or "M3" or "-M2")
-    #[allow(dead_code)] // Used in tests, may be used in future
     pub fn display(&self) -> String {
         match self {
             Score::Centipawns(cp) => {
@@ -152,6 +457,17 @@ impl Score {
         }
     }
 
+    /// The score from white's point of view, given a score from the side to move's
+    pub fn for_white(self, black_to_move: bool) -> Self {
+        if !black_to_move {
+            return self;
+        }
+        match self {
+            Score::Centipawns(cp) => Score::Centipawns(-cp),
+            Score::Mate(moves) => Score::Mate(-moves),
+        }
+    }
+
     /// Get a numeric value for comparison/display (centipawns, or large value for mate)
     #[allow(dead_code)] // Used in tests, may be used in future
     pub fn as_centipawns(&self) -> i32 {
@@ -384,6 +700,170 @@ mod tests {
         assert!(matches!(output.kind, UciOutputKind::BestMove(_)));
     }
 
+    #[test]
+    fn test_parse_id() {
+        let output = UciOutput::new("id name Stockfish 17.1".to_string());
+        assert!(
+            matches!(&output.kind, UciOutputKind::Id(UciId::Name(name)) if name == "Stockfish 17.1")
+        );
+
+        let mut identity = EngineIdentity::default();
+        identity.update(&UciId::parse("author the Stockfish developers").unwrap());
+        identity.update(&UciId::parse("name Stockfish 17.1").unwrap());
+        assert_eq!(identity.name.as_deref(), Some("Stockfish 17.1"));
+        assert_eq!(identity.author.as_deref(), Some("the Stockfish developers"));
+
+        assert!(UciId::parse("version 3").is_none());
+        assert!(UciId::parse("name").is_none());
+    }
+
+    // UciOption parsing tests
+    #[test]
+    fn test_parse_option_spin() {
+        let option = UciOption::parse("name Threads type spin default 1 min 1 max 1024").unwrap();
+        assert_eq!(option.name, "Threads");
+        assert_eq!(
+            option.option_type,
+            UciOptionType::Spin {
+                default: 1,
+                min: 1,
+                max: 1024
+            }
+        );
+        assert_eq!(option.default_value(), Some("1".to_string()));
+        assert_eq!(option.validate(" 8 "), Some("8".to_string()));
+        assert_eq!(option.validate("0"), None);
+        assert_eq!(option.validate("many"), None);
+    }
+
+    #[test]
+    fn test_parse_option_name_with_spaces() {
+        let option =
+            UciOption::parse("name Skill Level type spin default 20 min 0 max 20").unwrap();
+        assert_eq!(option.name, "Skill Level");
+    }
+
+    #[test]
+    fn test_parse_option_check_and_button() {
+        let option = UciOption::parse("name Ponder type check default false").unwrap();
+        assert_eq!(option.option_type, UciOptionType::Check { default: false });
+        assert_eq!(option.validate("true"), Some("true".to_string()));
+        assert_eq!(option.validate("yes"), None);
+
+        let option = UciOption::parse("name Clear Hash type button").unwrap();
+        assert_eq!(option.name, "Clear Hash");
+        assert_eq!(option.option_type, UciOptionType::Button);
+        assert_eq!(option.default_value(), None);
+    }
+
+    #[test]
+    fn test_parse_option_combo() {
+        let option = UciOption::parse(
+            "name Style type combo default Normal var Solid var Normal var Risky Play",
+        )
+        .unwrap();
+        assert_eq!(
+            option.option_type,
+            UciOptionType::Combo {
+                default: "Normal".to_string(),
+                vars: vec![
+                    "Solid".to_string(),
+                    "Normal".to_string(),
+                    "Risky Play".to_string()
+                ],
+            }
+        );
+        assert_eq!(
+            option.validate("risky play"),
+            Some("Risky Play".to_string())
+        );
+        assert_eq!(option.validate("Wild"), None);
+    }
+
+    #[test]
+    fn test_parse_option_empty_string_default() {
+        let option = UciOption::parse("name SyzygyPath type string default <empty>").unwrap();
+        assert_eq!(
+            option.option_type,
+            UciOptionType::String {
+                default: String::new()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_option_malformed() {
+        assert!(UciOption::parse("name Hash").is_none());
+        assert!(UciOption::parse("type spin default 1 min 1 max 2").is_none());
+        assert!(UciOption::parse("name Hash type spin default 16").is_none());
+        assert!(UciOption::parse("name Hash type slider default 16").is_none());
+
+        let output =
+            UciOutput::new("option name Hash type spin default 16 min 1 max 33554432".to_string());
+        assert!(matches!(output.kind, UciOutputKind::Option(_)));
+    }
+
+    #[test]
+    fn test_push_button_command() {
+        let cmd = UciCommand::PushButton("Clear Hash".to_string());
+        assert_eq!(cmd.to_uci_string(), "setoption name Clear Hash");
+    }
+
+    #[test]
+    fn test_go_commands() {
+        assert_eq!(
+            UciCommand::Go(GoParams::infinite()).to_uci_string(),
+            "go infinite"
+        );
+        assert_eq!(UciCommand::Go(GoParams::default()).to_uci_string(), "go");
+
+        let cmd = UciCommand::Go(GoParams {
+            searchmoves: vec!["e2e4".to_string(), "d2d4".to_string()],
+            depth: Some(12),
+            nodes: Some(500000),
+            ..GoParams::default()
+        });
+        assert_eq!(
+            cmd.to_uci_string(),
+            "go searchmoves e2e4 d2d4 depth 12 nodes 500000"
+        );
+
+        let cmd = UciCommand::Go(GoParams {
+            ponder: true,
+            wtime: Some(60000),
+            btime: Some(59000),
+            winc: Some(1000),
+            binc: Some(1000),
+            movestogo: Some(20),
+            ..GoParams::default()
+        });
+        assert_eq!(
+            cmd.to_uci_string(),
+            "go ponder wtime 60000 btime 59000 winc 1000 binc 1000 movestogo 20"
+        );
+        assert_eq!(UciCommand::PonderHit.to_uci_string(), "ponderhit");
+    }
+
+    #[test]
+    fn test_analysis_limits() {
+        assert_eq!(
+            UciCommand::Go(AnalysisLimit::MoveTime(5000).go_params()).to_uci_string(),
+            "go movetime 5000"
+        );
+        assert_eq!(
+            UciCommand::Go(AnalysisLimit::Mate(3).go_params()).to_uci_string(),
+            "go mate 3"
+        );
+        assert_eq!(AnalysisLimit::Infinite.go_params(), GoParams::infinite());
+
+        assert_eq!(AnalysisLimit::Depth(20).label(), "Depth 20");
+        assert_eq!(AnalysisLimit::MoveTime(5000).label(), "5 s");
+        assert_eq!(AnalysisLimit::MoveTime(250).label(), "250 ms");
+        assert_eq!(AnalysisLimit::Nodes(1_000_000).label(), "1M nodes");
+        assert_eq!(AnalysisLimit::Nodes(100_000).label(), "100K nodes");
+        assert_eq!(AnalysisLimit::Nodes(1_500).label(), "1500 nodes");
+    }
+
     // UciInfo parsing tests
     #[test]
     fn test_parse_uci_info_basic() {
@@ -500,6 +980,16 @@ mod tests {
         assert_eq!(score.display(), "-M2");
     }
 
+    #[test]
+    fn test_score_for_white() {
+        assert_eq!(
+            Score::Centipawns(35).for_white(false),
+            Score::Centipawns(35)
+        );
+        assert_eq!(Score::Centipawns(35).for_white(true), Score::Centipawns(-35));
+        assert_eq!(Score::Mate(2).for_white(true), Score::Mate(-2));
+    }
+
     #[test]
     fn test_score_as_centipawns() {
         assert_eq!(Score::Centipawns(100).as_centipawns(), 100);
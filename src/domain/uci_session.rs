@@ -0,0 +1,434 @@
+//! UCI session - sequences commands to an engine according to its state.
+//!
+//! The engine must finish the handshake (`uciok`, `readyok`) before it is
+//! configured or searches, options may only change while it is idle, and a
+//! new search can only start once the previous one has sent its `bestmove`.
+//! The session queues requests until the engine is in the right state and
+//! collects the commands to send in an outbox for the caller to write.
+//! This is a pure domain module with no I/O; the caller supplies the time.
+
+use std::fmt;
+use std::mem;
+use std::time::{Duration, Instant};
+
+use super::uci::{UciCommand, UciInfo, UciOutputKind};
+
+/// How long the engine may take to answer `uci`
+const UCI_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long the engine may take to answer `isready` (may allocate a large hash table)
+const READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long the engine may take to send `bestmove` after `stop`
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where the engine is in the UCI protocol
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UciState {
+    /// `uci` sent, waiting for `uciok`
+    Starting,
+    /// `isready` sent, waiting for `readyok`
+    Syncing,
+    /// Idle and ready for a search
+    Ready,
+    /// Searching a position
+    Searching,
+    /// `stop` sent, waiting for the search's `bestmove`
+    Stopping,
+}
+
+/// A search to run: the position to set up and how to search it
+#[derive(Clone, Debug)]
+pub struct SearchRequest {
+    /// The `position` command
+    pub position: UciCommand,
+    /// The `go` command
+    pub go: UciCommand,
+}
+
+/// Engine output the session passes on to its owner
+#[derive(Clone, Debug)]
+pub enum SessionEvent {
+    /// The handshake has finished and the engine is configured
+    Ready,
+    /// Analysis for the current search
+    Info(UciInfo),
+    /// The current search finished with a best move (the text after `bestmove`)
+    BestMove(String),
+}
+
+/// Error returned when the engine stops responding
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UciSessionError {
+    /// No answer while waiting in the given state
+    Timeout(UciState),
+}
+
+impl fmt::Display for UciSessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UciSessionError::Timeout(state) => {
+                let command = match state {
+                    UciState::Starting => "uci",
+                    UciState::Syncing => "isready",
+                    UciState::Stopping => "stop",
+                    UciState::Ready | UciState::Searching => "a command",
+                };
+                write!(f, "Engine did not respond to \"{}\" in time", command)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UciSessionError {}
+
+/// The state of one engine process's UCI conversation
+#[derive(Debug)]
+pub struct UciSession {
+    state: UciState,
+    /// Whether the handshake has finished (the first `readyok`)
+    handshake_done: bool,
+    /// When the session entered a state that waits for an answer
+    waiting_since: Instant,
+    /// Commands sent once `uciok` arrives (initial options)
+    setup: Vec<UciCommand>,
+    /// Option changes waiting for the engine to become idle
+    pending_commands: Vec<UciCommand>,
+    /// The search running now (kept so it can resume after option changes)
+    current_search: Option<SearchRequest>,
+    /// A search waiting for the engine to become idle
+    pending_search: Option<SearchRequest>,
+    /// Commands to send to the engine
+    outbox: Vec<UciCommand>,
+}
+
+impl UciSession {
+    /// Begin the handshake. `setup` is sent once the engine has answered `uci`.
+    pub fn start(setup: Vec<UciCommand>, now: Instant) -> Self {
+        Self {
+            state: UciState::Starting,
+            handshake_done: false,
+            waiting_since: now,
+            setup,
+            pending_commands: Vec::new(),
+            current_search: None,
+            pending_search: None,
+            outbox: vec![UciCommand::Uci],
+        }
+    }
+
+    /// Current protocol state
+    #[cfg(test)]
+    pub fn state(&self) -> UciState {
+        self.state
+    }
+
+    /// Whether the handshake has finished
+    pub fn is_ready(&self) -> bool {
+        self.handshake_done
+    }
+
+    /// Take the commands that should be written to the engine, in order
+    pub fn drain_commands(&mut self) -> Vec<UciCommand> {
+        mem::take(&mut self.outbox)
+    }
+
+    /// Search a new position, replacing any running or queued search
+    pub fn search(&mut self, request: SearchRequest, now: Instant) {
+        match self.state {
+            UciState::Ready => self.begin_search(request),
+            UciState::Searching => {
+                self.pending_search = Some(request);
+                self.send_stop(now);
+            }
+            UciState::Starting | UciState::Syncing | UciState::Stopping => {
+                self.pending_search = Some(request);
+            }
+        }
+    }
+
+    /// Stop searching without starting another search
+    pub fn stop(&mut self, now: Instant) {
+        self.pending_search = None;
+        self.current_search = None;
+        if self.state == UciState::Searching {
+            self.send_stop(now);
+        }
+    }
+
+    /// Send option commands (`setoption`) once the engine is idle.
+    /// A running search is stopped and resumed after the options are applied.
+    pub fn configure(&mut self, commands: Vec<UciCommand>, now: Instant) {
+        self.pending_commands.extend(commands);
+        match self.state {
+            UciState::Ready => self.flush(now),
+            UciState::Searching => {
+                if self.pending_search.is_none() {
+                    self.pending_search = self.current_search.clone();
+                }
+                self.send_stop(now);
+            }
+            UciState::Starting | UciState::Syncing | UciState::Stopping => {}
+        }
+    }
+
+    /// Feed a line of engine output into the session
+    pub fn handle_output(&mut self, output: &UciOutputKind, now: Instant) -> Option<SessionEvent> {
+        match (output, self.state) {
+            (UciOutputKind::UciOk, UciState::Starting) => {
+                self.outbox.append(&mut self.setup);
+                self.send_isready(now);
+                None
+            }
+            (UciOutputKind::ReadyOk, UciState::Syncing) => {
+                self.state = UciState::Ready;
+                self.flush(now);
+                if self.handshake_done {
+                    None
+                } else {
+                    self.handshake_done = true;
+                    Some(SessionEvent::Ready)
+                }
+            }
+            (UciOutputKind::Info(info), UciState::Searching) => {
+                Some(SessionEvent::Info(UciInfo::parse(info)))
+            }
+            (UciOutputKind::BestMove(best), UciState::Searching) => {
+                // The search finished on its own
+                self.current_search = None;
+                self.state = UciState::Ready;
+                self.flush(now);
+                Some(SessionEvent::BestMove(best.clone()))
+            }
+            (UciOutputKind::BestMove(_), UciState::Stopping) => {
+                // The stopped search's result is stale
+                self.state = UciState::Ready;
+                self.flush(now);
+                None
+            }
+            // Anything else is stale (e.g. info from a search being stopped) or not ours
+            _ => None,
+        }
+    }
+
+    /// Check whether the engine has taken too long to answer
+    pub fn check_timeout(&self, now: Instant) -> Result<(), UciSessionError> {
+        let limit = match self.state {
+            UciState::Starting => UCI_TIMEOUT,
+            UciState::Syncing => READY_TIMEOUT,
+            UciState::Stopping => STOP_TIMEOUT,
+            UciState::Ready | UciState::Searching => return Ok(()),
+        };
+        if now.duration_since(self.waiting_since) > limit {
+            Err(UciSessionError::Timeout(self.state))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Now idle: apply queued options first, then start the queued search
+    fn flush(&mut self, now: Instant) {
+        if !self.pending_commands.is_empty() {
+            self.outbox.append(&mut self.pending_commands);
+            self.send_isready(now);
+        } else if let Some(request) = self.pending_search.take() {
+            self.begin_search(request);
+        }
+    }
+
+    fn begin_search(&mut self, request: SearchRequest) {
+        self.outbox.push(request.position.clone());
+        self.outbox.push(request.go.clone());
+        self.current_search = Some(request);
+        self.state = UciState::Searching;
+    }
+
+    fn send_isready(&mut self, now: Instant) {
+        self.outbox.push(UciCommand::IsReady);
+        self.state = UciState::Syncing;
+        self.waiting_since = now;
+    }
+
+    fn send_stop(&mut self, now: Instant) {
+        self.outbox.push(UciCommand::Stop);
+        self.state = UciState::Stopping;
+        self.waiting_since = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::uci::GoParams;
+
+    fn commands(session: &mut UciSession) -> Vec<String> {
+        session
+            .drain_commands()
+            .iter()
+            .map(UciCommand::to_uci_string)
+            .collect()
+    }
+
+    fn search(fen: &str) -> SearchRequest {
+        SearchRequest {
+            position: UciCommand::Position {
+                fen: Some(fen.to_string()),
+                moves: vec![],
+            },
+            go: UciCommand::Go(GoParams::infinite()),
+        }
+    }
+
+    fn option(name: &str, value: &str) -> UciCommand {
+        UciCommand::SetOption {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    /// A session that has finished the handshake
+    fn ready_session(now: Instant) -> UciSession {
+        let mut session = UciSession::start(vec![], now);
+        session.handle_output(&UciOutputKind::UciOk, now);
+        session.handle_output(&UciOutputKind::ReadyOk, now);
+        session.drain_commands();
+        session
+    }
+
+    #[test]
+    fn test_handshake_waits_for_uciok_and_readyok() {
+        let now = Instant::now();
+        let mut session = UciSession::start(vec![option("Hash", "64")], now);
+        assert_eq!(commands(&mut session), vec!["uci"]);
+
+        // A search requested during the handshake waits for it to finish
+        session.search(search("8/8/8/8/8/8/8/K6k w - - 0 1"), now);
+        assert!(commands(&mut session).is_empty());
+
+        assert!(session.handle_output(&UciOutputKind::UciOk, now).is_none());
+        assert_eq!(
+            commands(&mut session),
+            vec!["setoption name Hash value 64", "isready"]
+        );
+        assert_eq!(session.state(), UciState::Syncing);
+
+        let event = session.handle_output(&UciOutputKind::ReadyOk, now);
+        assert!(matches!(event, Some(SessionEvent::Ready)));
+        assert!(session.is_ready());
+        assert_eq!(
+            commands(&mut session),
+            vec!["position fen 8/8/8/8/8/8/8/K6k w - - 0 1", "go infinite"]
+        );
+        assert_eq!(session.state(), UciState::Searching);
+    }
+
+    #[test]
+    fn test_new_search_waits_for_bestmove() {
+        let now = Instant::now();
+        let mut session = ready_session(now);
+        session.search(search("first"), now);
+        commands(&mut session);
+
+        session.search(search("second"), now);
+        assert_eq!(commands(&mut session), vec!["stop"]);
+        assert_eq!(session.state(), UciState::Stopping);
+
+        // A third position replaces the queued one
+        session.search(search("third"), now);
+        assert!(commands(&mut session).is_empty());
+
+        let stale = UciOutputKind::Info("depth 20 score cp 10 pv e2e4".to_string());
+        assert!(session.handle_output(&stale, now).is_none());
+        assert!(
+            session
+                .handle_output(&UciOutputKind::BestMove("e2e4".to_string()), now)
+                .is_none()
+        );
+        assert_eq!(
+            commands(&mut session),
+            vec!["position fen third", "go infinite"]
+        );
+
+        let info = UciOutputKind::Info("depth 5 score cp 10 pv d2d4".to_string());
+        assert!(matches!(
+            session.handle_output(&info, now),
+            Some(SessionEvent::Info(_))
+        ));
+    }
+
+    #[test]
+    fn test_configure_during_search_resumes_it() {
+        let now = Instant::now();
+        let mut session = ready_session(now);
+        session.search(search("fen"), now);
+        commands(&mut session);
+
+        session.configure(vec![option("Threads", "4")], now);
+        assert_eq!(commands(&mut session), vec!["stop"]);
+
+        session.handle_output(&UciOutputKind::BestMove("e2e4".to_string()), now);
+        assert_eq!(
+            commands(&mut session),
+            vec!["setoption name Threads value 4", "isready"]
+        );
+
+        assert!(
+            session
+                .handle_output(&UciOutputKind::ReadyOk, now)
+                .is_none()
+        );
+        assert_eq!(
+            commands(&mut session),
+            vec!["position fen fen", "go infinite"]
+        );
+    }
+
+    #[test]
+    fn test_stop_and_finished_search() {
+        let now = Instant::now();
+        let mut session = ready_session(now);
+        session.stop(now);
+        assert!(commands(&mut session).is_empty());
+
+        session.search(search("fen"), now);
+        commands(&mut session);
+        let event = session.handle_output(
+            &UciOutputKind::BestMove("e2e4 ponder e7e5".to_string()),
+            now,
+        );
+        assert!(matches!(event, Some(SessionEvent::BestMove(best)) if best == "e2e4 ponder e7e5"));
+        assert_eq!(session.state(), UciState::Ready);
+
+        session.search(search("fen"), now);
+        commands(&mut session);
+        session.stop(now);
+        assert_eq!(commands(&mut session), vec!["stop"]);
+        session.handle_output(&UciOutputKind::BestMove("e2e4".to_string()), now);
+        assert!(commands(&mut session).is_empty());
+        assert_eq!(session.state(), UciState::Ready);
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let now = Instant::now();
+        let session = UciSession::start(vec![], now);
+        assert!(session.check_timeout(now + Duration::from_secs(1)).is_ok());
+        assert_eq!(
+            session.check_timeout(now + Duration::from_secs(11)),
+            Err(UciSessionError::Timeout(UciState::Starting))
+        );
+
+        let mut session = ready_session(now);
+        session.search(search("fen"), now);
+        assert!(
+            session
+                .check_timeout(now + Duration::from_secs(600))
+                .is_ok()
+        );
+        session.stop(now);
+        assert_eq!(
+            session.check_timeout(now + Duration::from_secs(6)),
+            Err(UciSessionError::Timeout(UciState::Stopping))
+        );
+    }
+}
@@ -7,26 +7,35 @@
 //! - Engine I/O runs on OS threads (reader/writer)
 //! - A GPUI background task polls the event channel and pushes updates to the UI
 //! - This ensures the UI updates immediately when engine output arrives
+//! - A `UciSession` decides when commands may be sent and which output is current
 
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use gpui::{AsyncApp, Context, Task, WeakEntity};
 
-use crate::domain::uci::{UciCommand, UciInfo, UciOutput, UciOutputKind};
-
-/// Hardcoded engine path (will be configurable later)
-const ENGINE_PATH: &str = "/opt/homebrew/bin/stockfish";
+use crate::domain::uci::{
+    AnalysisLimit, EngineIdentity, GoParams, UciCommand, UciInfo, UciOption, UciOptionType,
+    UciOutput, UciOutputKind,
+};
+use crate::domain::uci_session::{SearchRequest, SessionEvent, UciSession};
+use crate::domain::{EngineProfile, OptionSetting};
 
 /// Maximum number of output lines to keep in history
 const MAX_OUTPUT_LINES: usize = 100;
 
-/// Number of principal variations to request from engine
-const MULTI_PV: u32 = 3;
+/// Number of principal variations to request from engine by default
+const DEFAULT_MULTI_PV: u32 = 3;
+
+/// Most principal variations to offer when the engine doesn't declare a limit
+const FALLBACK_MAX_MULTI_PV: u32 = 10;
+
+/// Name of the UCI option controlling the number of principal variations
+const MULTI_PV_OPTION: &str = "MultiPV";
 
 /// Messages sent from the engine reader thread to the model
 #[derive(Debug)]
@@ -43,16 +52,42 @@ pub enum EngineEvent {
 pub struct EngineModel {
     /// Whether the engine is currently running
     running: bool,
-    /// Whether the engine is currently analyzing
+    /// Whether analysis has been requested (the engine may still be starting).
+    /// Cleared when a limited search finishes.
     analyzing: bool,
+    /// Whether the last search finished on its own (not stopped or replaced)
+    search_finished: bool,
+    /// Whether the current or last search is analysis rather than a search for
+    /// a game's move (`start_search`)
+    analysis_search: bool,
+    /// How long to search each position
+    analysis_limit: AnalysisLimit,
+    /// Protocol state of the running engine
+    session: Option<UciSession>,
+    /// Why the engine last failed (shown until it is started again)
+    error: Option<String>,
     /// Recent output lines from the engine (for display)
     output_lines: Vec<UciOutput>,
     /// Current analysis lines (keyed by multipv number, 1-indexed)
     analysis_lines: HashMap<u32, UciInfo>,
+    /// Number of principal variations requested
+    multi_pv: u32,
     /// Whether it's black's turn (for flipping eval display)
     black_to_move: bool,
     /// Current FEN being analyzed (if any)
     current_fen: Option<String>,
+    /// The `position` command for the current FEN, including the game history
+    current_position: Option<UciCommand>,
+    /// Best move (in UCI notation) of the last search of the current FEN that finished
+    best_move: Option<String>,
+    /// Profile of the running engine, including options changed since it started
+    profile: Option<EngineProfile>,
+    /// Name and author reported by the engine
+    identity: EngineIdentity,
+    /// Options declared so far during the UCI handshake
+    declared_options: Vec<UciOption>,
+    /// Options the engine supports (available once it has sent "uciok")
+    options: Vec<UciOption>,
     /// Channel receiver for engine events (polled by background task)
     event_receiver: Option<Receiver<EngineEvent>>,
     /// Channel sender for commands to engine writer thread
@@ -68,10 +103,22 @@ impl EngineModel {
         Self {
             running: false,
             analyzing: false,
+            search_finished: false,
+            analysis_search: false,
+            analysis_limit: AnalysisLimit::Infinite,
+            session: None,
+            error: None,
             output_lines: Vec::new(),
             analysis_lines: HashMap::new(),
+            multi_pv: DEFAULT_MULTI_PV,
             black_to_move: false,
             current_fen: None,
+            current_position: None,
+            best_move: None,
+            profile: None,
+            identity: EngineIdentity::default(),
+            declared_options: Vec::new(),
+            options: Vec::new(),
             event_receiver: None,
             command_sender: None,
             process: None,
@@ -89,6 +136,16 @@ impl EngineModel {
         self.analyzing
     }
 
+    /// Check if the engine has finished the UCI handshake
+    pub fn is_ready(&self) -> bool {
+        self.session.as_ref().is_some_and(UciSession::is_ready)
+    }
+
+    /// Get the error that stopped the engine, if any
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
     /// Get the output lines for display
     pub fn output_lines(&self) -> &[UciOutput] {
         &self.output_lines
@@ -101,8 +158,72 @@ impl EngineModel {
         lines
     }
 
+    /// Get the number of principal variations requested
+    pub fn multi_pv(&self) -> u32 {
+        self.multi_pv
+    }
+
+    /// Get the largest number of principal variations the engine supports
+    pub fn max_multi_pv(&self) -> u32 {
+        self.options
+            .iter()
+            .find(|option| option.name.eq_ignore_ascii_case(MULTI_PV_OPTION))
+            .and_then(|option| match option.option_type {
+                UciOptionType::Spin { max, .. } => u32::try_from(max).ok(),
+                _ => None,
+            })
+            .unwrap_or(FALLBACK_MAX_MULTI_PV)
+            .max(1)
+    }
+
+    /// Change the number of principal variations, restarting the current search
+    pub fn set_multi_pv(&mut self, multi_pv: u32) {
+        let multi_pv = multi_pv.clamp(1, self.max_multi_pv());
+        if multi_pv == self.multi_pv {
+            return;
+        }
+        self.set_options(&[OptionSetting {
+            name: MULTI_PV_OPTION.to_string(),
+            value: multi_pv.to_string(),
+        }]);
+        // A running search resumes with the new value; a finished analysis is searched again
+        if self.analysis_search
+            && self.search_finished
+            && let (Some(fen), Some(position)) =
+                (self.current_fen.clone(), self.current_position.clone())
+        {
+            self.start_analysis(&fen, position);
+        }
+    }
+
+    /// Record a new MultiPV value and drop lines beyond it
+    fn apply_multi_pv(&mut self, multi_pv: u32) {
+        self.multi_pv = multi_pv;
+        self.analysis_lines.retain(|pv_num, _| *pv_num <= multi_pv);
+    }
+
+    /// Get how long each position is searched
+    pub fn analysis_limit(&self) -> AnalysisLimit {
+        self.analysis_limit
+    }
+
+    /// Change how long each position is searched. Analysis that is running or
+    /// finished on its own searches the current position again with the new limit.
+    pub fn set_analysis_limit(&mut self, limit: AnalysisLimit) {
+        if limit == self.analysis_limit {
+            return;
+        }
+        self.analysis_limit = limit;
+        if self.analysis_search
+            && (self.analyzing || self.search_finished)
+            && let (Some(fen), Some(position)) =
+                (self.current_fen.clone(), self.current_position.clone())
+        {
+            self.start_analysis(&fen, position);
+        }
+    }
+
     /// Get the best (first) analysis line
-    #[allow(dead_code)] // Reserved for future use
     pub fn best_analysis(&self) -> Option<&UciInfo> {
         self.analysis_lines.get(&1)
     }
@@ -117,25 +238,107 @@ impl EngineModel {
         self.current_fen.as_deref()
     }
 
-    /// Start the engine process
+    /// Get the best move of the current FEN once its search has finished
+    pub fn best_move(&self) -> Option<&str> {
+        self.best_move.as_deref()
+    }
+
+    /// Get the options the engine supports (empty until the UCI handshake is done)
+    pub fn options(&self) -> &[UciOption] {
+        &self.options
+    }
+
+    /// Get the profile the running engine was started with
+    pub fn profile(&self) -> Option<&EngineProfile> {
+        self.profile.as_ref()
+    }
+
+    /// Get the name and author the engine reported
+    pub fn identity(&self) -> &EngineIdentity {
+        &self.identity
+    }
+
+    /// Name to attribute analysis to: the engine's own name (with version), else the profile's
+    pub fn display_name(&self) -> Option<&str> {
+        self.identity
+            .name
+            .as_deref()
+            .or_else(|| self.profile.as_ref().map(|profile| profile.name.as_str()))
+    }
+
+    /// A comment recording the best line's evaluation and the engine that produced it,
+    /// e.g. "Stockfish 17: +0.35 (depth 24)"
+    pub fn evaluation_comment(&self) -> Option<String> {
+        let best = self.analysis_lines.get(&1)?;
+        let score = best.score?.for_white(self.black_to_move);
+        let mut comment = format!("{}: {}", self.display_name()?, score.display());
+        if let Some(depth) = best.depth {
+            comment.push_str(&format!(" (depth {})", depth));
+        }
+        Some(comment)
+    }
+
+    /// Get the current value of an option: the profile's value, else the engine default
+    pub fn option_value(&self, option: &UciOption) -> Option<String> {
+        self.profile
+            .as_ref()
+            .and_then(|profile| profile.option_value(&option.name))
+            .map(str::to_string)
+            .or_else(|| option.default_value())
+    }
+
+    /// Send option values to the engine.
+    /// The session applies them once the engine is idle, resuming any running analysis.
+    pub fn set_options(&mut self, options: &[OptionSetting]) {
+        let commands = options
+            .iter()
+            .map(|option| UciCommand::SetOption {
+                name: option.name.clone(),
+                value: option.value.clone(),
+            })
+            .collect();
+        for option in options {
+            if let Some(profile) = self.profile.as_mut() {
+                profile.set_option(&option.name, &option.value);
+            }
+            if option.name.eq_ignore_ascii_case(MULTI_PV_OPTION)
+                && let Ok(multi_pv) = option.value.parse()
+            {
+                self.apply_multi_pv(multi_pv);
+            }
+        }
+        self.configure(commands);
+    }
+
+    /// Trigger a button option (e.g. "Clear Hash")
+    pub fn push_button(&mut self, name: &str) {
+        self.configure(vec![UciCommand::PushButton(name.to_string())]);
+    }
+
+    /// Queue option commands on the session
+    fn configure(&mut self, commands: Vec<UciCommand>) {
+        if let Some(session) = self.session.as_mut() {
+            session.configure(commands, Instant::now());
+        }
+        self.flush_commands();
+    }
+
+    /// Start the engine process described by a profile
     /// 
     /// Must be called from a Context<EngineModel> to spawn the background polling task.
-    pub fn start(&mut self, cx: &mut Context<Self>) -> Result<(), String> {
+    pub fn start(&mut self, profile: &EngineProfile, cx: &mut Context<Self>) -> Result<(), String> {
         if self.running {
             return Ok(());
         }
 
-        // Spawn the engine process
-        let mut child = Command::new(ENGINE_PATH)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .spawn()
-            .map_err(|e| format!("Failed to start engine: {}", e))?;
-
-        // Take ownership of stdin/stdout
-        let stdin = child.stdin.take().ok_or("Failed to open stdin")?;
-        let stdout = child.stdout.take().ok_or("Failed to open stdout")?;
+        // Spawn the engine process; a failure is kept to be shown in place of the analysis
+        let (child, stdin, stdout) = match Self::spawn(profile) {
+            Ok(process) => process,
+            Err(e) => {
+                self.error = Some(e.clone());
+                return Err(e);
+            }
+        };
 
         // Create channels for communication
         let (event_tx, event_rx) = mpsc::channel::<EngineEvent>();
@@ -175,6 +378,11 @@ impl EngineModel {
         });
 
         self.process = Some(child);
+        self.profile = Some(profile.clone());
+        self.identity = EngineIdentity::default();
+        self.error = None;
+        self.declared_options.clear();
+        self.options.clear();
         self.event_receiver = Some(event_rx);
         self.command_sender = Some(cmd_tx);
         self.running = true;
@@ -185,17 +393,26 @@ impl EngineModel {
         });
         self._poll_task = Some(poll_task);
 
-        // Initialize UCI
-        self.send_command(UciCommand::Uci);
-        self.send_command(UciCommand::IsReady);
-        
-        // Set MultiPV option
-        self.send_command(UciCommand::SetOption {
-            name: "MultiPV".to_string(),
-            value: MULTI_PV.to_string(),
-        });
+        // Initialize UCI; options are sent once the engine has answered "uci"
+        let mut setup = vec![UciCommand::SetOption {
+            name: MULTI_PV_OPTION.to_string(),
+            value: self.multi_pv.to_string(),
+        }];
+        // The profile's options may override the defaults above
+        setup.extend(profile.options.iter().map(|option| UciCommand::SetOption {
+            name: option.name.clone(),
+            value: option.value.clone(),
+        }));
+        if let Some(multi_pv) = profile
+            .option_value(MULTI_PV_OPTION)
+            .and_then(|value| value.parse().ok())
+        {
+            self.apply_multi_pv(multi_pv);
+        }
+        self.session = Some(UciSession::start(setup, Instant::now()));
+        self.flush_commands();
 
-        self.add_output("[Engine started]".to_string());
+        self.add_output(format!("[{} started]", profile.name));
 
         Ok(())
     }
@@ -216,7 +433,8 @@ impl EngineModel {
                 
                 // Drain all available events from the channel
                 let had_events = engine.process_pending_events();
-                if had_events {
+                let timed_out = engine.check_timeout();
+                if had_events || timed_out {
                     cx.notify(); // Trigger UI re-render
                 }
                 
@@ -256,6 +474,8 @@ impl EngineModel {
                 EngineEvent::Exited => {
                     self.running = false;
                     self.analyzing = false;
+                    self.search_finished = false;
+                    self.session = None;
                     self.add_output("[Engine exited]".to_string());
                 }
                 EngineEvent::Error(e) => {
@@ -267,6 +487,42 @@ impl EngineModel {
         true
     }
 
+    /// Stop the engine if it has stopped answering.
+    /// Returns true if it timed out.
+    fn check_timeout(&mut self) -> bool {
+        let Some(session) = &self.session else {
+            return false;
+        };
+        let Err(e) = session.check_timeout(Instant::now()) else {
+            return false;
+        };
+        self.add_output(format!("[Error: {}]", e));
+        self.stop();
+        self.error = Some(e.to_string());
+        true
+    }
+
+    /// Launch a profile's executable with piped stdin/stdout
+    fn spawn(profile: &EngineProfile) -> Result<(Child, ChildStdin, ChildStdout), String> {
+        let mut command = Command::new(&profile.path);
+        command
+            .args(&profile.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        if let Some(dir) = &profile.working_dir {
+            command.current_dir(dir);
+        }
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", profile.path.display(), e))?;
+
+        // Take ownership of stdin/stdout
+        let stdin = child.stdin.take().ok_or("Failed to open stdin")?;
+        let stdout = child.stdout.take().ok_or("Failed to open stdout")?;
+        Ok((child, stdin, stdout))
+    }
+
     /// Stop the engine process
     pub fn stop(&mut self) {
         if !self.running {
@@ -278,8 +534,9 @@ impl EngineModel {
             self.stop_analysis();
         }
 
-        // Send quit command
+        // Send quit command (directly: the engine may be mid-handshake)
         self.send_command(UciCommand::Quit);
+        self.session = None;
 
         // Clean up channels (this will cause the polling loop to exit)
         self.command_sender = None;
@@ -296,21 +553,35 @@ impl EngineModel {
 
         self.running = false;
         self.analyzing = false;
+        self.search_finished = false;
+        // A restarted engine must be sent the position again
+        self.current_fen = None;
+        self.current_position = None;
+        self.best_move = None;
         self.add_output("[Engine stopped]".to_string());
     }
 
-    /// Start analyzing the given FEN position
-    pub fn start_analysis(&mut self, fen: &str) {
+    /// Start analyzing the position with the given FEN.
+    /// `position` is the `position` command reaching it, ideally with the game's moves
+    /// so the engine can detect repetitions.
+    pub fn start_analysis(&mut self, fen: &str, position: UciCommand) {
+        self.start_search(fen, position, self.analysis_limit.go_params());
+        self.analysis_search = true;
+    }
+
+    /// Search the position with the given FEN using explicit `go` parameters,
+    /// e.g. the clock times in a game against the engine.
+    /// The move it chooses is available from `best_move` once the search finishes.
+    pub fn start_search(&mut self, fen: &str, position: UciCommand, go: GoParams) {
         if !self.running {
             return;
         }
 
-        // Stop previous analysis if any
-        if self.analyzing {
-            self.send_command(UciCommand::Stop);
-        }
-
         self.current_fen = Some(fen.to_string());
+        self.current_position = Some(position.clone());
+        self.best_move = None;
+        self.search_finished = false;
+        self.analysis_search = false;
         self.analysis_lines.clear(); // Clear previous analysis
         
         // Parse side to move from FEN (second field)
@@ -320,26 +591,49 @@ impl EngineModel {
             .map(|s| s == "b")
             .unwrap_or(false);
 
-        // Send position and start analysis
-        self.send_command(UciCommand::Position {
-            fen: Some(fen.to_string()),
-            moves: vec![],
-        });
-        self.send_command(UciCommand::GoInfinite);
+        // Search the position (the session stops the previous search first)
+        let request = SearchRequest {
+            position,
+            go: UciCommand::Go(go),
+        };
+        if let Some(session) = self.session.as_mut() {
+            session.search(request, Instant::now());
+        }
+        self.flush_commands();
 
         self.analyzing = true;
     }
 
     /// Stop the current analysis
     pub fn stop_analysis(&mut self) {
+        self.search_finished = false;
         if !self.analyzing {
             return;
         }
 
-        self.send_command(UciCommand::Stop);
+        if let Some(session) = self.session.as_mut() {
+            session.stop(Instant::now());
+        }
+        self.flush_commands();
         self.analyzing = false;
     }
 
+    /// Tell the engine the next search is from a different game
+    pub fn new_game(&mut self) {
+        self.configure(vec![UciCommand::UciNewGame]);
+    }
+
+    /// Send the commands the session is ready to send
+    fn flush_commands(&mut self) {
+        let commands = match self.session.as_mut() {
+            Some(session) => session.drain_commands(),
+            None => return,
+        };
+        for cmd in commands {
+            self.send_command(cmd);
+        }
+    }
+
     /// Send a UCI command to the engine
     fn send_command(&self, cmd: UciCommand) {
         let cmd_str = cmd.to_uci_string();
@@ -352,15 +646,39 @@ impl EngineModel {
     fn add_output(&mut self, line: String) {
         let output = UciOutput::new(line);
 
-        // If this is an info line, try to parse it and update analysis
-        if let UciOutputKind::Info(info_str) = &output.kind {
-            let info = UciInfo::parse(info_str);
+        match &output.kind {
+            UciOutputKind::Id(id) => self.identity.update(id),
+            // Options are declared one per line before "uciok"
+            UciOutputKind::Option(option) => self.declared_options.push(option.clone()),
+            UciOutputKind::UciOk => self.options = std::mem::take(&mut self.declared_options),
+            _ => {}
+        }
+
+        // The session drops output from searches that have been stopped
+        let event = self
+            .session
+            .as_mut()
+            .and_then(|session| session.handle_output(&output.kind, Instant::now()));
+        match event {
             // Only update if this has meaningful analysis (depth + score + pv)
-            if info.has_analysis() {
+            Some(SessionEvent::Info(info)) if info.has_analysis() => {
                 let pv_num = info.multipv.unwrap_or(1);
                 self.analysis_lines.insert(pv_num, info);
             }
+            // A limited search reached its limit; keep its lines on display
+            Some(SessionEvent::BestMove(best)) => {
+                self.analyzing = false;
+                self.search_finished = true;
+                // "bestmove e2e4 ponder e7e5"; "(none)" or "0000" when there is no legal move
+                self.best_move = best
+                    .split_whitespace()
+                    .next()
+                    .filter(|uci| *uci != "(none)" && *uci != "0000")
+                    .map(str::to_string);
+            }
+            _ => {}
         }
+        self.flush_commands();
 
         self.output_lines.push(output);
 
@@ -389,3 +707,94 @@ impl Drop for EngineModel {
         self.stop();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An engine model that has finished the handshake, without a process behind it
+    fn ready_engine() -> EngineModel {
+        let mut engine = EngineModel::new();
+        engine.running = true;
+        engine.session = Some(UciSession::start(Vec::new(), Instant::now()));
+        engine.add_output("uciok".to_string());
+        engine.add_output("readyok".to_string());
+        assert!(engine.is_ready());
+        engine
+    }
+
+    fn start_position() -> UciCommand {
+        UciCommand::Position {
+            fen: None,
+            moves: Vec::new(),
+        }
+    }
+
+    const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn test_multi_pv_searches_finished_analysis_again() {
+        let mut engine = ready_engine();
+        engine.set_analysis_limit(AnalysisLimit::Depth(10));
+        engine.start_analysis(START_FEN, start_position());
+        engine.add_output("bestmove e2e4".to_string());
+        assert!(!engine.is_analyzing());
+        assert_eq!(engine.best_move(), Some("e2e4"));
+
+        engine.set_multi_pv(2);
+        assert!(engine.is_analyzing());
+        assert_eq!(engine.best_move(), None);
+    }
+
+    #[test]
+    fn test_multi_pv_keeps_stopped_analysis_stopped() {
+        let mut engine = ready_engine();
+        engine.start_analysis(START_FEN, start_position());
+        engine.stop_analysis();
+        // The stopped search's bestmove doesn't count as finishing on its own
+        engine.add_output("bestmove e2e4".to_string());
+
+        engine.set_multi_pv(2);
+        assert_eq!(engine.multi_pv(), 2);
+        assert!(!engine.is_analyzing());
+    }
+
+    #[test]
+    fn test_analysis_limit_searches_running_analysis_again() {
+        let mut engine = ready_engine();
+        engine.start_analysis(START_FEN, start_position());
+
+        engine.set_analysis_limit(AnalysisLimit::Depth(10));
+        // The infinite search was stopped, so its bestmove is stale
+        engine.add_output("bestmove e2e4".to_string());
+        assert!(engine.is_analyzing());
+        assert_eq!(engine.best_move(), None);
+    }
+
+    #[test]
+    fn test_analysis_limit_keeps_stopped_analysis_stopped() {
+        let mut engine = ready_engine();
+        engine.start_analysis(START_FEN, start_position());
+        engine.stop_analysis();
+
+        engine.set_analysis_limit(AnalysisLimit::Depth(10));
+        assert_eq!(engine.analysis_limit(), AnalysisLimit::Depth(10));
+        assert!(!engine.is_analyzing());
+    }
+
+    #[test]
+    fn test_analysis_limit_keeps_game_search() {
+        let mut engine = ready_engine();
+        engine.start_search(START_FEN, start_position(), GoParams::default());
+
+        engine.set_analysis_limit(AnalysisLimit::Depth(10));
+        engine.add_output("bestmove e2e4".to_string());
+        assert_eq!(engine.best_move(), Some("e2e4"));
+
+        // A finished game search isn't replaced by analysis either
+        engine.set_analysis_limit(AnalysisLimit::Depth(12));
+        engine.set_multi_pv(3);
+        assert!(!engine.is_analyzing());
+        assert_eq!(engine.best_move(), Some("e2e4"));
+    }
+}
@@ -0,0 +1,379 @@
+//! Engine match model - plays a match between two engines and keeps the score.
+//!
+//! Each engine runs in its own `EngineModel`. The match searches with the engine
+//! whose turn it is, plays the move it finds, and appends each finished game
+//! to the output PGN file.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use gpui::{AppContext, Context, Entity, Subscription, Task};
+
+use super::EngineModel;
+use crate::domain::{
+    EngineProfile, GameOutcome, MatchGame, MatchScore, MatchSettings, Opening, PieceColor,
+    SprtVerdict, match_pairing,
+};
+
+/// How often the clocks are checked for a fallen flag
+const CLOCK_TICK: Duration = Duration::from_millis(100);
+
+/// The engine match model - runs one match at a time
+pub struct MatchModel {
+    /// Whether a match is being played
+    running: bool,
+    /// The two engines; the score is counted for the first
+    engines: Vec<Entity<EngineModel>>,
+    /// Display names of the engines, in the same order
+    names: Vec<String>,
+    openings: Vec<Opening>,
+    settings: Option<MatchSettings>,
+    /// File the finished games are appended to
+    output_path: Option<PathBuf>,
+    /// The game being played (None between games)
+    game: Option<MatchGame>,
+    /// Number of finished games
+    games_played: usize,
+    /// Whether the first engine has white in the current game
+    first_engine_white: bool,
+    /// FEN of the position searched for the current move
+    searched_fen: Option<String>,
+    /// When the search for the current move started
+    search_started: Option<Instant>,
+    score: MatchScore,
+    /// How the last finished game ended
+    last_outcome: Option<GameOutcome>,
+    /// Why the match ended early, or a problem while it ran
+    error: Option<String>,
+    _engine_subscriptions: Vec<Subscription>,
+    /// Checks the clocks while a match is running
+    _clock_task: Option<Task<()>>,
+}
+
+impl MatchModel {
+    pub fn new() -> Self {
+        Self {
+            running: false,
+            engines: Vec::new(),
+            names: Vec::new(),
+            openings: Vec::new(),
+            settings: None,
+            output_path: None,
+            game: None,
+            games_played: 0,
+            first_engine_white: true,
+            searched_fen: None,
+            search_started: None,
+            score: MatchScore::default(),
+            last_outcome: None,
+            error: None,
+            _engine_subscriptions: Vec::new(),
+            _clock_task: None,
+        }
+    }
+
+    /// Check if a match is being played
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Get the names of the first and second engine
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Get the settings of the current or last match
+    pub fn settings(&self) -> Option<&MatchSettings> {
+        self.settings.as_ref()
+    }
+
+    /// Get the first engine's results so far
+    pub fn score(&self) -> MatchScore {
+        self.score
+    }
+
+    /// Get the number of finished games
+    pub fn games_played(&self) -> usize {
+        self.games_played
+    }
+
+    /// Get the game being played
+    pub fn game(&self) -> Option<&MatchGame> {
+        self.game.as_ref()
+    }
+
+    /// Get the name of the engine playing a side in the current game
+    pub fn player(&self, color: PieceColor) -> Option<&str> {
+        let index = engine_index(color, self.first_engine_white);
+        self.names.get(index).map(String::as_str)
+    }
+
+    /// Get how the last finished game ended
+    pub fn last_outcome(&self) -> Option<GameOutcome> {
+        self.last_outcome
+    }
+
+    /// Get why the match ended early, if it did
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Start a match between two engine profiles, replacing any running match.
+    /// Games start from the openings in turn (the standard position if there are none),
+    /// and finished games are appended to `output_path`.
+    /// A failure to start an engine is also kept as the match's error.
+    pub fn start(
+        &mut self,
+        profiles: [EngineProfile; 2],
+        openings: Vec<Opening>,
+        settings: MatchSettings,
+        output_path: Option<PathBuf>,
+        cx: &mut Context<Self>,
+    ) -> Result<(), String> {
+        self.stop(cx);
+
+        self.names = profiles.iter().map(|profile| profile.name.clone()).collect();
+        self.openings = if openings.is_empty() {
+            vec![Opening::default()]
+        } else {
+            openings
+        };
+        self.settings = Some(settings);
+        self.output_path = output_path;
+        self.game = None;
+        self.games_played = 0;
+        self.score = MatchScore::default();
+        self.last_outcome = None;
+        self.error = None;
+        self.engines = Vec::new();
+        self._engine_subscriptions = Vec::new();
+
+        let mut engines = Vec::new();
+        for profile in &profiles {
+            let engine = cx.new(|_| EngineModel::new());
+            let result = engine.update(cx, |engine, cx| {
+                engine.start(profile, cx)?;
+                // Only the best line matters, and extra lines weaken the search
+                engine.set_multi_pv(1);
+                Ok::<_, String>(())
+            });
+            // Engines already started are stopped as they are dropped
+            if let Err(e) = result {
+                self.error = Some(e.clone());
+                return Err(e);
+            }
+            engines.push(engine);
+        }
+
+        self._engine_subscriptions = engines
+            .iter()
+            .map(|engine| cx.observe(engine, |this, _, cx| this.play(cx)))
+            .collect();
+        self.engines = engines;
+        self.running = true;
+
+        self._clock_task = Some(cx.spawn(async move |this, cx| {
+            loop {
+                cx.background_executor().timer(CLOCK_TICK).await;
+                let running = this.update(cx, |this, cx| {
+                    this.play(cx);
+                    // Redraw the clocks
+                    cx.notify();
+                    this.running
+                });
+                match running {
+                    Ok(true) => continue,
+                    _ => break, // Match over or model dropped
+                }
+            }
+        }));
+        self.play(cx);
+        Ok(())
+    }
+
+    /// Stop the match, abandoning the game being played
+    pub fn stop(&mut self, cx: &mut Context<Self>) {
+        if !self.running {
+            return;
+        }
+        self.game = None;
+        self.end_match(cx);
+    }
+
+    /// Forget a finished match and its results
+    pub fn clear(&mut self) {
+        if self.running {
+            return;
+        }
+        *self = Self::new();
+    }
+
+    /// Move the match along: start games once the engines are ready, search with
+    /// the engine to move, play the moves they find and finish games that are over
+    fn play(&mut self, cx: &mut Context<Self>) {
+        let now = Instant::now();
+        while self.running {
+            let Some(game) = self.game.as_mut() else {
+                if let Some(index) = self
+                    .engines
+                    .iter()
+                    .position(|engine| !engine.read(cx).is_running())
+                {
+                    self.error = Some(format!("{} stopped", self.names[index]));
+                    self.end_match(cx);
+                    return;
+                }
+                if !self.engines.iter().all(|engine| engine.read(cx).is_ready()) {
+                    return;
+                }
+                self.start_game(now, cx);
+                continue;
+            };
+            if game.check_time(now).is_some() {
+                self.finish_game(cx);
+                continue;
+            }
+
+            let mover = game.turn();
+            let index = engine_index(mover, self.first_engine_white);
+            let engine = self.engines[index].read(cx);
+            if !engine.is_running() {
+                let name = &self.names[index];
+                self.error = Some(match engine.error() {
+                    Some(e) => format!("{} stopped: {}", name, e),
+                    None => format!("{} stopped", name),
+                });
+                game.finish(GameOutcome::forfeit(mover));
+                self.finish_game(cx);
+                self.end_match(cx);
+                return;
+            }
+
+            let fen = game.fen();
+            if engine.current_fen() != Some(fen.as_str())
+                || self.searched_fen.as_ref() != Some(&fen)
+            {
+                let position = game.uci_position();
+                let go = game.go_params(now);
+                self.searched_fen = Some(fen.clone());
+                self.search_started = Some(now);
+                self.engines[index].update(cx, |engine, _| {
+                    engine.start_search(&fen, position, go);
+                });
+                return;
+            }
+            let Some(best_move) = engine.best_move().map(str::to_string) else {
+                // An engine that hangs mid-search forfeits, and the match can't go on
+                if let Some(started) = self.search_started
+                    && game.check_move_deadline(started, now).is_some()
+                {
+                    self.error = Some(format!("{} stopped responding", self.names[index]));
+                    self.finish_game(cx);
+                    self.end_match(cx);
+                }
+                return;
+            };
+            // A repeated position must be searched again
+            self.searched_fen = None;
+            self.search_started = None;
+            game.play(&best_move, now);
+            if game.outcome().is_some() {
+                self.finish_game(cx);
+            }
+            cx.notify();
+        }
+    }
+
+    /// Set up the next game from its opening, with colours alternating
+    fn start_game(&mut self, now: Instant, cx: &mut Context<Self>) {
+        let Some(settings) = &self.settings else {
+            return;
+        };
+        let (opening, first_engine_white) = match_pairing(self.games_played, self.openings.len());
+        self.first_engine_white = first_engine_white;
+        self.searched_fen = None;
+        self.search_started = None;
+        for engine in &self.engines {
+            engine.update(cx, |engine, _| engine.new_game());
+        }
+        self.game = Some(MatchGame::new(
+            &self.openings[opening],
+            settings.limit.clone(),
+            now,
+        ));
+        cx.notify();
+    }
+
+    /// Score the game that just ended, save it, and end the match once enough
+    /// games are played or the SPRT is decided
+    fn finish_game(&mut self, cx: &mut Context<Self>) {
+        let Some(game) = self.game.take() else {
+            return;
+        };
+        let Some(outcome) = game.outcome() else {
+            return;
+        };
+        for engine in &self.engines {
+            engine.update(cx, |engine, _| engine.stop_analysis());
+        }
+        self.score.record(outcome.result, self.first_engine_white);
+        self.last_outcome = Some(outcome);
+        self.games_played += 1;
+
+        if let Some(path) = &self.output_path {
+            let white = self.player(PieceColor::White).unwrap_or("?");
+            let black = self.player(PieceColor::Black).unwrap_or("?");
+            let pgn = game.to_pgn(white, black, self.games_played);
+            if let Err(e) = append_game(path, &pgn) {
+                self.error = Some(e);
+            }
+        }
+
+        let Some(settings) = &self.settings else {
+            return;
+        };
+        let decided = settings
+            .sprt
+            .is_some_and(|sprt| sprt.verdict(&self.score) != SprtVerdict::Continue);
+        if decided || self.games_played >= settings.games {
+            self.end_match(cx);
+        }
+        cx.notify();
+    }
+
+    /// Stop the engines once the match is over
+    fn end_match(&mut self, cx: &mut Context<Self>) {
+        self.running = false;
+        for engine in &self.engines {
+            engine.update(cx, |engine, _| engine.stop());
+        }
+        cx.notify();
+    }
+}
+
+impl Default for MatchModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Index of the engine playing a side: the first engine (0) or the second (1)
+fn engine_index(color: PieceColor, first_engine_white: bool) -> usize {
+    if (color == PieceColor::White) == first_engine_white {
+        0
+    } else {
+        1
+    }
+}
+
+/// Append a game to a PGN file, separated from the previous game by a blank line
+fn append_game(path: &Path, pgn: &str) -> Result<(), String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", pgn))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
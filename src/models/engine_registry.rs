@@ -0,0 +1,139 @@
+//! Engine registry model - the installed engines, persisted in the config directory.
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::domain::{EngineProfile, EngineRegistry, OptionSetting};
+
+/// Name of the app's directory inside the platform config directory
+const CONFIG_DIR_NAME: &str = "rust-gpui";
+
+/// File the engine registry is stored in
+const ENGINES_FILE_NAME: &str = "engines.json";
+
+/// The engine registry model - loads and saves the list of installed engines
+pub struct EngineRegistryModel {
+    registry: EngineRegistry,
+    /// Where the registry is saved (None if the platform has no config directory)
+    config_path: Option<PathBuf>,
+    /// Set when the config file exists but couldn't be loaded, so it is
+    /// backed up before the defaults overwrite it
+    unreadable_config: bool,
+    /// Why the last engine selection failed (shown until a selection succeeds)
+    error: Option<String>,
+}
+
+impl EngineRegistryModel {
+    /// Load the registry from the config file, falling back to the defaults
+    /// if there is no config yet or it can't be read
+    pub fn load() -> Self {
+        let config_path =
+            dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(ENGINES_FILE_NAME));
+
+        let (registry, unreadable_config) = match &config_path {
+            Some(path) if path.exists() => {
+                let loaded = fs::read_to_string(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|json| EngineRegistry::from_json(&json).map_err(|e| e.to_string()));
+                match loaded {
+                    Ok(registry) => (registry, false),
+                    Err(e) => {
+                        eprintln!("Failed to load {}: {}", path.display(), e);
+                        (EngineRegistry::default(), true)
+                    }
+                }
+            }
+            _ => (EngineRegistry::default(), false),
+        };
+
+        Self {
+            registry,
+            config_path,
+            unreadable_config,
+            error: None,
+        }
+    }
+
+    /// All installed engines
+    pub fn engines(&self) -> &[EngineProfile] {
+        self.registry.engines()
+    }
+
+    /// The engine to use for analysis
+    pub fn selected(&self) -> Option<&EngineProfile> {
+        self.registry.selected()
+    }
+
+    /// Get why the last engine selection failed, if it did
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Select an engine by name and save the choice.
+    /// A failure is also kept as the registry's error.
+    pub fn select(&mut self, name: &str) -> Result<(), String> {
+        let result = if self.registry.select(name) {
+            self.save()
+        } else {
+            Err(format!("No engine named \"{}\"", name))
+        };
+        self.error = result.as_ref().err().cloned();
+        result
+    }
+
+    /// Add an engine, select it and save the registry.
+    /// Invalid profiles are refused and leave the registry untouched.
+    pub fn add(&mut self, profile: EngineProfile) -> Result<(), String> {
+        // Only keep the engine once it is saved, so a failed save can be retried
+        let mut registry = self.registry.clone();
+        registry.add(profile).map_err(|e| e.to_string())?;
+        self.save_registry(&registry)?;
+        self.registry = registry;
+        Ok(())
+    }
+
+    /// Set option values on an engine's profile and save the registry
+    pub fn set_options(&mut self, engine: &str, options: &[OptionSetting]) -> Result<(), String> {
+        for option in options {
+            if !self
+                .registry
+                .set_option(engine, &option.name, &option.value)
+            {
+                return Err(format!("No engine named \"{}\"", engine));
+            }
+        }
+        self.save()
+    }
+
+    /// Write the registry to the config file
+    fn save(&mut self) -> Result<(), String> {
+        let registry = self.registry.clone();
+        self.save_registry(&registry)
+    }
+
+    /// Write a registry to the config file. A config that couldn't be loaded
+    /// is backed up first rather than lost.
+    fn save_registry(&mut self, registry: &EngineRegistry) -> Result<(), String> {
+        let Some(path) = &self.config_path else {
+            return Err("No config directory to save engines to".to_string());
+        };
+        if self.unreadable_config {
+            let backup = path.with_extension("json.bak");
+            fs::copy(path, &backup).map_err(|e| {
+                format!(
+                    "Not overwriting {}, which couldn't be loaded or backed up: {}",
+                    path.display(),
+                    e
+                )
+            })?;
+            eprintln!("Backed up {} to {}", path.display(), backup.display());
+            self.unreadable_config = false;
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        fs::write(path, registry.to_json())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
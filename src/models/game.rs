@@ -2,23 +2,273 @@
 //!
 //! This model contains only pure game state and logic, with no UI concerns.
 
-use crate::domain::{MoveNodeId, MoveTree, Piece, PieceColor, shakmaty_to_piece, to_square};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::domain::pgn::{PgnError, parse_pgn, write_pgn};
+use crate::domain::uci::UciCommand;
+use crate::domain::{
+    BoardStatus, FenError, GameClock, GameHeaders, GameOutcome, MoveNodeId, MoveTree, Piece,
+    PieceColor, PieceKind, Termination, TimeControl, history_status, piece_kind_to_role,
+    position_to_fen, shakmaty_to_color, shakmaty_to_piece, square_to_row_col, to_square,
+    uci_line_to_san,
+};
 use shakmaty::san::San;
-use shakmaty::{Chess, Color as SColor, File, Move, Position, Rank, Role};
+use shakmaty::{Chess, File, Move, Position, Square};
+
+/// Result of trying to play a move on the board
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveOutcome {
+    /// The move was played (or an existing move was navigated to)
+    Played,
+    /// The move is a promotion and no promotion piece was given
+    NeedsPromotion,
+    /// No such legal move
+    Illegal,
+}
 
 /// The main game model containing all chess game state
 pub struct GameModel {
     /// The move tree containing all positions and variations
     tree: MoveTree,
+    /// The game's PGN tags (players, event, ...)
+    headers: GameHeaders,
+    /// How the game ended, if not by the moves on the board (e.g. resignation)
+    outcome: Option<GameOutcome>,
+    /// The game clock, if the game is played with one
+    clock: Option<GameClock>,
+    /// The end of the main line when the clock was last brought up to date
+    clock_node: MoveNodeId,
 }
 
 impl GameModel {
     pub fn new() -> Self {
         Self {
             tree: MoveTree::new(),
+            headers: GameHeaders::new(),
+            outcome: None,
+            clock: None,
+            clock_node: 0,
         }
     }
 
+    /// Replace the current game with a new game from the standard starting position
+    pub fn new_game(&mut self) {
+        self.tree = MoveTree::new();
+        self.headers = GameHeaders::new();
+        self.outcome = None;
+        self.clock = None;
+    }
+
+    /// Replace the current game with a new game starting from a FEN.
+    /// On error the current game is left untouched.
+    pub fn start_from_fen(&mut self, fen: &str) -> Result<(), FenError> {
+        self.tree = MoveTree::from_fen(fen)?;
+        self.headers = GameHeaders::new();
+        self.outcome = None;
+        self.clock = None;
+        Ok(())
+    }
+
+    /// Replace the current game with a new game starting from a position
+    pub fn start_from_position(&mut self, position: Chess) {
+        self.tree = MoveTree::from_position(position);
+        self.headers = GameHeaders::new();
+        self.outcome = None;
+        self.clock = None;
+    }
+
+    /// Get the FEN of the currently viewed position
+    pub fn current_fen(&self) -> String {
+        position_to_fen(self.current_position())
+    }
+
+    /// Get the UCI `position` command for the currently viewed position: the game's
+    /// starting position plus the moves leading here, so an engine can see repetitions
+    pub fn uci_position(&self) -> UciCommand {
+        self.uci_position_at(self.tree.current_id())
+    }
+
+    /// Get the UCI `position` command for the position after a move
+    pub fn uci_position_at(&self, id: MoveNodeId) -> UciCommand {
+        let (fen, moves) = self.tree.uci_history_to(id);
+        UciCommand::Position { fen, moves }
+    }
+
+    /// Get the ID of the last move of the main line (the root if there are no moves)
+    pub fn main_line_end_id(&self) -> MoveNodeId {
+        self.tree
+            .main_line()
+            .last()
+            .copied()
+            .unwrap_or(self.tree.root().id)
+    }
+
+    /// Get how the game ended, if it is over: the recorded outcome, or else the
+    /// ending reached on the board at the end of the main line
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        if self.outcome.is_some() {
+            return self.outcome;
+        }
+        match self.board_status_at(self.main_line_end_id()) {
+            BoardStatus::Over(outcome) => Some(outcome),
+            _ => None,
+        }
+    }
+
+    /// Get how the game stands on the board after a move, counting repetitions
+    /// along the moves leading to it
+    pub fn board_status_at(&self, id: MoveNodeId) -> BoardStatus {
+        let history: Vec<Chess> = self
+            .tree
+            .path_to(id)
+            .into_iter()
+            .filter_map(|id| self.tree.get(id))
+            .map(|node| node.position.clone())
+            .collect();
+        history_status(&history)
+    }
+
+    /// Get the draw that may be claimed at the end of the main line, if the game is
+    /// still going on
+    pub fn claimable_draw(&self) -> Option<Termination> {
+        if self.outcome.is_some() {
+            return None;
+        }
+        match self.board_status_at(self.main_line_end_id()) {
+            BoardStatus::DrawClaimable(termination) => Some(termination),
+            _ => None,
+        }
+    }
+
+    /// Claim a draw by threefold repetition or the fifty-move rule, if one may be claimed
+    pub fn claim_draw(&mut self) -> bool {
+        let Some(termination) = self.claimable_draw() else {
+            return false;
+        };
+        self.set_outcome(Some(GameOutcome::draw(termination)));
+        true
+    }
+
+    /// Record how the game ended (None reopens it). The clock stops when the game ends.
+    pub fn set_outcome(&mut self, outcome: Option<GameOutcome>) {
+        if outcome.is_some()
+            && let Some(clock) = self.clock.as_mut()
+        {
+            clock.stop(Instant::now());
+        }
+        self.outcome = outcome;
+    }
+
+    /// Get the game clock, if the game is played with one
+    pub fn clock(&self) -> Option<&GameClock> {
+        self.clock.as_ref()
+    }
+
+    /// Set a clock to the time control and start it for the side to move at the
+    /// end of the main line. Each move added to the end of the main line presses it.
+    pub fn start_clock(&mut self, time_control: TimeControl) {
+        self.headers.set("TimeControl", &time_control.pgn_tag());
+        let mut clock = GameClock::new(time_control);
+        self.clock_node = self.main_line_end_id();
+        if self.outcome().is_none() {
+            clock.start(self.live_turn(), Instant::now());
+        }
+        self.clock = Some(clock);
+    }
+
+    /// Pause a running clock, or resume a paused one for the side to move
+    pub fn toggle_clock(&mut self) {
+        let now = Instant::now();
+        let turn = self.live_turn();
+        let game_over = self.outcome().is_some();
+        let Some(clock) = self.clock.as_mut() else {
+            return;
+        };
+        if clock.running_side().is_some() {
+            clock.stop(now);
+        } else if !game_over {
+            clock.start(turn, now);
+        }
+        self.clock_node = self.main_line_end_id();
+    }
+
+    /// End the game on time if the running side's flag has fallen.
+    /// Returns the outcome if the game just ended.
+    pub fn check_flag(&mut self) -> Option<GameOutcome> {
+        if self.outcome().is_some() {
+            return None;
+        }
+        let color = self.clock.as_ref()?.flagged(Instant::now())?;
+        let outcome = GameOutcome::time_forfeit(self.live_position(), color);
+        self.set_outcome(Some(outcome));
+        Some(outcome)
+    }
+
+    /// Replace the current game with the first game in a PGN string.
+    /// On error the current game is left untouched.
+    pub fn load_pgn(&mut self, pgn: &str) -> Result<(), PgnError> {
+        let game = parse_pgn(pgn)?;
+        let mut headers = GameHeaders::from_tags(&game.headers);
+        // The result after the moves stands in for a missing Result tag
+        if headers.known("Result").is_none()
+            && let Some(result) = &game.result
+        {
+            headers.set("Result", result);
+        }
+        self.tree = game.tree;
+        self.headers = headers;
+        self.outcome = None;
+        self.clock = None;
+        Ok(())
+    }
+
+    /// Replace the current game with the first game in a PGN file
+    pub fn open_pgn_file(&mut self, path: &Path) -> Result<(), String> {
+        let pgn = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        self.load_pgn(&pgn)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    /// Get the game's PGN tags
+    pub fn headers(&self) -> &GameHeaders {
+        &self.headers
+    }
+
+    /// Replace the game's PGN tags
+    pub fn set_headers(&mut self, headers: GameHeaders) {
+        self.headers = headers;
+    }
+
+    /// Set one PGN tag (a blank value removes it)
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.headers.set(name, value);
+    }
+
+    /// Get the result as written in PGN: from how the game ended if it is over,
+    /// otherwise from the Result tag ("*" if unknown)
+    pub fn result_token(&self) -> &str {
+        match self.outcome() {
+            Some(outcome) => outcome.result.pgn_token(),
+            None => self.headers.get("Result").unwrap_or("*"),
+        }
+    }
+
+    /// Export the whole game (all variations) as PGN
+    pub fn to_pgn(&self) -> String {
+        let result = self.result_token();
+        let headers = self.headers.pgn_tags(&self.tree, result);
+        write_pgn(&self.tree, &headers, result)
+    }
+
+    /// Write the game as PGN to a file
+    pub fn save_pgn_file(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_pgn())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
     /// Get the currently viewed position
     pub fn current_position(&self) -> &Chess {
         &self.tree.current().position
@@ -78,92 +328,286 @@ impl GameModel {
             .map(shakmaty_to_piece)
     }
 
-    /// Try to make a move from one square to another. Returns true if successful.
+    /// Try to make a move from one square to another.
     ///
+    /// Promotions need the caller to supply the piece to promote to; without one
+    /// the move is not played and `MoveOutcome::NeedsPromotion` is returned.
     /// If the move already exists as a child of current node, navigates to it.
     /// Otherwise, creates a new variation and navigates to it.
-    pub fn try_move(&mut self, from: (usize, usize), to: (usize, usize)) -> bool {
+    pub fn try_move(
+        &mut self,
+        from: (usize, usize),
+        to: (usize, usize),
+        promotion: Option<PieceKind>,
+    ) -> MoveOutcome {
         let position = self.current_position().clone();
-        let from_sq = to_square(from.0, from.1);
-        let to_sq = to_square(to.0, to.1);
-
-        for m in &position.legal_moves() {
-            let (move_from, move_to) = match m {
-                Move::Normal { from, to, .. } => (*from, *to),
-                Move::EnPassant { from, to, .. } => (*from, *to),
-                Move::Castle { king, rook, .. } => {
-                    // For castling, user drags king to destination (g1/g8 or c1/c8)
-                    let king_dest = if rook.file() == File::H {
-                        shakmaty::Square::from_coords(File::G, rook.rank())
-                    } else {
-                        shakmaty::Square::from_coords(File::C, rook.rank())
-                    };
-                    (*king, king_dest)
-                }
-                Move::Put { .. } => continue,
-            };
+        let squares = (to_square(from.0, from.1), to_square(to.0, to.1));
 
-            if move_from == from_sq && move_to == to_sq {
-                // For pawn promotion, auto-promote to queen
-                let move_to_play = match m {
-                    Move::Normal {
-                        role: Role::Pawn,
-                        from,
-                        to,
-                        capture,
-                        promotion: None,
-                    } if to.rank() == Rank::Eighth || to.rank() == Rank::First => Move::Normal {
-                        role: Role::Pawn,
-                        from: *from,
-                        to: *to,
-                        capture: *capture,
-                        promotion: Some(Role::Queen),
-                    },
-                    _ => *m,
-                };
-
-                // Get SAN notation
-                let san = San::from_move(&position, move_to_play).to_string();
-
-                // Apply the move
-                let new_position = position.play(move_to_play).unwrap();
-
-                // Add to tree (will navigate to existing or create new)
-                self.tree.add_move(new_position, san);
-
-                return true;
+        let candidates: Vec<Move> = position
+            .legal_moves()
+            .into_iter()
+            .filter(|m| move_squares(m) == Some(squares))
+            .collect();
+
+        let is_promotion = candidates.iter().any(|m| m.promotion().is_some());
+        let move_to_play = match (is_promotion, promotion) {
+            (true, None) => return MoveOutcome::NeedsPromotion,
+            (true, Some(kind)) => {
+                let role = piece_kind_to_role(kind);
+                candidates.into_iter().find(|m| m.promotion() == Some(role))
             }
+            (false, _) => candidates.into_iter().next(),
+        };
+        let Some(move_to_play) = move_to_play else {
+            return MoveOutcome::Illegal;
+        };
+
+        // Get SAN notation
+        let san = San::from_move(&position, move_to_play).to_string();
+
+        // Apply the move
+        let new_position = position.play(move_to_play).unwrap();
+
+        // Add to tree (will navigate to existing or create new)
+        self.tree.add_move(new_position, san);
+        self.sync_clock();
+
+        MoveOutcome::Played
+    }
+
+    /// Play a line of UCI moves (e.g. an engine's principal variation) from the
+    /// currently viewed position, following existing moves and adding new ones as
+    /// a variation. Nothing is played unless the whole line is legal.
+    pub fn play_uci_line(&mut self, moves: &[String]) -> bool {
+        let line = uci_line_to_san(self.current_position(), moves);
+        if line.is_empty() || line.len() != moves.len() {
+            return false;
+        }
+        for line_move in line {
+            self.tree.add_move(line_move.position, line_move.san);
+            self.sync_clock();
+        }
+        true
+    }
+
+    /// Get the squares a piece can legally move to in the currently viewed position
+    pub fn legal_destinations(&self, from: (usize, usize)) -> Vec<(usize, usize)> {
+        let from_sq = to_square(from.0, from.1);
+        let mut destinations: Vec<(usize, usize)> = self
+            .current_position()
+            .legal_moves()
+            .iter()
+            .filter_map(move_squares)
+            .filter(|(move_from, _)| *move_from == from_sq)
+            .map(|(_, to)| square_to_row_col(to))
+            .collect();
+        // Promotions produce one move per piece
+        destinations.sort_unstable();
+        destinations.dedup();
+        destinations
+    }
+
+    /// Get the from/to squares of the move that led to the currently viewed position
+    pub fn last_move_squares(&self) -> Option<((usize, usize), (usize, usize))> {
+        let node = self.tree.current();
+        let parent = self.tree.get(node.parent_id?)?;
+        let san: San = node.san.as_deref()?.parse().ok()?;
+        let m = san.to_move(&parent.position).ok()?;
+        let (from, to) = move_squares(&m)?;
+        Some((square_to_row_col(from), square_to_row_col(to)))
+    }
+
+    /// Get the square of the side to move's king if it is in check
+    pub fn checked_king_square(&self) -> Option<(usize, usize)> {
+        let position = self.current_position();
+        if !position.is_check() {
+            return None;
         }
-        false
+        position
+            .board()
+            .king_of(position.turn())
+            .map(square_to_row_col)
     }
 
     /// Get the turn for the currently viewed position
     pub fn current_turn(&self) -> PieceColor {
-        match self.current_position().turn() {
-            SColor::White => PieceColor::White,
-            SColor::Black => PieceColor::Black,
-        }
+        shakmaty_to_color(self.current_position().turn())
     }
 
     /// Delete a move and all its descendants.
     /// If currently viewing the deleted move or a descendant, navigates to parent.
     pub fn delete_move(&mut self, node_id: MoveNodeId) -> bool {
-        self.tree.delete_node(node_id)
+        let deleted = self.tree.delete_node(node_id);
+        self.sync_clock();
+        deleted
     }
 
     /// Promote a variation to be the main line at its branch point.
     pub fn promote_variation(&mut self, node_id: MoveNodeId) -> bool {
-        self.tree.promote_variation(node_id)
+        let promoted = self.tree.promote_variation(node_id);
+        self.sync_clock();
+        promoted
     }
 
     /// Promote a variation to be the global main line (promotes at all branch points).
     pub fn promote_to_main_line(&mut self, node_id: MoveNodeId) -> bool {
-        self.tree.promote_to_main_line(node_id)
+        let promoted = self.tree.promote_to_main_line(node_id);
+        self.sync_clock();
+        promoted
+    }
+
+    /// Set the comment after a move. Blank comments are removed.
+    pub fn set_comment(&mut self, node_id: MoveNodeId, comment: &str) -> bool {
+        self.tree.set_comment(node_id, non_blank(comment))
+    }
+
+    /// Append text to the comment after a move, separated from any existing comment
+    pub fn append_comment(&mut self, node_id: MoveNodeId, text: &str) -> bool {
+        let Some(node) = self.tree.get(node_id) else {
+            return false;
+        };
+        let comment = match node.comment.as_deref() {
+            Some(existing) => format!("{} {}", existing, text.trim()),
+            None => text.to_string(),
+        };
+        self.set_comment(node_id, &comment)
+    }
+
+    /// Set the comment before a move. Blank comments are removed.
+    pub fn set_pre_comment(&mut self, node_id: MoveNodeId, comment: &str) -> bool {
+        self.tree.set_pre_comment(node_id, non_blank(comment))
+    }
+
+    /// Toggle an annotation glyph (NAG) on a move
+    pub fn toggle_nag(&mut self, node_id: MoveNodeId, nag: u8) -> bool {
+        self.tree.toggle_nag(node_id, nag)
+    }
+
+    /// Get the position at the end of the main line, where the game is played
+    fn live_position(&self) -> &Chess {
+        let live = self.tree.get(self.main_line_end_id());
+        live.map_or(&self.tree.root().position, |node| &node.position)
+    }
+
+    /// Get the side to move at the end of the main line
+    fn live_turn(&self) -> PieceColor {
+        shakmaty_to_color(self.live_position().turn())
+    }
+
+    /// Keep the clock in step with the main line: press it when a move is added
+    /// to the end (recording the reading on the move), and carry on from the new
+    /// end if moves were deleted or reordered. The clock stops when the game ends.
+    fn sync_clock(&mut self) {
+        let live_id = self.main_line_end_id();
+        if self.clock.is_none() || live_id == self.clock_node {
+            return;
+        }
+        let previous_node = std::mem::replace(&mut self.clock_node, live_id);
+        let parent_id = self.tree.get(live_id).and_then(|node| node.parent_id);
+        let position = self.live_position().clone();
+        let turn = self.live_turn();
+        let game_over = self.outcome().is_some();
+        let now = Instant::now();
+        let Some(clock) = self.clock.as_mut() else {
+            return;
+        };
+        if clock.running_side().is_none() {
+            // A paused clock doesn't count moves
+            return;
+        }
+
+        if parent_id == Some(previous_node) {
+            let reading = clock.press(now);
+            self.tree.set_clock(live_id, reading);
+            // Moving after the flag fell still loses on time
+            if reading.is_some_and(|reading| reading.remaining_ms == 0) {
+                let outcome = GameOutcome::time_forfeit(&position, turn.opposite());
+                self.set_outcome(Some(outcome));
+                return;
+            }
+        } else {
+            clock.start(turn, now);
+        }
+        if game_over {
+            clock.stop(now);
+        }
+    }
+}
+
+/// Get the from/to squares of a move as the user drags it on the board
+fn move_squares(m: &Move) -> Option<(Square, Square)> {
+    match m {
+        Move::Normal { from, to, .. } => Some((*from, *to)),
+        Move::EnPassant { from, to, .. } => Some((*from, *to)),
+        Move::Castle { king, rook } => {
+            // For castling, user drags king to destination (g1/g8 or c1/c8)
+            let king_dest = if rook.file() == File::H {
+                Square::from_coords(File::G, rook.rank())
+            } else {
+                Square::from_coords(File::C, rook.rank())
+            };
+            Some((*king, king_dest))
+        }
+        Move::Put { .. } => None,
     }
 }
 
+/// Trim a comment, treating an empty one as no comment
+fn non_blank(comment: &str) -> Option<String> {
+    let trimmed = comment.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
 impl Default for GameModel {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Board coordinates (row 0 = rank 8) of a square name such as "e7"
+    fn square(name: &str) -> (usize, usize) {
+        square_to_row_col(name.parse().unwrap())
+    }
+
+    #[test]
+    fn test_try_move_needs_promotion() {
+        let mut game = GameModel::new();
+        game.start_from_fen("8/4P3/8/8/8/8/k7/4K3 w - - 0 1")
+            .unwrap();
+
+        let outcome = game.try_move(square("e7"), square("e8"), None);
+        assert_eq!(outcome, MoveOutcome::NeedsPromotion);
+        assert!(game.is_at_root());
+    }
+
+    #[test]
+    fn test_try_move_underpromotion() {
+        let mut game = GameModel::new();
+        game.start_from_fen("8/4P3/8/8/8/8/k7/4K3 w - - 0 1")
+            .unwrap();
+
+        let outcome = game.try_move(square("e7"), square("e8"), Some(PieceKind::Knight));
+        assert_eq!(outcome, MoveOutcome::Played);
+        assert_eq!(game.tree().current().san.as_deref(), Some("e8=N"));
+        let (row, col) = square("e8");
+        let piece = game.piece_at(row, col).unwrap();
+        assert_eq!(piece.kind, PieceKind::Knight);
+        assert_eq!(piece.color, PieceColor::White);
+    }
+
+    #[test]
+    fn test_try_move_illegal() {
+        let mut game = GameModel::new();
+        let outcome = game.try_move(square("e2"), square("e5"), None);
+        assert_eq!(outcome, MoveOutcome::Illegal);
+        assert!(game.is_at_root());
+
+        // A promotion piece doesn't make an ordinary move illegal
+        let outcome = game.try_move(square("e2"), square("e4"), Some(PieceKind::Queen));
+        assert_eq!(outcome, MoveOutcome::Played);
+    }
+}
@@ -1,5 +1,9 @@
 mod engine;
+mod engine_match;
+mod engine_registry;
 mod game;
 
 pub use engine::*;
+pub use engine_match::*;
+pub use engine_registry::*;
 pub use game::*;
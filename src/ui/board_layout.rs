@@ -7,11 +7,40 @@ use gpui::{Pixels, Size, px};
 #[derive(Clone, Copy, Debug)]
 pub struct BoardLayout {
     pub panel_size: Size<Pixels>,
+    /// Whether black is at the bottom. Rows/cols stay in board coordinates
+    /// (row 0 = rank 8); only the mapping to the screen is mirrored.
+    pub flipped: bool,
 }
 
 impl BoardLayout {
     pub fn new(panel_size: Size<Pixels>) -> Self {
-        Self { panel_size }
+        Self {
+            panel_size,
+            flipped: false,
+        }
+    }
+
+    /// A layout whose board is the given size (e.g. for a preview board)
+    pub fn for_board_size(board_size: f32) -> Self {
+        let panel = px(board_size + BOARD_PADDING * 2.0);
+        Self::new(Size {
+            width: panel,
+            height: panel,
+        })
+    }
+
+    /// The same layout with the given orientation
+    pub fn with_flipped(self, flipped: bool) -> Self {
+        Self { flipped, ..self }
+    }
+
+    /// Map between board row/col and screen row/col (the mapping is its own inverse)
+    fn orient(&self, row: usize, col: usize) -> (usize, usize) {
+        if self.flipped {
+            (7 - row, 7 - col)
+        } else {
+            (row, col)
+        }
     }
 
     /// Calculate square size from measured panel dimensions
@@ -42,12 +71,22 @@ impl BoardLayout {
         let row = (board_y / square_size) as usize;
 
         if row < 8 && col < 8 {
-            Some((row, col))
+            Some(self.orient(row, col))
         } else {
             None
         }
     }
 
+    /// Get the top-left corner of a square relative to the board
+    pub fn square_origin(&self, row: usize, col: usize) -> (f32, f32) {
+        let square_size = self.square_size();
+        let (screen_row, screen_col) = self.orient(row, col);
+        (
+            screen_col as f32 * square_size,
+            screen_row as f32 * square_size,
+        )
+    }
+
     /// Get the total size of the board (8 squares)
     pub fn board_total_size(&self) -> f32 {
         self.square_size() * 8.0
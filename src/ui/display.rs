@@ -16,35 +16,38 @@ pub fn main_line_display(game: &GameModel) -> Vec<MainLineMoveDisplay> {
     let mut result = Vec::new();
 
     for &node_id in &main_line {
-        if let Some(node) = tree.get(node_id) {
-            if let Some(san) = &node.san {
-                let (move_num, is_black) = node.move_number(tree);
-
-                // Check if this move has sibling variations (other children of parent)
-                let sibling_variations = if let Some(parent_id) = node.parent_id {
-                    if let Some(parent) = tree.get(parent_id) {
-                        parent.variation_children().len()
-                    } else {
-                        0
-                    }
+        if let Some(node) = tree.get(node_id)
+            && let Some(san) = &node.san
+        {
+            let (move_num, is_black) = node.move_number(tree);
+
+            // Check if this move has sibling variations (other children of parent)
+            let sibling_variations = if let Some(parent_id) = node.parent_id {
+                if let Some(parent) = tree.get(parent_id) {
+                    parent.variation_children().len()
                 } else {
                     0
-                };
-
-                // Check if this move gives check or checkmate
-                let is_check = node.position.is_check();
-                let is_checkmate = node.position.is_checkmate();
-
-                result.push(MainLineMoveDisplay {
-                    node_id,
-                    move_num,
-                    is_black,
-                    san: san.clone(),
-                    has_sibling_variations: sibling_variations > 0,
-                    is_check,
-                    is_checkmate,
-                });
-            }
+                }
+            } else {
+                0
+            };
+
+            // Check if this move gives check or checkmate
+            let is_check = node.position.is_check();
+            let is_checkmate = node.position.is_checkmate();
+
+            result.push(MainLineMoveDisplay {
+                node_id,
+                move_num,
+                is_black,
+                san: san.clone(),
+                has_sibling_variations: sibling_variations > 0,
+                is_check,
+                is_checkmate,
+                nags: node.nags.clone(),
+                pre_comment: node.pre_comment.clone(),
+                comment: node.comment.clone(),
+            });
         }
     }
 
@@ -144,6 +147,9 @@ fn collect_variation_line(tree: &MoveTree, start_id: MoveNodeId) -> Vec<Variatio
                 has_sibling_sub_variations,
                 is_check,
                 is_checkmate,
+                nags: node.nags.clone(),
+                pre_comment: node.pre_comment.clone(),
+                comment: node.comment.clone(),
             });
         }
 
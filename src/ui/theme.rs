@@ -15,3 +15,36 @@ pub const MOVE_LIST_BG: u32 = 0x1e1e1e;
 pub const BORDER_COLOR: u32 = 0x4a4a4a;
 pub const TEXT_PRIMARY: u32 = 0xffffff;
 pub const TEXT_SECONDARY: u32 = 0x888888;
+pub const ERROR_TEXT: u32 = 0xe06c6c;
+
+// Board highlight colors (rgba, drawn over the board image)
+pub const SELECTED_SQUARE: u32 = 0x14551e80;
+pub const LAST_MOVE_TINT: u32 = 0x9bc70066;
+pub const CHECK_GLOW: u32 = 0xff0000aa;
+pub const LEGAL_MOVE_MARKER: u32 = 0x14551e80;
+pub const LEGAL_MOVE_DOT_SIZE: f32 = 0.3; // dot diameter relative to square
+pub const LEGAL_CAPTURE_RING_WIDTH: f32 = 0.08; // ring width relative to square
+
+// Coordinate labels, drawn in the colour of the opposite square
+pub const COORD_ON_LIGHT_SQUARE: u32 = 0xa0764a;
+pub const COORD_ON_DARK_SQUARE: u32 = 0xf2dcb3;
+pub const COORD_FONT_SCALE: f32 = 0.2; // font size relative to square
+pub const COORD_INSET: f32 = 0.05; // distance from square edge relative to square
+
+// Engine move arrows (rgba, best line first; later lines use the last colour)
+pub const ENGINE_ARROW_COLORS: [u32; 3] = [0x1f6fd0d0, 0x1f6fd080, 0x1f6fd058];
+pub const ENGINE_ARROW_WIDTH: f32 = 0.18; // shaft width relative to square
+pub const ENGINE_ARROW_HEAD_WIDTH: f32 = 0.45; // relative to square
+pub const ENGINE_ARROW_HEAD_LENGTH: f32 = 0.4; // relative to square
+
+// Hover preview board
+pub const PREVIEW_BOARD_SIZE: f32 = 240.0;
+pub const PREVIEW_MOUSE_OFFSET: f32 = 16.0; // distance from the mouse pointer
+
+// Game clocks
+pub const CLOCK_RUNNING_BG: u32 = 0x3d5a80;
+
+// Promotion picker colors
+pub const PROMOTION_BACKDROP: u32 = 0x00000099; // rgba
+pub const PROMOTION_CHOICE_BG: u32 = 0xb0b0b0;
+pub const PROMOTION_CHOICE_HOVER_BG: u32 = 0xe0a040;
@@ -3,17 +3,57 @@
 //! These types are DTOs (Data Transfer Objects) that prepare game state
 //! for display in the UI. They live in the UI layer, not the domain layer.
 
-use crate::domain::{MoveNodeId, Piece};
+use shakmaty::Chess;
+
+use crate::domain::{MoveNodeId, Piece, PieceColor};
 
 /// State for a piece being dragged
 #[derive(Clone, Copy, Debug)]
 pub struct DragState {
     pub piece: Piece,
-    pub from_row: usize,
-    pub from_col: usize,
+    /// Source square as row/col, or None when dragged from the setup palette
+    pub from: Option<(usize, usize)>,
     /// Mouse position relative to window
     pub mouse_x: f32,
     pub mouse_y: f32,
+    /// Where the mouse was pressed
+    pub start_x: f32,
+    pub start_y: f32,
+    /// Whether the mouse has moved far enough for this to be a drag rather than a click
+    pub active: bool,
+}
+
+/// A position shown on a floating mini-board while a move is hovered
+#[derive(Clone, Debug)]
+pub struct BoardPreview {
+    /// Element id of the hovered move
+    pub source: String,
+    /// The position after the hovered move
+    pub position: Chess,
+    /// Mouse position relative to window when the hover started
+    pub mouse_x: f32,
+    pub mouse_y: f32,
+}
+
+/// Square highlights drawn over the board, as row/col squares
+#[derive(Clone, Debug, Default)]
+pub struct BoardHighlights {
+    /// From/to squares of the move that led to the current position
+    pub last_move: Option<((usize, usize), (usize, usize))>,
+    /// Square selected for click-to-move
+    pub selected: Option<(usize, usize)>,
+    /// King of the side to move, if in check
+    pub check: Option<(usize, usize)>,
+    /// Legal destinations of the selected or dragged piece, and whether each is a capture
+    pub legal_destinations: Vec<((usize, usize), bool)>,
+}
+
+/// A pawn move onto the last rank waiting for the promotion piece to be chosen
+#[derive(Clone, Copy, Debug)]
+pub struct PendingPromotion {
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+    pub color: PieceColor,
 }
 
 /// Display data for a move in the main line
@@ -29,6 +69,12 @@ pub struct MainLineMoveDisplay {
     pub is_check: bool,
     /// Whether this move gives checkmate
     pub is_checkmate: bool,
+    /// Annotation glyphs (NAGs) on this move
+    pub nags: Vec<u8>,
+    /// Comment shown before this move
+    pub pre_comment: Option<String>,
+    /// Comment shown after this move
+    pub comment: Option<String>,
 }
 
 /// Display data for a complete variation line
@@ -50,4 +96,10 @@ pub struct VariationMoveDisplay {
     pub is_check: bool,
     /// Whether this move gives checkmate
     pub is_checkmate: bool,
+    /// Annotation glyphs (NAGs) on this move
+    pub nags: Vec<u8>,
+    /// Comment shown before this move
+    pub pre_comment: Option<String>,
+    /// Comment shown after this move
+    pub comment: Option<String>,
 }
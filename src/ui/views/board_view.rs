@@ -1,25 +1,97 @@
 //! Chess board view - the main board with drag-and-drop piece movement.
 
 use gpui::{
-    Action, Context, Entity, FocusHandle, MouseButton, MouseDownEvent, MouseMoveEvent,
-    MouseUpEvent, Pixels, Subscription, Window, actions, canvas, div, img, prelude::*, px, rgb,
+    Action, App, BoxShadow, Context, Div, Entity, FocusHandle, FontWeight, Img, MouseButton,
+    MouseDownEvent, MouseMoveEvent, MouseUpEvent, Path, PathBuilder, PathPromptOptions, Pixels,
+    Point, SharedString, Subscription, Task, Window, actions, anchored, canvas, deferred, div, img,
+    point, prelude::*, px, rgb, rgba,
 };
+use gpui_component::input::{Input, InputState};
 use gpui_component::resizable::{h_resizable, resizable_panel, v_resizable};
+use gpui_component::{Root, WindowExt, v_flex};
+use shakmaty::{Chess, Position};
 use std::collections::HashSet;
+use std::time::{Duration, Instant};
 
-use crate::domain::MoveNodeId;
-use crate::models::{EngineModel, GameModel};
+use crate::domain::{
+    EngineProfile, GameOutcome, MoveNodeId, Piece, PieceColor, PieceKind, PositionSetup,
+    engine_accepts_draw, parse_engine_args, parse_option_settings, position_to_fen,
+    shakmaty_to_color, shakmaty_to_piece, to_square, uci_move_squares,
+};
+use crate::models::{EngineModel, EngineRegistryModel, GameModel, MatchModel, MoveOutcome};
 use crate::ui::BoardLayout;
 use crate::ui::assets::piece_svg_path;
 use crate::ui::theme::{
-    BOARD_CORNER_RADIUS, BOARD_PADDING, GHOST_OPACITY, INITIAL_LEFT_PANEL, PANEL_BG,
+    BOARD_CORNER_RADIUS, BOARD_PADDING, CHECK_GLOW, COORD_FONT_SCALE, COORD_INSET,
+    COORD_ON_DARK_SQUARE, COORD_ON_LIGHT_SQUARE, ENGINE_ARROW_COLORS, ENGINE_ARROW_HEAD_LENGTH,
+    ENGINE_ARROW_HEAD_WIDTH, ENGINE_ARROW_WIDTH, ERROR_TEXT, GHOST_OPACITY, INITIAL_LEFT_PANEL,
+    LAST_MOVE_TINT, LEGAL_CAPTURE_RING_WIDTH, LEGAL_MOVE_DOT_SIZE, LEGAL_MOVE_MARKER, PANEL_BG,
+    PREVIEW_BOARD_SIZE, PREVIEW_MOUSE_OFFSET, PROMOTION_BACKDROP, PROMOTION_CHOICE_BG,
+    PROMOTION_CHOICE_HOVER_BG, SELECTED_SQUARE,
+};
+use crate::ui::view_models::{BoardHighlights, BoardPreview, DragState, PendingPromotion};
+use crate::ui::views::{
+    MatchSetup, PlaySetup, open_clock_dialog, open_engine_options_dialog, open_game_info_dialog,
+    open_match_dialog, open_play_dialog, render_engine_pane, render_game_info_panel,
+    render_match_panel, render_move_list_panel, render_play_panel, render_setup_panel,
 };
-use crate::ui::view_models::DragState;
-use crate::ui::views::{render_engine_pane, render_move_list_panel};
 
 // Define navigation actions
 actions!(chess, [MoveBack, MoveForward, MoveToStart, MoveToEnd]);
 
+// Define game/file actions
+actions!(chess, [NewGame, NewGameFromFen, OpenPgn, SavePgn]);
+
+// Define game result actions
+actions!(chess, [ClaimDraw]);
+
+// Define game info actions
+actions!(chess, [EditGameInfo]);
+
+// Define position setup actions
+actions!(chess, [EditPosition, FinishSetup, CancelSetup]);
+
+// Define board view actions
+actions!(chess, [FlipBoard, ToggleAutoFlip, ToggleCoordinates]);
+
+// Define engine actions
+actions!(chess, [AddEngine, EngineOptions, AddEvaluationComment]);
+
+// Define engine game actions
+actions!(chess, [PlayEngine, Resign, OfferDraw]);
+
+// Define clock actions
+actions!(chess, [StartClock, ToggleClock]);
+
+// Define engine match actions
+actions!(chess, [EngineMatch, StopMatch, CloseMatch]);
+
+#[derive(Clone, PartialEq, Debug, serde::Deserialize, schemars::JsonSchema, Action)]
+pub struct SelectEngine {
+    pub name: String,
+}
+
+/// Which engine moves are drawn as arrows on the board
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Deserialize, schemars::JsonSchema)]
+pub enum EngineArrows {
+    Off,
+    /// The first move of the best line
+    BestMove,
+    /// The first move of every line, fainter for worse lines
+    AllLines,
+}
+
+#[derive(Clone, PartialEq, Debug, serde::Deserialize, schemars::JsonSchema, Action)]
+pub struct SetEngineArrows {
+    pub arrows: EngineArrows,
+}
+
+/// Play the start of an engine line (UCI moves from the analyzed position)
+#[derive(Clone, PartialEq, Debug, serde::Deserialize, schemars::JsonSchema, Action)]
+pub struct PlayEngineLine {
+    pub moves: Vec<String>,
+}
+
 // Define move tree actions with node_id data
 #[derive(Clone, PartialEq, Debug, serde::Deserialize, schemars::JsonSchema, Action)]
 pub struct DeleteMove {
@@ -31,105 +103,1308 @@ pub struct PromoteVariation {
     pub node_id: MoveNodeId,
 }
 
-#[derive(Clone, PartialEq, Debug, serde::Deserialize, schemars::JsonSchema, Action)]
-pub struct PromoteToMainLine {
-    pub node_id: MoveNodeId,
-}
+#[derive(Clone, PartialEq, Debug, serde::Deserialize, schemars::JsonSchema, Action)]
+pub struct PromoteToMainLine {
+    pub node_id: MoveNodeId,
+}
+
+// Define annotation actions
+#[derive(Clone, PartialEq, Debug, serde::Deserialize, schemars::JsonSchema, Action)]
+pub struct ToggleNag {
+    pub node_id: MoveNodeId,
+    pub nag: u8,
+}
+
+#[derive(Clone, PartialEq, Debug, serde::Deserialize, schemars::JsonSchema, Action)]
+pub struct EditComment {
+    pub node_id: MoveNodeId,
+}
+
+/// UI state for the board view (not part of game model)
+pub struct BoardViewState {
+    pub drag_state: Option<DragState>,
+    /// Square selected for click-to-move
+    pub selected_square: Option<(usize, usize)>,
+    /// A promotion move waiting for the user to pick a piece
+    pub pending_promotion: Option<PendingPromotion>,
+}
+
+impl BoardViewState {
+    pub fn new() -> Self {
+        Self {
+            drag_state: None,
+            selected_square: None,
+            pending_promotion: None,
+        }
+    }
+}
+
+/// Distance in pixels the mouse must move before a press becomes a drag
+const DRAG_THRESHOLD: f32 = 4.0;
+
+/// How often the game clock is redrawn and checked for a fallen flag
+const CLOCK_TICK: Duration = Duration::from_millis(100);
+
+/// User-adjustable board display settings (entity so menus can read them)
+pub struct BoardSettings {
+    /// Show the board from black's side
+    pub flipped: bool,
+    /// Turn the board to the player's side when starting a game as black
+    pub auto_flip: bool,
+    /// Draw rank and file labels along the board edge
+    pub show_coordinates: bool,
+    /// Engine moves to draw as arrows
+    pub engine_arrows: EngineArrows,
+}
+
+impl BoardSettings {
+    pub fn new() -> Self {
+        Self {
+            flipped: false,
+            auto_flip: true,
+            show_coordinates: true,
+            engine_arrows: EngineArrows::BestMove,
+        }
+    }
+}
+
+/// Board layout state (entity so canvas can update it)
+pub struct BoardLayoutState {
+    pub layout: BoardLayout,
+}
+
+impl BoardLayoutState {
+    pub fn new() -> Self {
+        Self {
+            layout: BoardLayout::default(),
+        }
+    }
+}
+
+/// UI state model for move list (entity so it can be shared and updated)
+pub struct MoveListState {
+    pub collapsed_variations: HashSet<MoveNodeId>,
+}
+
+impl MoveListState {
+    pub fn new() -> Self {
+        Self {
+            collapsed_variations: HashSet::new(),
+        }
+    }
+
+    pub fn toggle_variation(&mut self, node_id: MoveNodeId) {
+        if self.collapsed_variations.contains(&node_id) {
+            self.collapsed_variations.remove(&node_id);
+        } else {
+            self.collapsed_variations.insert(node_id);
+        }
+    }
+
+    /// Forget per-node UI state when a new game replaces the tree
+    /// (node IDs from the previous game are meaningless now)
+    pub fn reset(&mut self, cx: &mut Context<Self>) {
+        self.collapsed_variations.clear();
+        cx.notify();
+    }
+}
+
+/// Position editor state (entity so the setup panel can update it)
+pub struct SetupState {
+    /// The position being edited, or None when not in setup mode
+    pub setup: Option<PositionSetup>,
+}
+
+impl SetupState {
+    pub fn new() -> Self {
+        Self { setup: None }
+    }
+}
+
+/// Hover preview state (entity so the move list and engine pane can set it)
+pub struct PreviewState {
+    pub preview: Option<BoardPreview>,
+}
+
+impl PreviewState {
+    pub fn new() -> Self {
+        Self { preview: None }
+    }
+
+    /// A hover listener that previews `position` while the element `source` is hovered
+    pub fn hover_listener(
+        preview_state: &Entity<Self>,
+        source: SharedString,
+        position: Chess,
+    ) -> impl Fn(&bool, &mut Window, &mut App) + 'static {
+        let preview_state = preview_state.clone();
+        move |hovered, window, cx| {
+            let mouse = window.mouse_position();
+            preview_state.update(cx, |state, cx| {
+                if *hovered {
+                    state.preview = Some(BoardPreview {
+                        source: source.to_string(),
+                        position: position.clone(),
+                        mouse_x: mouse.x.into(),
+                        mouse_y: mouse.y.into(),
+                    });
+                } else if state
+                    .preview
+                    .as_ref()
+                    .is_some_and(|preview| preview.source == source.as_ref())
+                {
+                    // Leaving one move may be reported after entering the next
+                    state.preview = None;
+                }
+                cx.notify();
+            });
+        }
+    }
+}
+
+/// A game against the engine
+pub struct PlayGame {
+    /// The side the user plays
+    pub human: PieceColor,
+    /// Name of the engine profile playing the other side
+    pub engine_name: String,
+    /// The end of the main line when the game was last brought up to date
+    pub last_node: MoveNodeId,
+    /// The position the engine was last asked to find a move in
+    pub searched_fen: Option<String>,
+    /// Whether the engine declined a draw offer since the last move
+    pub draw_declined: bool,
+    /// Whether the game has ended (the outcome is recorded on the game model)
+    pub finished: bool,
+    /// Why the engine forfeited the game, if it did
+    pub error: Option<String>,
+}
+
+/// Engine game state (entity so the play panel can read it)
+pub struct PlayState {
+    /// The current or last game against the engine
+    pub game: Option<PlayGame>,
+}
+
+impl PlayState {
+    pub fn new() -> Self {
+        Self { game: None }
+    }
+
+    /// Get the game against the engine if it is still being played
+    pub fn active_game(&self) -> Option<&PlayGame> {
+        self.game.as_ref().filter(|game| !game.finished)
+    }
+
+    /// Leave the game against the engine when a new game replaces it
+    pub fn reset(&mut self, cx: &mut Context<Self>) {
+        self.game = None;
+        cx.notify();
+    }
+}
+
+/// The main chess board view that observes a GameModel
+pub struct ChessBoardView {
+    model: Entity<GameModel>,
+    engine_model: Entity<EngineModel>,
+    engine_registry: Entity<EngineRegistryModel>,
+    pub view_state: BoardViewState,
+    layout_state: Entity<BoardLayoutState>,
+    move_list_state: Entity<MoveListState>,
+    setup_state: Entity<SetupState>,
+    board_settings: Entity<BoardSettings>,
+    preview_state: Entity<PreviewState>,
+    play_state: Entity<PlayState>,
+    match_model: Entity<MatchModel>,
+    focus_handle: FocusHandle,
+    /// Ticks the game clock while it runs
+    _clock_task: Option<Task<()>>,
+    _subscription: Subscription,
+    _layout_subscription: Subscription,
+    _move_list_subscription: Subscription,
+    _setup_subscription: Subscription,
+    _settings_subscription: Subscription,
+    _preview_subscription: Subscription,
+    _play_subscription: Subscription,
+    _match_subscription: Subscription,
+    _engine_subscription: Subscription,
+    _engine_registry_subscription: Subscription,
+}
+
+impl ChessBoardView {
+    pub fn new(model: Entity<GameModel>, cx: &mut Context<Self>) -> Self {
+        let _subscription = cx.observe(&model, |this, _, cx| {
+            // Selection and pending promotion belong to the position they were made in
+            this.view_state.selected_square = None;
+            this.view_state.pending_promotion = None;
+            // The hovered move may have been played or deleted
+            this.preview_state
+                .update(cx, |state, _| state.preview = None);
+            // A move may have been played in a game against the engine
+            this.sync_engine_game(cx);
+            // When game position changes, update engine analysis if running
+            this.update_engine_position(cx);
+            cx.notify();
+        });
+        let layout_state = cx.new(|_| BoardLayoutState::new());
+        let _layout_subscription = cx.observe(&layout_state, |_, _, cx| cx.notify());
+        let move_list_state = cx.new(|_| MoveListState::new());
+        let _move_list_subscription = cx.observe(&move_list_state, |_, _, cx| cx.notify());
+        let setup_state = cx.new(|_| SetupState::new());
+        let _setup_subscription = cx.observe(&setup_state, |_, _, cx| cx.notify());
+        let board_settings = cx.new(|_| BoardSettings::new());
+        let _settings_subscription = cx.observe(&board_settings, |_, _, cx| cx.notify());
+        let preview_state = cx.new(|_| PreviewState::new());
+        let _preview_subscription = cx.observe(&preview_state, |_, _, cx| cx.notify());
+        let play_state = cx.new(|_| PlayState::new());
+        let _play_subscription = cx.observe(&play_state, |_, _, cx| cx.notify());
+        let match_model = cx.new(|_| MatchModel::new());
+        let _match_subscription = cx.observe(&match_model, |_, _, cx| cx.notify());
+        let engine_model = cx.new(|_| EngineModel::new());
+        let engine_registry = cx.new(|_| EngineRegistryModel::load());
+        let _engine_registry_subscription = cx.observe(&engine_registry, |_, _, cx| cx.notify());
+        // Observe engine model to:
+        // 1. Re-render when analysis updates
+        // 2. Start analysis when engine becomes ready (but only if position changed)
+        let _engine_subscription = cx.observe(&engine_model, |this, _, cx| {
+            // Check if we need to start analysis (engine running but position not sent yet)
+            this.update_engine_position(cx);
+            cx.notify();
+        });
+
+
+
+        Self {
+            model,
+            engine_model,
+            engine_registry,
+            view_state: BoardViewState::new(),
+            layout_state,
+            move_list_state,
+            setup_state,
+            board_settings,
+            preview_state,
+            play_state,
+            match_model,
+            focus_handle: cx.focus_handle(),
+            _clock_task: None,
+            _subscription,
+            _layout_subscription,
+            _move_list_subscription,
+            _setup_subscription,
+            _settings_subscription,
+            _preview_subscription,
+            _play_subscription,
+            _match_subscription,
+            _engine_subscription,
+            _engine_registry_subscription,
+        }
+    }
+
+    /// Get the measured board layout in the current orientation
+    fn board_layout(&self, cx: &App) -> BoardLayout {
+        let flipped = self.board_settings.read(cx).flipped;
+        self.layout_state.read(cx).layout.with_flipped(flipped)
+    }
+
+    /// Flip the board orientation
+    fn flip_board(&mut self, cx: &mut Context<Self>) {
+        self.board_settings.update(cx, |settings, cx| {
+            settings.flipped = !settings.flipped;
+            cx.notify();
+        });
+    }
+
+    /// Turn the board to the player's side if auto-flip is enabled
+    fn orient_for_player(&mut self, player: PieceColor, cx: &mut Context<Self>) {
+        self.board_settings.update(cx, |settings, cx| {
+            if settings.auto_flip {
+                settings.flipped = player == PieceColor::Black;
+                cx.notify();
+            }
+        });
+    }
+
+    /// Start a new game from the standard starting position
+    fn new_game(&mut self, cx: &mut Context<Self>) {
+        self.model.update(cx, |game, cx| {
+            game.new_game();
+            cx.notify();
+        });
+        self.move_list_state.update(cx, |state, cx| state.reset(cx));
+        self.play_state.update(cx, |state, cx| state.reset(cx));
+    }
+
+    /// Open a dialog to start a new game from a FEN.
+    /// Invalid FENs keep the dialog open and show why they were rejected.
+    fn new_game_from_fen(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let current_fen = self.model.read(cx).current_fen();
+        let fen_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("FEN")
+                .default_value(current_fen)
+        });
+        let error = cx.new(|_| None::<SharedString>);
+        let model = self.model.clone();
+        let board_view = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, cx| {
+            let fen_input = fen_input.clone();
+            let error_message = error.read(cx).clone();
+            let error = error.clone();
+            let model = model.clone();
+            let board_view = board_view.clone();
+
+            dialog
+                .title("New Game from FEN")
+                .confirm()
+                .child(v_flex().gap_2().child(Input::new(&fen_input)).children(
+                    error_message.map(|message| div().text_color(rgb(ERROR_TEXT)).child(message)),
+                ))
+                .on_ok(move |_, window, cx| {
+                    let fen = fen_input.read(cx).value();
+                    let result = model.update(cx, |game, cx| {
+                        let result = game.start_from_fen(&fen);
+                        if result.is_ok() {
+                            cx.notify();
+                        }
+                        result
+                    });
+                    match result {
+                        Ok(()) => {
+                            let _ = board_view.update(cx, |view, cx| {
+                                view.started_from_position(cx);
+                            });
+                            true
+                        }
+                        Err(e) => {
+                            error.update(cx, |error, _| *error = Some(e.to_string().into()));
+                            window.refresh();
+                            false
+                        }
+                    }
+                })
+        });
+    }
+
+    /// Enter setup mode, starting from the currently viewed position
+    fn edit_position(&mut self, cx: &mut Context<Self>) {
+        let setup = PositionSetup::from_position(self.model.read(cx).current_position());
+        self.view_state.drag_state = None;
+        self.view_state.selected_square = None;
+        self.setup_state.update(cx, |state, cx| {
+            state.setup = Some(setup);
+            cx.notify();
+        });
+    }
+
+    /// Leave setup mode and start a new game from the edited position.
+    /// Illegal setups are refused (the setup panel shows why).
+    fn finish_setup(&mut self, cx: &mut Context<Self>) {
+        let Some(setup) = &self.setup_state.read(cx).setup else {
+            return;
+        };
+        let position = match setup.to_position() {
+            Ok(position) => position,
+            Err(e) => {
+                eprintln!("Illegal position: {}", e);
+                return;
+            }
+        };
+
+        self.model.update(cx, |game, cx| {
+            game.start_from_position(position);
+            cx.notify();
+        });
+        self.started_from_position(cx);
+        self.cancel_setup(cx);
+    }
+
+    /// Reset per-game UI state after a game was started from a FEN or setup.
+    /// Such games are played from the side to move, so auto-flip orients for it.
+    fn started_from_position(&mut self, cx: &mut Context<Self>) {
+        self.move_list_state.update(cx, |state, cx| state.reset(cx));
+        self.play_state.update(cx, |state, cx| state.reset(cx));
+        let player = self.model.read(cx).current_turn();
+        self.orient_for_player(player, cx);
+    }
+
+    /// Leave setup mode without changing the game
+    fn cancel_setup(&mut self, cx: &mut Context<Self>) {
+        self.view_state.drag_state = None;
+        self.setup_state.update(cx, |state, cx| {
+            state.setup = None;
+            cx.notify();
+        });
+    }
+
+    /// Get the piece shown on a square: the edited position in setup mode,
+    /// otherwise the currently viewed game position
+    fn displayed_piece_at(&self, row: usize, col: usize, cx: &App) -> Option<Piece> {
+        match &self.setup_state.read(cx).setup {
+            Some(setup) => setup.piece_at(row, col),
+            None => self.model.read(cx).piece_at(row, col),
+        }
+    }
+
+    /// Start dragging a piece from the setup palette
+    fn start_palette_drag(
+        &mut self,
+        piece: Piece,
+        position: Point<Pixels>,
+        cx: &mut Context<Self>,
+    ) {
+        self.view_state.drag_state = Some(DragState {
+            piece,
+            from: None,
+            mouse_x: position.x.into(),
+            mouse_y: position.y.into(),
+            start_x: position.x.into(),
+            start_y: position.y.into(),
+            active: true,
+        });
+        cx.notify();
+    }
+
+    /// Handle a mouse press on the board: pick up a piece, or complete a
+    /// click-to-move from the selected square
+    fn press_square(
+        &mut self,
+        square: (usize, usize),
+        position: Point<Pixels>,
+        cx: &mut Context<Self>,
+    ) {
+        let piece = self.displayed_piece_at(square.0, square.1, cx);
+        let in_setup_mode = self.setup_state.read(cx).setup.is_some();
+
+        // In setup mode any piece can be picked up, otherwise only the side to move
+        let can_pick_up =
+            piece.is_some_and(|piece| in_setup_mode || self.may_move(piece.color, cx));
+
+        if !can_pick_up {
+            // Clicking an empty square or an opponent's piece completes a click-to-move,
+            // or just deselects if there's no legal move there
+            if let Some(from) = self.view_state.selected_square.take() {
+                self.play_board_move(from, square, cx);
+            }
+            cx.notify();
+            return;
+        }
+
+        if let Some(piece) = piece {
+            self.view_state.drag_state = Some(DragState {
+                piece,
+                from: Some(square),
+                mouse_x: position.x.into(),
+                mouse_y: position.y.into(),
+                start_x: position.x.into(),
+                start_y: position.y.into(),
+                active: false,
+            });
+            cx.notify();
+        }
+    }
+
+    /// Whether the user may move pieces of a color in the viewed position: the side
+    /// to move, and in a game against the engine only their own side in the latest position
+    fn may_move(&self, color: PieceColor, cx: &App) -> bool {
+        let game = self.model.read(cx);
+        if color != game.current_turn() {
+            return false;
+        }
+        match self.play_state.read(cx).active_game() {
+            Some(play) => color == play.human && game.current_node_id() == game.main_line_end_id(),
+            None => true,
+        }
+    }
+
+    /// Handle a mouse release: finish a drag, or treat a press without movement as a click
+    fn release_drag(&mut self, drag: DragState, position: Point<Pixels>, cx: &mut Context<Self>) {
+        if drag.active {
+            let layout = self.board_layout(cx);
+            let to = layout.pos_to_square(position.x.into(), position.y.into());
+            self.drop_piece(drag, to, cx);
+            return;
+        }
+
+        // A click on a piece selects it, or deselects it if it was already selected
+        if let Some(from) = drag.from
+            && self.setup_state.read(cx).setup.is_none()
+        {
+            let was_selected = self.view_state.selected_square == Some(from);
+            self.view_state.selected_square = (!was_selected).then_some(from);
+        }
+    }
+
+    /// Finish a drag: play the move, or edit the position in setup mode
+    fn drop_piece(&mut self, drag: DragState, to: Option<(usize, usize)>, cx: &mut Context<Self>) {
+        if self.setup_state.read(cx).setup.is_some() {
+            self.setup_state.update(cx, |state, cx| {
+                let Some(setup) = state.setup.as_mut() else {
+                    return;
+                };
+                match (drag.from, to) {
+                    (Some(from), Some(to)) => setup.move_piece(from, to),
+                    (None, Some((row, col))) => setup.put_piece(row, col, drag.piece),
+                    // Dragging a piece off the board removes it
+                    (Some((row, col)), None) => {
+                        setup.remove_piece(row, col);
+                    }
+                    (None, None) => {}
+                }
+                cx.notify();
+            });
+            return;
+        }
+
+        self.view_state.selected_square = None;
+        if let (Some(from), Some(to)) = (drag.from, to) {
+            self.play_board_move(from, to, cx);
+        }
+    }
+
+    /// Play a move made on the board, opening the promotion picker if needed
+    fn play_board_move(
+        &mut self,
+        from: (usize, usize),
+        to: (usize, usize),
+        cx: &mut Context<Self>,
+    ) {
+        let Some(piece) = self.model.read(cx).piece_at(from.0, from.1) else {
+            return;
+        };
+        let outcome = self.model.update(cx, |game, cx| {
+            let outcome = game.try_move(from, to, None);
+            if outcome == MoveOutcome::Played {
+                cx.notify(); // Notify that model changed
+            }
+            outcome
+        });
+        if outcome == MoveOutcome::NeedsPromotion {
+            // The pawn stays on its square until a piece is picked
+            self.view_state.pending_promotion = Some(PendingPromotion {
+                from,
+                to,
+                color: piece.color,
+            });
+        }
+    }
+
+    /// Play the pending promotion with the chosen piece, or cancel it with None
+    fn complete_promotion(&mut self, kind: Option<PieceKind>, cx: &mut Context<Self>) {
+        let Some(pending) = self.view_state.pending_promotion.take() else {
+            return;
+        };
+        if let Some(kind) = kind {
+            self.model.update(cx, |game, cx| {
+                if game.try_move(pending.from, pending.to, Some(kind)) == MoveOutcome::Played {
+                    cx.notify();
+                }
+            });
+        }
+        cx.notify();
+    }
+
+    /// Render the promotion picker: the board dimmed, with the four promotion
+    /// pieces in a column starting at the promotion square. Clicking elsewhere cancels.
+    fn render_promotion_picker(
+        &self,
+        pending: PendingPromotion,
+        layout: &BoardLayout,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        const CHOICES: [PieceKind; 4] = [
+            PieceKind::Queen,
+            PieceKind::Knight,
+            PieceKind::Rook,
+            PieceKind::Bishop,
+        ];
+        let piece_size = layout.piece_size();
+        let piece_offset = layout.piece_offset();
+        let (to_row, to_col) = pending.to;
+
+        let choices = CHOICES.into_iter().enumerate().map(|(i, kind)| {
+            // The column extends from the promotion square towards the center
+            let row = if to_row == 0 { i } else { to_row - i };
+            let piece = Piece {
+                kind,
+                color: pending.color,
+            };
+            square_overlay(layout, (row, to_col))
+                .rounded_full()
+                .bg(rgb(PROMOTION_CHOICE_BG))
+                .hover(|s| s.bg(rgb(PROMOTION_CHOICE_HOVER_BG)))
+                .cursor_pointer()
+                .child(
+                    img(piece_svg_path(&piece))
+                        .absolute()
+                        .left(px(piece_offset))
+                        .top(px(piece_offset))
+                        .size(px(piece_size)),
+                )
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(move |view, _: &MouseDownEvent, _window, cx| {
+                        cx.stop_propagation();
+                        view.complete_promotion(Some(kind), cx);
+                    }),
+                )
+        });
+
+        div()
+            .absolute()
+            .top_0()
+            .left_0()
+            .size(px(layout.board_total_size()))
+            .rounded(px(BOARD_CORNER_RADIUS))
+            .bg(rgba(PROMOTION_BACKDROP))
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(|view, _: &MouseDownEvent, _window, cx| {
+                    cx.stop_propagation();
+                    view.complete_promotion(None, cx);
+                }),
+            )
+            .children(choices)
+    }
+
+    /// Collect the square highlights for the currently viewed position
+    fn board_highlights(&self, dragging_from: Option<(usize, usize)>, cx: &App) -> BoardHighlights {
+        let game = self.model.read(cx);
+        let selected = self.view_state.selected_square;
+        let legal_destinations = dragging_from
+            .or(selected)
+            .map(|from| {
+                game.legal_destinations(from)
+                    .into_iter()
+                    .map(|to| (to, game.piece_at(to.0, to.1).is_some()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        BoardHighlights {
+            last_move: game.last_move_squares(),
+            selected,
+            check: game.checked_king_square(),
+            legal_destinations,
+        }
+    }
+
+    /// Show a dialog for adding an engine to the registry
+    fn add_engine(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let name_input = cx.new(|cx| InputState::new(window, cx).placeholder("Stockfish"));
+        let path_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder("/usr/local/bin/stockfish"));
+        let args_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder("Optional, quote arguments with spaces")
+        });
+        let dir_input = cx.new(|cx| InputState::new(window, cx).placeholder("Optional"));
+        let options_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder("Hash=256, Threads=4"));
+        let error = cx.new(|_| None::<SharedString>);
+        let engine_registry = self.engine_registry.clone();
+
+        window.open_dialog(cx, move |dialog, _window, cx| {
+            let name_input = name_input.clone();
+            let path_input = path_input.clone();
+            let args_input = args_input.clone();
+            let dir_input = dir_input.clone();
+            let options_input = options_input.clone();
+            let error_message = error.read(cx).clone();
+            let error = error.clone();
+            let engine_registry = engine_registry.clone();
+
+            let field = |label: &'static str, input: &Entity<InputState>| {
+                v_flex()
+                    .gap_1()
+                    .child(div().text_sm().child(label))
+                    .child(Input::new(input))
+            };
+
+            dialog
+                .title("Add Engine")
+                .confirm()
+                .child(
+                    v_flex()
+                        .gap_2()
+                        .child(field("Name", &name_input))
+                        .child(field("Executable", &path_input))
+                        .child(field("Arguments", &args_input))
+                        .child(field("Working directory", &dir_input))
+                        .child(field("Options", &options_input))
+                        .children(
+                            error_message
+                                .map(|message| div().text_color(rgb(ERROR_TEXT)).child(message)),
+                        ),
+                )
+                .on_ok(move |_, window, cx| {
+                    let working_dir = dir_input.read(cx).value().trim().to_string();
+                    let result = parse_option_settings(&options_input.read(cx).value())
+                        .map_err(|e| e.to_string())
+                        .and_then(|options| {
+                            let profile = EngineProfile {
+                                name: name_input.read(cx).value().trim().to_string(),
+                                path: path_input.read(cx).value().trim().into(),
+                                args: parse_engine_args(&args_input.read(cx).value()),
+                                working_dir: (!working_dir.is_empty()).then(|| working_dir.into()),
+                                options,
+                            };
+                            engine_registry.update(cx, |registry, cx| {
+                                let result = registry.add(profile);
+                                cx.notify();
+                                result
+                            })
+                        });
+                    match result {
+                        Ok(()) => true,
+                        Err(e) => {
+                            error.update(cx, |error, _| *error = Some(e.into()));
+                            window.refresh();
+                            false
+                        }
+                    }
+                })
+        });
+    }
+
+    /// Switch to another engine, restarting the analysis if an engine is running
+    fn select_engine(&mut self, name: &str, cx: &mut Context<Self>) {
+        self.engine_registry.update(cx, |registry, cx| {
+            // A failure to select is shown in the engine pane
+            let _ = registry.select(name);
+            cx.notify();
+        });
+
+        if !self.engine_model.read(cx).is_running() {
+            return;
+        }
+        let Some(profile) = self.engine_registry.read(cx).selected().cloned() else {
+            return;
+        };
+        self.engine_model.update(cx, |engine, cx| {
+            engine.stop();
+            // A failure to start is shown in the engine pane
+            let _ = engine.start(&profile, cx);
+            cx.notify();
+        });
+    }
+
+    /// Record the engine's evaluation of the viewed position in the move's comment
+    fn add_evaluation_comment(&mut self, cx: &mut Context<Self>) {
+        let engine = self.engine_model.read(cx);
+        // Analysis may still be for the previous position
+        if engine.current_fen() != Some(self.model.read(cx).current_fen().as_str()) {
+            return;
+        }
+        let Some(comment) = engine.evaluation_comment() else {
+            return;
+        };
+        self.model.update(cx, |game, cx| {
+            let node_id = game.current_node_id();
+            if game.append_comment(node_id, &comment) {
+                cx.notify();
+            }
+        });
+    }
+
+    /// First moves of the engine's lines to draw as arrows (as from/to squares), best first
+    fn engine_arrows(&self, cx: &App) -> Vec<((usize, usize), (usize, usize))> {
+        let lines = match self.board_settings.read(cx).engine_arrows {
+            EngineArrows::Off => return Vec::new(),
+            EngineArrows::BestMove => 1,
+            EngineArrows::AllLines => usize::MAX,
+        };
+        let engine = self.engine_model.read(cx);
+        // Analysis may still be for the previous position
+        if engine.current_fen() != Some(self.model.read(cx).current_fen().as_str()) {
+            return Vec::new();
+        }
+
+        let mut arrows = Vec::new();
+        for info in engine.analysis_lines().into_iter().take(lines) {
+            if let Some(arrow) = info.pv.first().and_then(|uci| uci_move_squares(uci))
+                && !arrows.contains(&arrow)
+            {
+                arrows.push(arrow);
+            }
+        }
+        arrows
+    }
+
+    /// Add the start of an engine line to the game from the viewed position
+    fn play_engine_line(&mut self, moves: &[String], cx: &mut Context<Self>) {
+        // No help from the engine while playing against it
+        if self.play_state.read(cx).active_game().is_some() {
+            return;
+        }
+        // The line may be for the previous position
+        let engine_fen = self.engine_model.read(cx).current_fen().map(str::to_string);
+        self.model.update(cx, |game, cx| {
+            if engine_fen.as_deref() == Some(game.current_fen().as_str())
+                && game.play_uci_line(moves)
+            {
+                cx.notify();
+            }
+        });
+    }
+
+    /// Open the dialog for starting a game against an engine
+    fn play_engine(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let board_view = cx.entity().downgrade();
+        open_play_dialog(
+            &self.engine_registry,
+            move |setup, cx| {
+                let _ = board_view.update(cx, |view, cx| view.start_engine_game(setup, cx));
+            },
+            window,
+            cx,
+        );
+    }
+
+    /// Start a new game against an engine from the standard starting position
+    fn start_engine_game(&mut self, setup: PlaySetup, cx: &mut Context<Self>) {
+        let Some(name) = setup.engine else {
+            return;
+        };
+        let Some(profile) = self
+            .engine_registry
+            .read(cx)
+            .engines()
+            .iter()
+            .find(|profile| profile.name == name)
+            .cloned()
+        else {
+            return;
+        };
+
+        // The chosen engine also analyzes once the game is over
+        self.engine_registry.update(cx, |registry, cx| {
+            // A failure to select is shown in the engine pane
+            let _ = registry.select(&name);
+            cx.notify();
+        });
+        let result = self.engine_model.update(cx, |engine, cx| {
+            if engine.profile().is_some_and(|running| running.name != name) {
+                engine.stop();
+            }
+            let result = engine.start(&profile, cx);
+            cx.notify();
+            result
+        });
+        // A failure to start is shown in the engine pane
+        if result.is_err() {
+            return;
+        }
+
+        self.new_game(cx);
+        self.model.update(cx, |game, cx| {
+            let engine_side = match setup.human {
+                PieceColor::White => "Black",
+                PieceColor::Black => "White",
+            };
+            game.set_header(engine_side, &name);
+            game.start_clock(setup.time_control);
+            cx.notify();
+        });
+        let last_node = self.model.read(cx).main_line_end_id();
+        self.play_state.update(cx, |state, cx| {
+            state.game = Some(PlayGame {
+                human: setup.human,
+                engine_name: name,
+                last_node,
+                searched_fen: None,
+                draw_declined: false,
+                finished: false,
+                error: None,
+            });
+            cx.notify();
+        });
+        self.orient_for_player(setup.human, cx);
+        self.run_clock(cx);
+        self.update_engine_position(cx);
+    }
+
+    /// Open the dialog for starting the clock of the current game.
+    /// A game against the engine keeps the clock it was started with.
+    fn start_clock(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.play_state.read(cx).active_game().is_some() {
+            return;
+        }
+        let board_view = cx.entity().downgrade();
+        open_clock_dialog(
+            move |time_control, cx| {
+                let _ = board_view.update(cx, |view, cx| {
+                    // The clock now belongs to an over-the-board game
+                    view.play_state.update(cx, |state, cx| state.reset(cx));
+                    view.model.update(cx, |game, cx| {
+                        game.start_clock(time_control);
+                        cx.notify();
+                    });
+                    view.run_clock(cx);
+                });
+            },
+            window,
+            cx,
+        );
+    }
+
+    /// Pause or resume the game clock
+    fn toggle_clock(&mut self, cx: &mut Context<Self>) {
+        self.model.update(cx, |game, cx| {
+            game.toggle_clock();
+            cx.notify();
+        });
+        self.run_clock(cx);
+    }
+
+    /// Tick the game clock until it stops
+    fn run_clock(&mut self, cx: &mut Context<Self>) {
+        self._clock_task = Some(cx.spawn(async move |view, cx| {
+            loop {
+                cx.background_executor().timer(CLOCK_TICK).await;
+                match view.update(cx, |view, cx| view.tick_clock(cx)) {
+                    Ok(true) => continue,
+                    _ => break, // Clock stopped or view dropped
+                }
+            }
+        }));
+    }
+
+    /// Redraw the clock and end the game if a flag has fallen.
+    /// Returns false once the clock has stopped.
+    fn tick_clock(&mut self, cx: &mut Context<Self>) -> bool {
+        let running = self.model.update(cx, |game, cx| {
+            if game.check_flag().is_some() {
+                cx.notify();
+            }
+            game.clock()
+                .is_some_and(|clock| clock.running_side().is_some())
+        });
+        cx.notify();
+        running
+    }
+
+    /// Keep a game against the engine in step with the moves played
+    /// and end it once it is over
+    fn sync_engine_game(&mut self, cx: &mut Context<Self>) {
+        let game = self.model.read(cx);
+        let live_id = game.main_line_end_id();
+        let outcome = game.outcome();
+
+        let mut active = false;
+        self.play_state.update(cx, |state, cx| {
+            let Some(play) = state.game.as_mut().filter(|play| !play.finished) else {
+                return;
+            };
+            active = true;
+            if play.last_node != live_id {
+                play.last_node = live_id;
+                play.draw_declined = false;
+                cx.notify();
+            }
+        });
+        if active && let Some(outcome) = outcome {
+            self.finish_engine_game(outcome, cx);
+        }
+    }
+
+    /// Drive the engine in a game against it: search the latest position on the
+    /// engine's turn and play the move it finds
+    fn update_engine_game(&mut self, cx: &mut Context<Self>) {
+        let Some(play) = self.play_state.read(cx).active_game() else {
+            return;
+        };
+        let engine_color = play.human.opposite();
+        let engine_name = play.engine_name.clone();
+        let searched_fen = play.searched_fen.clone();
+        let game = self.model.read(cx);
+        let Some(go) = game.clock().map(|clock| clock.go_params(Instant::now())) else {
+            return;
+        };
+        let live_id = game.main_line_end_id();
+        let Some(live) = game.tree().get(live_id) else {
+            return;
+        };
+        let engine_to_move = shakmaty_to_color(live.position.turn()) == engine_color;
+        let fen = position_to_fen(&live.position);
+        let engine = self.engine_model.read(cx);
+        if !engine.is_running() {
+            return;
+        }
+
+        if !engine_to_move {
+            // The engine doesn't think on the user's time
+            if engine.is_analyzing() {
+                self.engine_model.update(cx, |engine, cx| {
+                    engine.stop_analysis();
+                    cx.notify();
+                });
+            }
+            return;
+        }
+        if engine.current_fen() != Some(fen.as_str()) || searched_fen.as_ref() != Some(&fen) {
+            let position = game.uci_position_at(live_id);
+            self.play_state.update(cx, |state, _| {
+                if let Some(play) = state.game.as_mut() {
+                    play.searched_fen = Some(fen.clone());
+                }
+            });
+            self.engine_model.update(cx, |engine, _| {
+                engine.start_search(&fen, position, go);
+            });
+            return;
+        }
+        let Some(best_move) = engine.best_move().map(str::to_string) else {
+            return;
+        };
+        let played = self.model.update(cx, |game, cx| {
+            // Show the engine's move even if the user was looking at an earlier position
+            game.go_to_node(live_id);
+            let played = game.play_uci_line(std::slice::from_ref(&best_move));
+            cx.notify();
+            played
+        });
+        if !played {
+            self.play_state.update(cx, |state, cx| {
+                if let Some(play) = state.game.as_mut() {
+                    play.error = Some(format!(
+                        "{} played an illegal move: {}",
+                        engine_name, best_move
+                    ));
+                    cx.notify();
+                }
+            });
+            self.finish_engine_game(GameOutcome::forfeit(engine_color), cx);
+        }
+    }
+
+    /// Resign the game against the engine
+    fn resign(&mut self, cx: &mut Context<Self>) {
+        let Some(human) = self
+            .play_state
+            .read(cx)
+            .active_game()
+            .map(|play| play.human)
+        else {
+            return;
+        };
+        self.finish_engine_game(GameOutcome::resignation(human), cx);
+    }
+
+    /// Offer the engine a draw. It accepts if its last evaluation doesn't favour it.
+    fn offer_draw(&mut self, cx: &mut Context<Self>) {
+        let Some(play) = self.play_state.read(cx).active_game() else {
+            return;
+        };
+        let engine = self.engine_model.read(cx);
+        // The engine only searches on its own turn, so the score is from its point of view
+        let accepts = play.searched_fen.is_some()
+            && engine.current_fen() == play.searched_fen.as_deref()
+            && engine
+                .best_analysis()
+                .and_then(|info| info.score)
+                .is_some_and(engine_accepts_draw);
+        if accepts {
+            self.finish_engine_game(GameOutcome::draw_agreed(), cx);
+            return;
+        }
+        self.play_state.update(cx, |state, cx| {
+            if let Some(play) = state.game.as_mut() {
+                play.draw_declined = true;
+                cx.notify();
+            }
+        });
+    }
+
+    /// End the game against the engine and record its outcome on the game
+    fn finish_engine_game(&mut self, outcome: GameOutcome, cx: &mut Context<Self>) {
+        self.play_state.update(cx, |state, cx| {
+            if let Some(play) = state.game.as_mut() {
+                play.finished = true;
+                cx.notify();
+            }
+        });
+        self.engine_model.update(cx, |engine, cx| {
+            engine.stop_analysis();
+            cx.notify();
+        });
+        self.model.update(cx, |game, cx| {
+            game.set_outcome(Some(outcome));
+            cx.notify();
+        });
+    }
+
+    /// Open the dialog for a match between two engines
+    fn engine_match(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let board_view = cx.entity().downgrade();
+        open_match_dialog(
+            &self.engine_registry,
+            move |setup, cx| {
+                let _ = board_view.update(cx, |view, cx| view.start_engine_match(setup, cx));
+            },
+            window,
+            cx,
+        );
+    }
 
-/// UI state for the board view (not part of game model)
-pub struct BoardViewState {
-    pub drag_state: Option<DragState>,
-}
+    /// Start a match between two engines from the registry
+    fn start_engine_match(&mut self, setup: MatchSetup, cx: &mut Context<Self>) {
+        let registry = self.engine_registry.read(cx);
+        let profile = |name: &str| {
+            registry
+                .engines()
+                .iter()
+                .find(|profile| profile.name == name)
+                .cloned()
+        };
+        let [first, second] = &setup.engines;
+        let (Some(first), Some(second)) = (profile(first), profile(second)) else {
+            return;
+        };
 
-impl BoardViewState {
-    pub fn new() -> Self {
-        Self { drag_state: None }
+        self.match_model.update(cx, |engine_match, cx| {
+            // A failure to start is shown in the match panel
+            let _ = engine_match.start(
+                [first, second],
+                setup.openings,
+                setup.settings,
+                setup.output_path,
+                cx,
+            );
+            cx.notify();
+        });
     }
-}
 
-/// Board layout state (entity so canvas can update it)
-pub struct BoardLayoutState {
-    pub layout: BoardLayout,
-}
+    /// Stop the running engine match
+    fn stop_engine_match(&mut self, cx: &mut Context<Self>) {
+        self.match_model.update(cx, |engine_match, cx| {
+            engine_match.stop(cx);
+            cx.notify();
+        });
+    }
 
-impl BoardLayoutState {
-    pub fn new() -> Self {
-        Self {
-            layout: BoardLayout::default(),
-        }
+    /// Hide the results of a finished engine match
+    fn close_engine_match(&mut self, cx: &mut Context<Self>) {
+        self.match_model.update(cx, |engine_match, cx| {
+            engine_match.clear();
+            cx.notify();
+        });
     }
-}
 
-/// UI state model for move list (entity so it can be shared and updated)
-pub struct MoveListState {
-    pub collapsed_variations: HashSet<MoveNodeId>,
-}
+    /// Prompt for a PGN file and load its first game into the model
+    fn open_pgn(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Open PGN".into()),
+        });
+        let model = self.model.clone();
+        let move_list_state = self.move_list_state.clone();
+        let play_state = self.play_state.clone();
 
-impl MoveListState {
-    pub fn new() -> Self {
-        Self {
-            collapsed_variations: HashSet::new(),
-        }
+        cx.spawn_in(window, async move |_, cx| {
+            let Ok(Ok(Some(paths))) = paths.await else {
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+
+            let result = model.update(cx, |game, cx| {
+                let result = game.open_pgn_file(&path);
+                if result.is_ok() {
+                    cx.notify();
+                }
+                result
+            });
+            match result {
+                Ok(Ok(())) => {
+                    let _ = move_list_state.update(cx, |state, cx| state.reset(cx));
+                    let _ = play_state.update(cx, |state, cx| state.reset(cx));
+                }
+                // A failed open leaves the current game, and any game against the engine, as it was
+                Ok(Err(e)) => {
+                    let _ = cx.update(|window, cx| open_error_dialog("Open PGN", e, window, cx));
+                }
+                Err(_) => {}
+            }
+        })
+        .detach();
     }
 
-    pub fn toggle_variation(&mut self, node_id: MoveNodeId) {
-        if self.collapsed_variations.contains(&node_id) {
-            self.collapsed_variations.remove(&node_id);
-        } else {
-            self.collapsed_variations.insert(node_id);
-        }
+    /// Prompt for a destination and write the game to it as PGN
+    fn save_pgn(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let directory = std::env::current_dir().unwrap_or_default();
+        let path = cx.prompt_for_new_path(&directory, Some("game.pgn"));
+        let model = self.model.clone();
+
+        cx.spawn_in(window, async move |_, cx| {
+            let Ok(Ok(Some(path))) = path.await else {
+                return;
+            };
+
+            let result = model.read_with(cx, |game, _| game.save_pgn_file(&path));
+            if let Ok(Err(e)) = result {
+                let _ = cx.update(|window, cx| open_error_dialog("Save PGN", e, window, cx));
+            }
+        })
+        .detach();
     }
-}
 
-/// The main chess board view that observes a GameModel
-pub struct ChessBoardView {
-    model: Entity<GameModel>,
-    engine_model: Entity<EngineModel>,
-    pub view_state: BoardViewState,
-    layout_state: Entity<BoardLayoutState>,
-    move_list_state: Entity<MoveListState>,
-    focus_handle: FocusHandle,
-    _subscription: Subscription,
-    _layout_subscription: Subscription,
-    _move_list_subscription: Subscription,
-    _engine_subscription: Subscription,
-}
+    /// Open a dialog to edit the comments before and after a move
+    fn edit_comment(&mut self, node_id: MoveNodeId, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(node) = self.model.read(cx).tree().get(node_id) else {
+            return;
+        };
+        let pre_comment = node.pre_comment.clone().unwrap_or_default();
+        let comment = node.comment.clone().unwrap_or_default();
+        // Only a variation's first move can have a comment before it in PGN
+        let has_pre_comment = self.model.read(cx).tree().is_variation_start(node_id);
 
-impl ChessBoardView {
-    pub fn new(model: Entity<GameModel>, cx: &mut Context<Self>) -> Self {
-        let _subscription = cx.observe(&model, |this, _, cx| {
-            // When game position changes, update engine analysis if running
-            this.update_engine_position(cx);
-            cx.notify();
+        let pre_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .multi_line(true)
+                .rows(2)
+                .placeholder("Comment before the move")
+                .default_value(pre_comment)
         });
-        let layout_state = cx.new(|_| BoardLayoutState::new());
-        let _layout_subscription = cx.observe(&layout_state, |_, _, cx| cx.notify());
-        let move_list_state = cx.new(|_| MoveListState::new());
-        let _move_list_subscription = cx.observe(&move_list_state, |_, _, cx| cx.notify());
-        let engine_model = cx.new(|_| EngineModel::new());
-        // Observe engine model to:
-        // 1. Re-render when analysis updates
-        // 2. Start analysis when engine becomes ready (but only if position changed)
-        let _engine_subscription = cx.observe(&engine_model, |this, _, cx| {
-            // Check if we need to start analysis (engine running but position not sent yet)
-            this.update_engine_position(cx);
-            cx.notify();
+        let post_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .multi_line(true)
+                .rows(4)
+                .placeholder("Comment after the move")
+                .default_value(comment)
         });
+        let model = self.model.clone();
 
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let pre_input = pre_input.clone();
+            let post_input = post_input.clone();
+            let model = model.clone();
 
-
-        Self {
-            model,
-            engine_model,
-            view_state: BoardViewState::new(),
-            layout_state,
-            move_list_state,
-            focus_handle: cx.focus_handle(),
-            _subscription,
-            _layout_subscription,
-            _move_list_subscription,
-            _engine_subscription,
-        }
+            dialog
+                .title("Edit Comment")
+                .confirm()
+                .child(
+                    v_flex()
+                        .gap_2()
+                        .when(has_pre_comment, |el| el.child(Input::new(&pre_input)))
+                        .child(Input::new(&post_input)),
+                )
+                .on_ok(move |_, _window, cx| {
+                    let pre_comment = pre_input.read(cx).value();
+                    let comment = post_input.read(cx).value();
+                    model.update(cx, |game, cx| {
+                        if has_pre_comment {
+                            game.set_pre_comment(node_id, &pre_comment);
+                        }
+                        game.set_comment(node_id, &comment);
+                        cx.notify();
+                    });
+                    true
+                })
+        });
     }
 
     /// Update engine analysis with current position (if engine is running)
@@ -137,6 +1412,12 @@ impl ChessBoardView {
         use shakmaty::fen::Fen;
         use shakmaty::EnPassantMode;
         
+        // A game against the engine drives the engine itself
+        if self.play_state.read(cx).active_game().is_some() {
+            self.update_engine_game(cx);
+            return;
+        }
+
         let (is_running, current_engine_fen) = {
             let engine = self.engine_model.read(cx);
             (engine.is_running(), engine.current_fen().map(|s| s.to_string()))
@@ -151,25 +1432,264 @@ impl ChessBoardView {
         
         // Only start analysis if the position changed or we're not analyzing yet
         if current_engine_fen.as_deref() != Some(&game_fen) {
+            let position = game.uci_position();
             self.engine_model.update(cx, |engine, _| {
-                engine.start_analysis(&game_fen);
+                engine.start_analysis(&game_fen, position);
             });
         }
     }
 }
 
+/// Show an error that has no dialog of its own to appear in
+fn open_error_dialog(title: &'static str, message: String, window: &mut Window, cx: &mut App) {
+    let message = SharedString::from(message);
+    window.open_dialog(cx, move |dialog, _window, _cx| {
+        dialog
+            .title(title)
+            .alert()
+            .child(div().text_color(rgb(ERROR_TEXT)).child(message.clone()))
+    });
+}
+
+/// A piece image absolutely positioned on its square
+fn render_piece(piece: &Piece, (row, col): (usize, usize), layout: &BoardLayout) -> Img {
+    let (x, y) = layout.square_origin(row, col);
+    let piece_offset = layout.piece_offset();
+    img(piece_svg_path(piece))
+        .absolute()
+        .left(px(x + piece_offset))
+        .top(px(y + piece_offset))
+        .size(px(layout.piece_size()))
+}
+
+/// A small board showing a position, floating next to the mouse pointer
+fn render_preview_board(preview: &BoardPreview, flipped: bool) -> impl IntoElement {
+    let layout = BoardLayout::for_board_size(PREVIEW_BOARD_SIZE).with_flipped(flipped);
+    let board_size = layout.board_total_size();
+    let board = preview.position.board();
+    let pieces: Vec<_> = (0..8)
+        .flat_map(|row| {
+            (0..8).filter_map(move |col| {
+                board
+                    .piece_at(to_square(row, col))
+                    .map(|piece| render_piece(&shakmaty_to_piece(piece), (row, col), &layout))
+            })
+        })
+        .collect();
+
+    let origin = point(
+        px(preview.mouse_x + PREVIEW_MOUSE_OFFSET),
+        px(preview.mouse_y + PREVIEW_MOUSE_OFFSET),
+    );
+    deferred(
+        anchored()
+            .position(origin)
+            .snap_to_window_with_margin(px(PREVIEW_MOUSE_OFFSET))
+            .child(
+                div()
+                    .relative()
+                    .size(px(board_size))
+                    .rounded(px(BOARD_CORNER_RADIUS))
+                    .shadow_lg()
+                    .child(
+                        img("assets/maple.jpg")
+                            .absolute()
+                            .top_0()
+                            .left_0()
+                            .size(px(board_size))
+                            .rounded(px(BOARD_CORNER_RADIUS)),
+                    )
+                    .children(pieces),
+            ),
+    )
+}
+
+/// An absolutely positioned element covering one square of the board
+fn square_overlay(layout: &BoardLayout, (row, col): (usize, usize)) -> Div {
+    let (x, y) = layout.square_origin(row, col);
+    div()
+        .absolute()
+        .left(px(x))
+        .top(px(y))
+        .size(px(layout.square_size()))
+}
+
+/// Render the highlights drawn below the pieces: last move, selection and check
+fn render_highlights(highlights: &BoardHighlights, layout: &BoardLayout) -> Vec<Div> {
+    let mut elements = Vec::new();
+
+    if let Some((from, to)) = highlights.last_move {
+        for square in [from, to] {
+            elements.push(square_overlay(layout, square).bg(rgba(LAST_MOVE_TINT)));
+        }
+    }
+    if let Some(square) = highlights.selected {
+        elements.push(square_overlay(layout, square).bg(rgba(SELECTED_SQUARE)));
+    }
+    if let Some(square) = highlights.check {
+        let glow = rgba(CHECK_GLOW);
+        elements.push(
+            square_overlay(layout, square)
+                .rounded_full()
+                .bg(glow)
+                .shadow(vec![BoxShadow {
+                    color: glow.into(),
+                    offset: point(px(0.), px(0.)),
+                    blur_radius: px(layout.square_size() * 0.3),
+                    spread_radius: px(0.),
+                }]),
+        );
+    }
+
+    elements
+}
+
+/// Render legal destination markers over the pieces: dots on empty squares,
+/// rings around capturable pieces
+fn render_legal_destinations(highlights: &BoardHighlights, layout: &BoardLayout) -> Vec<Div> {
+    let square_size = layout.square_size();
+
+    highlights
+        .legal_destinations
+        .iter()
+        .map(|&(square, is_capture)| {
+            let marker = if is_capture {
+                div()
+                    .size_full()
+                    .rounded_full()
+                    .border(px(square_size * LEGAL_CAPTURE_RING_WIDTH))
+                    .border_color(rgba(LEGAL_MOVE_MARKER))
+            } else {
+                div()
+                    .size(px(square_size * LEGAL_MOVE_DOT_SIZE))
+                    .rounded_full()
+                    .bg(rgba(LEGAL_MOVE_MARKER))
+            };
+            square_overlay(layout, square)
+                .flex()
+                .items_center()
+                .justify_center()
+                .child(marker)
+        })
+        .collect()
+}
+
+/// Render arrows for engine moves over the pieces, fading with the line's rank
+fn render_engine_arrows(
+    arrows: Vec<((usize, usize), (usize, usize))>,
+    layout: &BoardLayout,
+) -> impl IntoElement {
+    let layout = *layout;
+    let board_size = layout.board_total_size();
+    canvas(
+        |_, _, _| {},
+        move |bounds, _, window, _| {
+            // Worst line first so the best move is drawn on top
+            for (rank, &(from, to)) in arrows.iter().enumerate().rev() {
+                let color = ENGINE_ARROW_COLORS[rank.min(ENGINE_ARROW_COLORS.len() - 1)];
+                if let Some(path) = arrow_path(bounds.origin, from, to, &layout) {
+                    window.paint_path(path, rgba(color));
+                }
+            }
+        },
+    )
+    .absolute()
+    .top_0()
+    .left_0()
+    .size(px(board_size))
+}
+
+/// Build an arrow from the centre of one square to the centre of another.
+/// `origin` is the board's top-left corner in window coordinates.
+fn arrow_path(
+    origin: Point<Pixels>,
+    from: (usize, usize),
+    to: (usize, usize),
+    layout: &BoardLayout,
+) -> Option<Path<Pixels>> {
+    let square_size = layout.square_size();
+    let center = |(row, col): (usize, usize)| {
+        let (x, y) = layout.square_origin(row, col);
+        (x + square_size / 2.0, y + square_size / 2.0)
+    };
+    let (x0, y0) = center(from);
+    let (x1, y1) = center(to);
+    let length = (x1 - x0).hypot(y1 - y0);
+    if length == 0.0 {
+        return None;
+    }
+
+    // Unit vectors along the arrow and across it
+    let (ux, uy) = ((x1 - x0) / length, (y1 - y0) / length);
+    let (nx, ny) = (-uy, ux);
+    let shaft = square_size * ENGINE_ARROW_WIDTH / 2.0;
+    let head = square_size * ENGINE_ARROW_HEAD_WIDTH / 2.0;
+    let head_length = (square_size * ENGINE_ARROW_HEAD_LENGTH).min(length);
+    // Where the head meets the shaft
+    let (bx, by) = (x1 - ux * head_length, y1 - uy * head_length);
+
+    let at = |x: f32, y: f32| point(origin.x + px(x), origin.y + px(y));
+    let mut builder = PathBuilder::fill();
+    builder.add_polygon(
+        &[
+            at(x0 + nx * shaft, y0 + ny * shaft),
+            at(bx + nx * shaft, by + ny * shaft),
+            at(bx + nx * head, by + ny * head),
+            at(x1, y1),
+            at(bx - nx * head, by - ny * head),
+            at(bx - nx * shaft, by - ny * shaft),
+            at(x0 - nx * shaft, y0 - ny * shaft),
+        ],
+        true,
+    );
+    builder.build().ok()
+}
+
+/// Render rank labels along the left edge and file labels along the bottom edge,
+/// inside the board. Which ranks/files sit on those edges follows the orientation.
+fn render_coordinates(layout: &BoardLayout) -> Vec<Div> {
+    let square_size = layout.square_size();
+    let inset = px(square_size * COORD_INSET);
+    let (bottom_row, left_col) = if layout.flipped { (0, 7) } else { (7, 0) };
+    let label = |(row, col): (usize, usize), text: String| {
+        let color = if (row + col) % 2 == 0 {
+            COORD_ON_LIGHT_SQUARE
+        } else {
+            COORD_ON_DARK_SQUARE
+        };
+        square_overlay(layout, (row, col))
+            .flex()
+            .p(inset)
+            .text_size(px(square_size * COORD_FONT_SCALE))
+            .line_height(px(square_size * COORD_FONT_SCALE))
+            .font_weight(FontWeight::BOLD)
+            .text_color(rgb(color))
+            .child(text)
+    };
+
+    let ranks = (0..8).map(|row| label((row, left_col), (8 - row).to_string()));
+    let files = (0..8).map(|col| {
+        let file = char::from(b'a' + col as u8).to_string();
+        label((bottom_row, col), file).items_end().justify_end()
+    });
+    ranks.chain(files).collect()
+}
+
 impl Render for ChessBoardView {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let dialog_layer = Root::render_dialog_layer(window, cx);
         let model = self.model.clone();
         let engine_model = self.engine_model.clone();
 
-        let game = self.model.read(cx);
-        let drag_state = self.view_state.drag_state;
-        let dragging_from = drag_state.map(|d| (d.from_row, d.from_col));
+        // A press only shows as a drag once the mouse has moved
+        let drag_state = self.view_state.drag_state.filter(|d| d.active);
+        let dragging_from = drag_state.and_then(|d| d.from);
+        let in_setup_mode = self.setup_state.read(cx).setup.is_some();
+        let preview = self.preview_state.read(cx).preview.clone();
+        let flipped = self.board_settings.read(cx).flipped;
 
         // Sizing based on measured panel dimensions
-        let layout = self.layout_state.read(cx).layout;
-        let square_size = layout.square_size();
+        let layout = self.board_layout(cx);
         let piece_size = layout.piece_size();
 
         // Floating piece follows cursor during drag
@@ -186,10 +1706,12 @@ impl Render for ChessBoardView {
         let board_total_size = layout.board_total_size();
 
         // Collect only pieces that exist with their positions
+        let view: &Self = self;
+        let app: &App = cx;
         let pieces: Vec<_> = (0..8)
             .flat_map(|row| {
                 (0..8).filter_map(move |col| {
-                    game.piece_at(row, col).map(|piece| {
+                    view.displayed_piece_at(row, col, app).map(|piece| {
                         let is_being_dragged = dragging_from == Some((row, col));
                         (row, col, piece, is_being_dragged)
                     })
@@ -199,6 +1721,26 @@ impl Render for ChessBoardView {
 
         let radius = px(BOARD_CORNER_RADIUS);
 
+        // Square highlights (not shown while editing a position)
+        let highlights = if in_setup_mode {
+            BoardHighlights::default()
+        } else {
+            self.board_highlights(dragging_from, cx)
+        };
+        let highlight_elements = render_highlights(&highlights, &layout);
+        let destination_elements = render_legal_destinations(&highlights, &layout);
+        // Engine arrows (not shown while editing a position)
+        let engine_arrows = if in_setup_mode {
+            Vec::new()
+        } else {
+            self.engine_arrows(cx)
+        };
+        let coordinate_elements = if self.board_settings.read(cx).show_coordinates {
+            render_coordinates(&layout)
+        } else {
+            Vec::new()
+        };
+
         // Board background image
         let board_bg = img("assets/maple.jpg")
             .absolute()
@@ -208,17 +1750,10 @@ impl Render for ChessBoardView {
             .rounded(radius);
 
         // Pieces absolutely positioned on the board
-        let piece_offset = layout.piece_offset();
         let piece_elements: Vec<_> = pieces
             .into_iter()
             .map(|(row, col, piece, is_being_dragged)| {
-                let x = col as f32 * square_size + piece_offset;
-                let y = row as f32 * square_size + piece_offset;
-                img(piece_svg_path(&piece))
-                    .absolute()
-                    .left(px(x))
-                    .top(px(y))
-                    .size(px(piece_size))
+                render_piece(&piece, (row, col), &layout)
                     .when(is_being_dragged, |el| el.opacity(GHOST_OPACITY))
             })
             .collect();
@@ -230,7 +1765,16 @@ impl Render for ChessBoardView {
             .w(px(board_total_size))
             .h(px(board_total_size))
             .child(board_bg)
-            .children(piece_elements);
+            .children(highlight_elements)
+            .children(piece_elements)
+            .children(coordinate_elements)
+            .when(!engine_arrows.is_empty(), |el| {
+                el.child(render_engine_arrows(engine_arrows, &layout))
+            })
+            .children(destination_elements)
+            .when_some(self.view_state.pending_promotion, |el, pending| {
+                el.child(self.render_promotion_picker(pending, &layout, cx))
+            });
 
         let board_panel_content = div()
             .id("board-panel")
@@ -240,60 +1784,35 @@ impl Render for ChessBoardView {
             .bg(rgb(PANEL_BG))
             .p(px(BOARD_PADDING))
             .child(board)
-            .when_some(floating_piece, |el, fp| el.child(fp))
-            // Mouse down: start drag if clicking on a piece
+            // Mouse down: pick up a piece (drag or click-to-move)
             .on_mouse_down(
                 MouseButton::Left,
                 cx.listener(|view, ev: &MouseDownEvent, _window, cx| {
                     let pos = ev.position;
-                    let game = view.model.read(cx);
-                    let layout = view.layout_state.read(cx).layout;
-
-                    if let Some((row, col)) = layout.pos_to_square(pos.x.into(), pos.y.into()) {
-                        if let Some(piece) = game.piece_at(row, col) {
-                            if piece.color == game.current_turn() {
-                                view.view_state.drag_state = Some(DragState {
-                                    piece,
-                                    from_row: row,
-                                    from_col: col,
-                                    mouse_x: pos.x.into(),
-                                    mouse_y: pos.y.into(),
-                                });
-                                cx.notify();
-                            }
-                        }
+                    let layout = view.board_layout(cx);
+                    if let Some(square) = layout.pos_to_square(pos.x.into(), pos.y.into()) {
+                        view.press_square(square, pos, cx);
                     }
                 }),
             )
-            // Mouse move: update drag position
-            .on_mouse_move(cx.listener(|view, ev: &MouseMoveEvent, _window, cx| {
-                if let Some(ref mut drag) = view.view_state.drag_state {
-                    drag.mouse_x = ev.position.x.into();
-                    drag.mouse_y = ev.position.y.into();
-                    cx.notify();
-                }
-            }))
-            // Mouse up: complete the move
-            .on_mouse_up(
-                MouseButton::Left,
-                cx.listener(|view, ev: &MouseUpEvent, _window, cx| {
-                    let pos = ev.position;
-
-                    if let Some(drag) = view.view_state.drag_state.take() {
-                        let layout = view.layout_state.read(cx).layout;
-                        if let Some((to_row, to_col)) =
-                            layout.pos_to_square(pos.x.into(), pos.y.into())
-                        {
-                            view.model.update(cx, |game, cx| {
-                                if game.try_move((drag.from_row, drag.from_col), (to_row, to_col)) {
-                                    cx.notify(); // Notify that model changed
+            // Right click in setup mode: remove the piece
+            .when(in_setup_mode, |el| {
+                el.on_mouse_down(
+                    MouseButton::Right,
+                    cx.listener(|view, ev: &MouseDownEvent, _window, cx| {
+                        let pos = ev.position;
+                        let layout = view.board_layout(cx);
+                        if let Some((row, col)) = layout.pos_to_square(pos.x.into(), pos.y.into()) {
+                            view.setup_state.update(cx, |state, cx| {
+                                if let Some(setup) = state.setup.as_mut() {
+                                    setup.remove_piece(row, col);
+                                    cx.notify();
                                 }
                             });
                         }
-                        cx.notify();
-                    }
-                }),
-            );
+                    }),
+                )
+            });
 
         // Canvas to measure actual panel size
         let layout_state = self.layout_state.clone();
@@ -320,27 +1839,61 @@ impl Render for ChessBoardView {
             .child(measure_canvas)
             .child(board_panel_content);
 
-        // Move list panel
-        let move_list_panel_content = render_move_list_panel(&model, &self.move_list_state, cx);
-
-        // Engine pane
-        let engine_pane_content = render_engine_pane(&engine_model, cx);
-
         // Right panel with vertical split: move list (top) + engine (bottom)
-        let right_panel_content = v_resizable("right-panel-layout")
-            .child(
-                resizable_panel()
-                    .size(px(300.))
-                    .size_range(px(150.)..Pixels::MAX)
-                    .child(move_list_panel_content),
+        let right_panel_content = if in_setup_mode {
+            let board_view = cx.entity().downgrade();
+            render_setup_panel(
+                &self.setup_state,
+                move |piece, position, cx| {
+                    let _ = board_view.update(cx, |view, cx| {
+                        view.start_palette_drag(piece, position, cx);
+                    });
+                },
+                cx,
             )
-            .child(
-                resizable_panel()
-                    .size(px(200.))
-                    .size_range(px(100.)..Pixels::MAX)
-                    .child(engine_pane_content),
+            .into_any_element()
+        } else {
+            // Move list panel, below the clocks of a game against the engine
+            let move_list_panel_content = div()
+                .size_full()
+                .flex()
+                .flex_col()
+                .child(render_play_panel(&self.play_state, &model, flipped, cx))
+                .child(render_match_panel(&self.match_model, cx))
+                .child(render_game_info_panel(&model, cx))
+                .child(div().flex_1().min_h_0().child(render_move_list_panel(
+                    &model,
+                    &self.move_list_state,
+                    &self.board_settings,
+                    &self.preview_state,
+                    cx,
+                )));
+
+            // Engine pane
+            let engine_pane_content = render_engine_pane(
+                &engine_model,
+                &self.engine_registry,
+                &self.board_settings,
+                &self.preview_state,
+                cx,
             );
 
+            v_resizable("right-panel-layout")
+                .child(
+                    resizable_panel()
+                        .size(px(300.))
+                        .size_range(px(150.)..Pixels::MAX)
+                        .child(move_list_panel_content),
+                )
+                .child(
+                    resizable_panel()
+                        .size(px(200.))
+                        .size_range(px(100.)..Pixels::MAX)
+                        .child(engine_pane_content),
+                )
+                .into_any_element()
+        };
+
         // Clone model for each action handler
         let model_back = model.clone();
         let model_forward = model.clone();
@@ -349,6 +1902,7 @@ impl Render for ChessBoardView {
         let model_delete = model.clone();
         let model_promote = model.clone();
         let model_promote_main = model.clone();
+        let model_nag = model.clone();
 
         // Main resizable layout
         div()
@@ -397,6 +1951,107 @@ impl Render for ChessBoardView {
                     cx.notify();
                 });
             })
+            .on_action(move |action: &ToggleNag, _window, cx| {
+                model_nag.update(cx, |game, cx| {
+                    game.toggle_nag(action.node_id, action.nag);
+                    cx.notify();
+                });
+            })
+            .on_action(cx.listener(|view, action: &EditComment, window, cx| {
+                view.edit_comment(action.node_id, window, cx);
+            }))
+            .on_action(cx.listener(|view, _: &NewGame, _window, cx| {
+                view.new_game(cx);
+            }))
+            .on_action(cx.listener(|view, _: &ClaimDraw, _window, cx| {
+                // A game against the engine ends once the game model records the draw
+                view.model.update(cx, |game, cx| {
+                    if game.claim_draw() {
+                        cx.notify();
+                    }
+                });
+            }))
+            .on_action(cx.listener(|view, _: &EditGameInfo, window, cx| {
+                open_game_info_dialog(&view.model, window, cx);
+            }))
+            .on_action(cx.listener(|view, _: &NewGameFromFen, window, cx| {
+                view.new_game_from_fen(window, cx);
+            }))
+            .on_action(cx.listener(|view, _: &EditPosition, _window, cx| {
+                view.edit_position(cx);
+            }))
+            .on_action(cx.listener(|view, _: &FinishSetup, _window, cx| {
+                view.finish_setup(cx);
+            }))
+            .on_action(cx.listener(|view, _: &CancelSetup, _window, cx| {
+                view.cancel_setup(cx);
+            }))
+            .on_action(cx.listener(|view, _: &FlipBoard, _window, cx| {
+                view.flip_board(cx);
+            }))
+            .on_action(cx.listener(|view, _: &ToggleAutoFlip, _window, cx| {
+                view.board_settings.update(cx, |settings, cx| {
+                    settings.auto_flip = !settings.auto_flip;
+                    cx.notify();
+                });
+            }))
+            .on_action(cx.listener(|view, _: &ToggleCoordinates, _window, cx| {
+                view.board_settings.update(cx, |settings, cx| {
+                    settings.show_coordinates = !settings.show_coordinates;
+                    cx.notify();
+                });
+            }))
+            .on_action(cx.listener(|view, action: &SetEngineArrows, _window, cx| {
+                view.board_settings.update(cx, |settings, cx| {
+                    settings.engine_arrows = action.arrows;
+                    cx.notify();
+                });
+            }))
+            .on_action(cx.listener(|view, _: &AddEngine, window, cx| {
+                view.add_engine(window, cx);
+            }))
+            .on_action(cx.listener(|view, _: &AddEvaluationComment, _window, cx| {
+                view.add_evaluation_comment(cx);
+            }))
+            .on_action(cx.listener(|view, _: &EngineOptions, window, cx| {
+                open_engine_options_dialog(&view.engine_model, &view.engine_registry, window, cx);
+            }))
+            .on_action(cx.listener(|view, action: &SelectEngine, _window, cx| {
+                view.select_engine(&action.name, cx);
+            }))
+            .on_action(cx.listener(|view, action: &PlayEngineLine, _window, cx| {
+                view.play_engine_line(&action.moves, cx);
+            }))
+            .on_action(cx.listener(|view, _: &PlayEngine, window, cx| {
+                view.play_engine(window, cx);
+            }))
+            .on_action(cx.listener(|view, _: &Resign, _window, cx| {
+                view.resign(cx);
+            }))
+            .on_action(cx.listener(|view, _: &OfferDraw, _window, cx| {
+                view.offer_draw(cx);
+            }))
+            .on_action(cx.listener(|view, _: &StartClock, window, cx| {
+                view.start_clock(window, cx);
+            }))
+            .on_action(cx.listener(|view, _: &ToggleClock, _window, cx| {
+                view.toggle_clock(cx);
+            }))
+            .on_action(cx.listener(|view, _: &EngineMatch, window, cx| {
+                view.engine_match(window, cx);
+            }))
+            .on_action(cx.listener(|view, _: &StopMatch, _window, cx| {
+                view.stop_engine_match(cx);
+            }))
+            .on_action(cx.listener(|view, _: &CloseMatch, _window, cx| {
+                view.close_engine_match(cx);
+            }))
+            .on_action(cx.listener(|view, _: &OpenPgn, window, cx| {
+                view.open_pgn(window, cx);
+            }))
+            .on_action(cx.listener(|view, _: &SavePgn, window, cx| {
+                view.save_pgn(window, cx);
+            }))
             .child(
                 h_resizable("chess-layout")
                     .child(
@@ -411,5 +2066,34 @@ impl Render for ChessBoardView {
                             .child(right_panel_content),
                     ),
             )
+            // Dragging is tracked on the whole window so pieces can be dragged
+            // in from the setup palette and dropped off the board
+            .when_some(floating_piece, |el, fp| el.child(fp))
+            // Position after a hovered move in the move list or an engine line
+            .when_some(preview, |el, preview| {
+                el.child(render_preview_board(&preview, flipped))
+            })
+            .on_mouse_move(cx.listener(|view, ev: &MouseMoveEvent, _window, cx| {
+                if let Some(ref mut drag) = view.view_state.drag_state {
+                    drag.mouse_x = ev.position.x.into();
+                    drag.mouse_y = ev.position.y.into();
+                    let distance = (drag.mouse_x - drag.start_x).hypot(drag.mouse_y - drag.start_y);
+                    if distance >= DRAG_THRESHOLD {
+                        drag.active = true;
+                    }
+                    cx.notify();
+                }
+            }))
+            // Mouse up: complete the move
+            .on_mouse_up(
+                MouseButton::Left,
+                cx.listener(|view, ev: &MouseUpEvent, _window, cx| {
+                    if let Some(drag) = view.view_state.drag_state.take() {
+                        view.release_drag(drag, ev.position, cx);
+                        cx.notify();
+                    }
+                }),
+            )
+            .children(dialog_layer)
     }
 }
@@ -0,0 +1,105 @@
+//! Clock dialog - pick a time control and start the clock for an over-the-board game.
+
+use std::rc::Rc;
+
+use gpui::{App, Div, SharedString, Window, div, prelude::*, rgb};
+use gpui_component::button::Button;
+use gpui_component::{Selectable, Sizable, WindowExt, v_flex};
+
+use crate::domain::{TimeBonus, TimeControl};
+use crate::ui::theme::TEXT_SECONDARY;
+
+/// Default time control for a new clock
+fn default_time_control() -> TimeControl {
+    TimeControl::minutes(15, 10)
+}
+
+/// Open the dialog for starting the clock of the current game.
+/// `on_start` is called with the time control when the user confirms.
+pub fn open_clock_dialog(
+    on_start: impl Fn(TimeControl, &mut App) + 'static,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let time_control = cx.new(|_| default_time_control());
+    let on_start = Rc::new(on_start);
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let current = time_control.read(cx).clone();
+        let on_change = {
+            let time_control = time_control.clone();
+            move |choice: TimeControl, cx: &mut App| {
+                time_control.update(cx, |time_control, _| *time_control = choice);
+            }
+        };
+        let time_control = time_control.clone();
+        let on_start = on_start.clone();
+
+        dialog
+            .title("Start Clock")
+            .confirm()
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(rgb(TEXT_SECONDARY))
+                            .child("The clock starts for the side to move at the end of the game."),
+                    )
+                    .children(render_time_control_picker(&current, on_change)),
+            )
+            .on_ok(move |_, _window, cx| {
+                on_start(time_control.read(cx).clone(), cx);
+                true
+            })
+    });
+}
+
+/// Render the time control sections shared by the clock and engine game dialogs:
+/// the periods and how the bonus is applied. `on_change` is called with each choice.
+pub fn render_time_control_picker(
+    current: &TimeControl,
+    on_change: impl Fn(TimeControl, &mut App) + 'static,
+) -> [Div; 2] {
+    let on_change = Rc::new(on_change);
+
+    let presets = TimeControl::presets().into_iter().map(|preset| {
+        let on_change = on_change.clone();
+        let label = preset.stages_label();
+        let choice = preset.with_bonus(current.bonus());
+        Button::new(SharedString::from(format!("time-control-{label}")))
+            .label(label)
+            .small()
+            .selected(current.same_stages(&choice))
+            .on_click(move |_, window, cx| {
+                on_change(choice.clone(), cx);
+                window.refresh();
+            })
+    });
+    let bonuses = TimeBonus::ALL.into_iter().map(|bonus| {
+        let on_change = on_change.clone();
+        let choice = current.clone().with_bonus(bonus);
+        Button::new(SharedString::from(format!("time-bonus-{}", bonus.label())))
+            .label(bonus.label())
+            .small()
+            .selected(current.bonus() == bonus)
+            .on_click(move |_, window, cx| {
+                on_change(choice.clone(), cx);
+                window.refresh();
+            })
+    });
+
+    [
+        render_section("Time control (minutes + bonus seconds)")
+            .child(div().flex().flex_wrap().gap_1().children(presets)),
+        render_section("Bonus per move").child(div().flex().flex_wrap().gap_1().children(bonuses)),
+    ]
+}
+
+/// Render a labelled section of the dialog
+fn render_section(label: &'static str) -> Div {
+    v_flex()
+        .gap_1()
+        .child(div().text_xs().text_color(rgb(TEXT_SECONDARY)).child(label))
+}
@@ -0,0 +1,239 @@
+//! Engine options dialog - edit the options the running engine declares.
+//!
+//! Changed values are sent to the engine with `setoption` and saved in the
+//! engine's profile so they are applied again the next time it starts.
+
+use gpui::{App, Corner, Entity, SharedString, Window, div, prelude::*, px, rgb};
+use gpui_component::button::Button;
+use gpui_component::checkbox::Checkbox;
+use gpui_component::input::{Input, InputState};
+use gpui_component::menu::{DropdownMenu, PopupMenuItem};
+use gpui_component::{Sizable, WindowExt, v_flex};
+
+use crate::domain::OptionSetting;
+use crate::domain::uci::{UciOption, UciOptionType};
+use crate::models::{EngineModel, EngineRegistryModel};
+use crate::ui::theme::{ERROR_TEXT, TEXT_SECONDARY};
+
+/// Maximum height of the option list before it scrolls
+const OPTIONS_LIST_MAX_HEIGHT: f32 = 420.0;
+
+/// How an option is edited in the dialog
+#[derive(Clone)]
+enum OptionField {
+    /// Spin and string options are typed in
+    Text(Entity<InputState>),
+    /// Check and combo options are picked from a fixed set of values
+    Choice(Entity<String>),
+    /// Buttons act immediately
+    Button,
+}
+
+/// Open the options dialog for the running engine
+pub fn open_engine_options_dialog(
+    engine_model: &Entity<EngineModel>,
+    engine_registry: &Entity<EngineRegistryModel>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let engine = engine_model.read(cx);
+    let Some(engine_name) = engine.profile().map(|profile| profile.name.clone()) else {
+        return;
+    };
+    let current_values: Vec<(UciOption, Option<String>)> = engine
+        .options()
+        .iter()
+        .map(|option| (option.clone(), engine.option_value(option)))
+        .collect();
+
+    let fields: Vec<(UciOption, OptionField)> = current_values
+        .into_iter()
+        .map(|(option, value)| {
+            let value = value.unwrap_or_default();
+            let field = match option.option_type {
+                UciOptionType::Spin { .. } | UciOptionType::String { .. } => {
+                    OptionField::Text(cx.new(|cx| InputState::new(window, cx).default_value(value)))
+                }
+                UciOptionType::Check { .. } | UciOptionType::Combo { .. } => {
+                    OptionField::Choice(cx.new(|_| value))
+                }
+                UciOptionType::Button => OptionField::Button,
+            };
+            (option, field)
+        })
+        .collect();
+    let error = cx.new(|_| None::<SharedString>);
+    let engine_model = engine_model.clone();
+    let engine_registry = engine_registry.clone();
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let error_message = error.read(cx).clone();
+        let error = error.clone();
+        let fields = fields.clone();
+        let engine_model = engine_model.clone();
+        let engine_registry = engine_registry.clone();
+        let engine_name = engine_name.clone();
+
+        let rows = fields
+            .iter()
+            .map(|(option, field)| render_option_row(option, field, &engine_model, cx));
+        let content = if fields.is_empty() {
+            v_flex().child(
+                div()
+                    .text_sm()
+                    .text_color(rgb(TEXT_SECONDARY))
+                    .child("This engine has no options"),
+            )
+        } else {
+            v_flex().gap_2().children(rows)
+        };
+
+        dialog
+            .title(format!("{} Options", engine_name))
+            .confirm()
+            .child(
+                v_flex()
+                    .gap_2()
+                    .child(
+                        div()
+                            .id("engine-options-scroll")
+                            .max_h(px(OPTIONS_LIST_MAX_HEIGHT))
+                            .overflow_y_scroll()
+                            .child(content),
+                    )
+                    .children(
+                        error_message
+                            .map(|message| div().text_color(rgb(ERROR_TEXT)).child(message)),
+                    ),
+            )
+            .on_ok(move |_, window, cx| {
+                let changed = match changed_options(&fields, &engine_model, cx) {
+                    Ok(changed) => changed,
+                    Err(e) => {
+                        error.update(cx, |error, _| *error = Some(e.into()));
+                        window.refresh();
+                        return false;
+                    }
+                };
+                if changed.is_empty() {
+                    return true;
+                }
+
+                engine_model.update(cx, |engine, cx| {
+                    engine.set_options(&changed);
+                    cx.notify();
+                });
+                engine_registry.update(cx, |registry, cx| {
+                    if let Err(e) = registry.set_options(&engine_name, &changed) {
+                        eprintln!("Failed to save engine options: {}", e);
+                    }
+                    cx.notify();
+                });
+                true
+            })
+    });
+}
+
+/// Render the name and editor for one option
+fn render_option_row(
+    option: &UciOption,
+    field: &OptionField,
+    engine_model: &Entity<EngineModel>,
+    cx: &App,
+) -> impl IntoElement {
+    let id = SharedString::from(format!("engine-option-{}", option.name));
+    let editor = match (field, &option.option_type) {
+        (OptionField::Text(input), _) => Input::new(input).small().into_any_element(),
+        (OptionField::Choice(choice), UciOptionType::Check { .. }) => {
+            let choice = choice.clone();
+            Checkbox::new(id)
+                .checked(choice.read(cx) == "true")
+                .on_click(move |checked, window, cx| {
+                    choice.update(cx, |value, _| *value = checked.to_string());
+                    window.refresh();
+                })
+                .into_any_element()
+        }
+        (OptionField::Choice(choice), UciOptionType::Combo { vars, .. }) => {
+            let current = choice.read(cx).clone();
+            let choice = choice.clone();
+            let vars = vars.clone();
+            Button::new(id)
+                .label(current.clone())
+                .outline()
+                .small()
+                .dropdown_menu_with_anchor(Corner::TopRight, move |menu, _window, _cx| {
+                    vars.iter().fold(menu, |menu, var| {
+                        let choice = choice.clone();
+                        let var = var.clone();
+                        menu.item(
+                            PopupMenuItem::new(var.clone())
+                                .checked(var == current)
+                                .on_click(move |_, window, cx| {
+                                    choice.update(cx, |value, _| *value = var.clone());
+                                    window.refresh();
+                                }),
+                        )
+                    })
+                })
+                .into_any_element()
+        }
+        _ => {
+            let engine_model = engine_model.clone();
+            let name = option.name.clone();
+            Button::new(id)
+                .label("Run")
+                .outline()
+                .small()
+                .on_click(move |_, _, cx| {
+                    engine_model.update(cx, |engine, cx| {
+                        engine.push_button(&name);
+                        cx.notify();
+                    });
+                })
+                .into_any_element()
+        }
+    };
+
+    div()
+        .flex()
+        .items_center()
+        .justify_between()
+        .gap_4()
+        .child(div().text_sm().child(option.name.clone()))
+        .child(div().w(px(200.)).flex().justify_end().child(editor))
+}
+
+/// Validate the edited values and collect the ones that differ from the engine's
+fn changed_options(
+    fields: &[(UciOption, OptionField)],
+    engine_model: &Entity<EngineModel>,
+    cx: &App,
+) -> Result<Vec<OptionSetting>, String> {
+    let engine = engine_model.read(cx);
+    let mut changed = Vec::new();
+
+    for (option, field) in fields {
+        let value = match field {
+            OptionField::Text(input) => input.read(cx).value().to_string(),
+            OptionField::Choice(choice) => choice.read(cx).clone(),
+            OptionField::Button => continue,
+        };
+        let value = option
+            .validate(&value)
+            .ok_or_else(|| match &option.option_type {
+                UciOptionType::Spin { min, max, .. } => {
+                    format!("{} must be a number from {} to {}", option.name, min, max)
+                }
+                _ => format!("Invalid value for {}", option.name),
+            })?;
+        if engine.option_value(option).as_ref() != Some(&value) {
+            changed.push(OptionSetting {
+                name: option.name.clone(),
+                value,
+            });
+        }
+    }
+
+    Ok(changed)
+}
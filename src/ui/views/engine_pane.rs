@@ -1,12 +1,22 @@
 //! Engine analysis pane - displays UCI engine output with start/stop control.
 
-use gpui::{App, Entity, SharedString, div, prelude::*, px, rgb};
+use gpui::{App, Corner, Entity, SharedString, div, prelude::*, px, rgb};
 use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::menu::{DropdownMenu, PopupMenuItem};
+use gpui_component::{Disableable, Sizable};
 
-use crate::domain::uci::{Score, UciInfo};
-use crate::models::EngineModel;
+use shakmaty::Chess;
+
+use super::board_view::{BoardSettings, PreviewState};
+use super::{
+    AddEngine, AddEvaluationComment, EngineArrows, EngineMatch, EngineOptions, PlayEngineLine,
+    SelectEngine, SetEngineArrows,
+};
+use crate::domain::uci::{AnalysisLimit, Score, UciInfo};
+use crate::domain::{parse_fen, uci_line_to_san};
+use crate::models::{EngineModel, EngineRegistryModel};
 use crate::ui::theme::{
-    BOARD_PADDING, BORDER_COLOR, MOVE_LIST_BG, PANEL_BG, TEXT_PRIMARY, TEXT_SECONDARY,
+    BOARD_PADDING, BORDER_COLOR, ERROR_TEXT, MOVE_LIST_BG, PANEL_BG, TEXT_PRIMARY, TEXT_SECONDARY,
 };
 
 // Colors for evaluation display
@@ -16,14 +26,32 @@ const EVAL_NEUTRAL: u32 = 0xa1a1aa; // gray - equal
 #[allow(dead_code)] // Reserved for mate display
 const EVAL_MATE: u32 = 0xfbbf24; // yellow/gold - mate
 
+/// Arrow settings offered in the engine menu
+const ARROW_MODES: [(&str, EngineArrows); 3] = [
+    ("No Arrows", EngineArrows::Off),
+    ("Best Move Arrow", EngineArrows::BestMove),
+    ("Arrows for All Lines", EngineArrows::AllLines),
+];
+
+/// Most moves of a principal variation to show
+const PV_DISPLAY_MOVES: usize = 8;
+
 /// Render the engine analysis pane.
 /// Shows parsed analysis (eval, depth, PV) and raw output below.
-pub fn render_engine_pane(engine_model: &Entity<EngineModel>, cx: &App) -> impl IntoElement {
+pub fn render_engine_pane(
+    engine_model: &Entity<EngineModel>,
+    engine_registry: &Entity<EngineRegistryModel>,
+    board_settings: &Entity<BoardSettings>,
+    preview_state: &Entity<PreviewState>,
+    cx: &App,
+) -> impl IntoElement {
     let engine = engine_model.read(cx);
     let is_running = engine.is_running();
     let is_analyzing = engine.is_analyzing();
     let analysis_lines = engine.analysis_lines();
     let black_to_move = engine.is_black_to_move();
+    // The analyzed position, for showing lines in SAN
+    let position = engine.current_fen().and_then(|fen| parse_fen(fen).ok());
     let output_lines = engine.output_lines();
 
     // Start/Stop button
@@ -44,35 +72,76 @@ pub fn render_engine_pane(engine_model: &Entity<EngineModel>, cx: &App) -> impl
             .label("Start")
             .primary()
             .compact()
-            .on_click(move |_, _, cx| {
-                engine_model_clone.update(cx, |engine, cx| {
-                    if let Err(e) = engine.start(cx) {
-                        eprintln!("Failed to start engine: {}", e);
-                    }
-                    cx.notify();
-                });
+            .disabled(engine_registry.read(cx).selected().is_none())
+            .on_click({
+                let engine_registry = engine_registry.clone();
+                move |_, _, cx| {
+                    let Some(profile) = engine_registry.read(cx).selected().cloned() else {
+                        return;
+                    };
+                    engine_model_clone.update(cx, |engine, cx| {
+                        // A failure to start is shown in the pane
+                        let _ = engine.start(&profile, cx);
+                        cx.notify();
+                    });
+                }
             })
     };
 
     // Status indicator
     let status_text = if is_running {
-        if is_analyzing {
+        if !engine.is_ready() {
+            "Starting..."
+        } else if is_analyzing {
             "Analyzing..."
+        } else if engine.current_fen().is_some() {
+            // A limited search has finished
+            "Done"
         } else {
             "Ready"
         }
+    } else if engine.error().is_some() {
+        "Error"
     } else {
         "Stopped"
     };
 
     let status_color = if is_running { EVAL_POSITIVE } else { 0xf87171 };
 
+    // The engine's own name and author once it has identified itself
+    let identity = engine.identity();
+    let engine_title = identity
+        .name
+        .clone()
+        .unwrap_or_else(|| "Engine".to_string());
+    let engine_author = identity.author.clone();
+
     // Build the analysis display section
-    let analysis_section = render_analysis_section(&analysis_lines, black_to_move, is_running);
+    let analysis_section = render_analysis_section(
+        &analysis_lines,
+        position.as_ref(),
+        preview_state,
+        black_to_move,
+        is_running,
+        engine.error(),
+    );
 
     // Build the raw output section
     let raw_output_section = render_raw_output_section(output_lines);
 
+    // A failure to select or save the engine choice
+    let registry_error = engine_registry.read(cx).error().map(|error| {
+        div()
+            .flex_shrink_0()
+            .px_4()
+            .py_2()
+            .border_b_1()
+            .border_color(rgb(BORDER_COLOR))
+            .text_sm()
+            .text_color(rgb(ERROR_TEXT))
+            .child(error.to_string())
+    });
+
     let engine_pane = div()
         .flex_1()
         .min_h_0()
@@ -99,12 +168,25 @@ pub fn render_engine_pane(engine_model: &Entity<EngineModel>, cx: &App) -> impl
                         .flex()
                         .items_center()
                         .gap_2()
+                        .min_w_0()
                         .child(
                             div()
                                 .text_color(rgb(TEXT_PRIMARY))
                                 .font_weight(gpui::FontWeight::SEMIBOLD)
-                                .child("Engine"),
+                                .overflow_hidden()
+                                .text_ellipsis()
+                                .child(engine_title),
                         )
+                        .when_some(engine_author, |el, author| {
+                            el.child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(TEXT_SECONDARY))
+                                    .overflow_hidden()
+                                    .text_ellipsis()
+                                    .child(format!("by {}", author)),
+                            )
+                        })
                         .child(
                             div()
                                 .text_xs()
@@ -112,8 +194,23 @@ pub fn render_engine_pane(engine_model: &Entity<EngineModel>, cx: &App) -> impl
                                 .child(status_text),
                         ),
                 )
-                .child(toggle_button),
+                .child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .gap_1()
+                        .child(render_analysis_limit_menu(engine_model, cx))
+                        .child(render_multi_pv_controls(engine_model, cx))
+                        .child(render_engine_menu(
+                            engine_model,
+                            engine_registry,
+                            board_settings,
+                            cx,
+                        ))
+                        .child(toggle_button),
+                ),
         )
+        .children(registry_error)
         // Analysis section (shows all PV lines)
         .child(analysis_section)
         // Raw output section (scrollable, takes remaining space)
@@ -129,25 +226,173 @@ pub fn render_engine_pane(engine_model: &Entity<EngineModel>, cx: &App) -> impl
         .child(engine_pane)
 }
 
+/// Render the selector for how long each position is searched
+fn render_analysis_limit_menu(engine_model: &Entity<EngineModel>, cx: &App) -> impl IntoElement {
+    let current = engine_model.read(cx).analysis_limit();
+    let engine_model = engine_model.clone();
+
+    Button::new("analysis-limit-menu")
+        .label(current.label())
+        .ghost()
+        .xsmall()
+        .dropdown_menu_with_anchor(Corner::TopRight, move |menu, _window, _cx| {
+            AnalysisLimit::PRESETS.iter().fold(menu, |menu, &limit| {
+                let engine_model = engine_model.clone();
+                menu.item(
+                    PopupMenuItem::new(limit.label())
+                        .checked(limit == current)
+                        .on_click(move |_, _, cx| {
+                            engine_model.update(cx, |engine, cx| {
+                                engine.set_analysis_limit(limit);
+                                cx.notify();
+                            });
+                        }),
+                )
+            })
+        })
+}
+
+/// Render the -/+ controls for the number of analysis lines
+fn render_multi_pv_controls(engine_model: &Entity<EngineModel>, cx: &App) -> impl IntoElement {
+    let engine = engine_model.read(cx);
+    let multi_pv = engine.multi_pv();
+    let max_multi_pv = engine.max_multi_pv();
+    let step_button = |id: &'static str, label: &'static str, target: u32, disabled: bool| {
+        let engine_model = engine_model.clone();
+        Button::new(id)
+            .label(label)
+            .ghost()
+            .xsmall()
+            .disabled(disabled)
+            .on_click(move |_, _, cx| {
+                engine_model.update(cx, |engine, cx| {
+                    engine.set_multi_pv(target);
+                    cx.notify();
+                });
+            })
+    };
+
+    div()
+        .flex()
+        .items_center()
+        .child(step_button(
+            "multipv-less",
+            "-",
+            multi_pv.saturating_sub(1),
+            multi_pv <= 1,
+        ))
+        .child(
+            div()
+                .text_xs()
+                .text_color(rgb(TEXT_SECONDARY))
+                .child(if multi_pv == 1 {
+                    "1 line".to_string()
+                } else {
+                    format!("{} lines", multi_pv)
+                }),
+        )
+        .child(step_button(
+            "multipv-more",
+            "+",
+            multi_pv + 1,
+            multi_pv >= max_multi_pv,
+        ))
+}
+
+/// Render the engine menu: installed engines, annotation, arrows, options, matches and
+/// "Add Engine..."
+fn render_engine_menu(
+    engine_model: &Entity<EngineModel>,
+    engine_registry: &Entity<EngineRegistryModel>,
+    board_settings: &Entity<BoardSettings>,
+    cx: &App,
+) -> impl IntoElement {
+    let label = engine_registry
+        .read(cx)
+        .selected()
+        .map(|engine| engine.name.clone())
+        .unwrap_or_else(|| "No Engine".to_string());
+    let engine_model = engine_model.clone();
+    let engine_registry = engine_registry.clone();
+    let board_settings = board_settings.clone();
+
+    Button::new("engine-menu")
+        .label(label)
+        .ghost()
+        .small()
+        .dropdown_menu_with_anchor(Corner::TopRight, move |menu, _window, cx| {
+            let registry = engine_registry.read(cx);
+            let selected = registry.selected().map(|engine| engine.name.clone());
+            let arrows = board_settings.read(cx).engine_arrows;
+            let menu = registry
+                .engines()
+                .iter()
+                .fold(menu, |menu, engine| {
+                    menu.menu_with_check(
+                        engine.name.clone(),
+                        selected.as_ref() == Some(&engine.name),
+                        Box::new(SelectEngine {
+                            name: engine.name.clone(),
+                        }),
+                    )
+                })
+                .separator()
+                .menu_with_disabled(
+                    "Add Evaluation to Comment",
+                    Box::new(AddEvaluationComment),
+                    engine_model.read(cx).evaluation_comment().is_none(),
+                )
+                .separator();
+            ARROW_MODES
+                .into_iter()
+                .fold(menu, |menu, (label, mode)| {
+                    menu.menu_with_check(
+                        label,
+                        arrows == mode,
+                        Box::new(SetEngineArrows { arrows: mode }),
+                    )
+                })
+                .separator()
+                .menu_with_disabled(
+                    "Engine Options...",
+                    Box::new(EngineOptions),
+                    engine_model.read(cx).options().is_empty(),
+                )
+                .menu_with_disabled(
+                    "Engine Match...",
+                    Box::new(EngineMatch),
+                    registry.engines().is_empty(),
+                )
+                .menu("Add Engine...", Box::new(AddEngine))
+        })
+}
+
 /// Render the main analysis display (all PV lines)
 fn render_analysis_section(
     analysis_lines: &[&UciInfo],
+    position: Option<&Chess>,
+    preview_state: &Entity<PreviewState>,
     black_to_move: bool,
     is_running: bool,
+    error: Option<&str>,
 ) -> impl IntoElement {
     let content = if !analysis_lines.is_empty() {
         // Show all analysis lines
         div().flex().flex_col().gap_2().children(
-            analysis_lines
-                .iter()
-                .enumerate()
-                .map(|(i, info)| render_pv_line(info, i == 0, black_to_move)),
+            analysis_lines.iter().enumerate().map(|(i, info)| {
+                render_pv_line(info, i == 0, position, preview_state, black_to_move)
+            }),
         )
     } else if is_running {
         div()
             .text_color(rgb(TEXT_SECONDARY))
             .text_sm()
             .child("Waiting for analysis...")
+    } else if let Some(error) = error {
+        div()
+            .text_color(rgb(ERROR_TEXT))
+            .text_sm()
+            .child(error.to_string())
     } else {
         div()
             .text_color(rgb(TEXT_SECONDARY))
@@ -165,9 +410,15 @@ fn render_analysis_section(
 }
 
 /// Render a single PV line
-fn render_pv_line(info: &UciInfo, is_best: bool, black_to_move: bool) -> gpui::Div {
+fn render_pv_line(
+    info: &UciInfo,
+    is_best: bool,
+    position: Option<&Chess>,
+    preview_state: &Entity<PreviewState>,
+    black_to_move: bool,
+) -> gpui::Div {
     let (eval_text, eval_color) = format_evaluation(info.score, black_to_move);
-    let pv_text = format_pv(&info.pv);
+    let pv_moves = render_pv_moves(info, position, preview_state);
 
     if is_best {
         // Best line gets prominent display
@@ -220,14 +471,7 @@ fn render_pv_line(info: &UciInfo, is_best: bool, black_to_move: bool) -> gpui::D
                     ),
             )
             // Principal variation
-            .child(
-                div()
-                    .text_sm()
-                    .text_color(rgb(TEXT_PRIMARY))
-                    .overflow_hidden()
-                    .text_ellipsis()
-                    .child(pv_text),
-            )
+            .child(pv_moves.text_sm().text_color(rgb(TEXT_PRIMARY)))
     } else {
         // Secondary lines get compact display
         div()
@@ -247,15 +491,7 @@ fn render_pv_line(info: &UciInfo, is_best: bool, black_to_move: bool) -> gpui::D
                     .child(eval_text),
             )
             // PV
-            .child(
-                div()
-                    .flex_1()
-                    .text_xs()
-                    .text_color(rgb(TEXT_SECONDARY))
-                    .overflow_hidden()
-                    .text_ellipsis()
-                    .child(pv_text),
-            )
+            .child(pv_moves.flex_1().text_xs().text_color(rgb(TEXT_SECONDARY)))
     }
 }
 
@@ -365,21 +601,56 @@ fn format_depth(depth: Option<u32>, seldepth: Option<u32>) -> String {
     }
 }
 
-/// Format the principal variation for display
-fn format_pv(pv: &[String]) -> String {
-    if pv.is_empty() {
-        return "...".to_string();
+/// Render the principal variation in SAN with move numbers.
+/// Hovering a move previews the position after it; clicking plays the line up to it.
+fn render_pv_moves(
+    info: &UciInfo,
+    position: Option<&Chess>,
+    preview_state: &Entity<PreviewState>,
+) -> gpui::Div {
+    let row = div().flex().items_center().gap_1().overflow_hidden();
+    let line = position
+        .map(|position| uci_line_to_san(position, &info.pv))
+        .unwrap_or_default();
+    if line.is_empty() {
+        return row.child("...");
     }
 
-    // Show first several moves, join with spaces
-    let display_moves: Vec<&str> = pv.iter().take(8).map(|s| s.as_str()).collect();
-    let mut result = display_moves.join(" ");
-
-    if pv.len() > 8 {
-        result.push_str(" ...");
-    }
+    let pv_num = info.multipv.unwrap_or(1);
+    let moves = line
+        .into_iter()
+        .take(PV_DISPLAY_MOVES)
+        .enumerate()
+        .map(|(i, line_move)| {
+            let moves = info.pv[..=i].to_vec();
+            let label = match line_move.number {
+                Some(number) => format!("{} {}", number, line_move.san),
+                None => line_move.san,
+            };
+            let id = SharedString::from(format!("pv-{}-{}", pv_num, i));
+            let on_hover =
+                PreviewState::hover_listener(preview_state, id.clone(), line_move.position);
+            div()
+                .id(id)
+                .flex_shrink_0()
+                .px_0p5()
+                .rounded_sm()
+                .cursor_pointer()
+                .hover(|s| s.bg(rgb(BORDER_COLOR)))
+                .on_hover(on_hover)
+                .on_click(move |_, window, cx| {
+                    window.dispatch_action(
+                        Box::new(PlayEngineLine {
+                            moves: moves.clone(),
+                        }),
+                        cx,
+                    );
+                })
+                .child(label)
+        });
 
-    result
+    row.children(moves)
+        .when(info.pv.len() > PV_DISPLAY_MOVES, |row| row.child("..."))
 }
 
 /// Format search statistics
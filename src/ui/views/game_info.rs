@@ -0,0 +1,232 @@
+//! Game info panel - the game's players and event above the move list,
+//! and the dialog for editing its PGN tags.
+
+use std::rc::Rc;
+
+use gpui::{App, Div, Entity, FontWeight, SharedString, Window, div, prelude::*, px, rgb};
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::input::{Input, InputState};
+use gpui_component::{WindowExt, v_flex};
+
+use super::EditGameInfo;
+use crate::domain::{COMMON_TAGS, GameHeaders, SEVEN_TAG_ROSTER, is_valid_tag_name};
+use crate::models::GameModel;
+use crate::ui::theme::{
+    BOARD_PADDING, BORDER_COLOR, ERROR_TEXT, MOVE_LIST_BG, PANEL_BG, TEXT_PRIMARY, TEXT_SECONDARY,
+};
+
+/// Width of the tag names in the edit dialog
+const TAG_LABEL_WIDTH: f32 = 96.0;
+
+/// Render the players, event and other details of the game
+pub fn render_game_info_panel(model: &Entity<GameModel>, cx: &App) -> Div {
+    let headers = model.read(cx).headers();
+    let player = |name: &str| headers.known(name).unwrap_or("?").to_string();
+    let event: Vec<&str> = ["Event", "Site", "Round", "Date"]
+        .into_iter()
+        .filter_map(|name| headers.known(name))
+        .collect();
+    let details: Vec<String> = [("ECO", "ECO"), ("TimeControl", "Time control")]
+        .into_iter()
+        .filter_map(|(name, label)| headers.known(name).map(|value| format!("{label} {value}")))
+        .collect();
+
+    let secondary = |text: String| {
+        div()
+            .text_xs()
+            .text_color(rgb(TEXT_SECONDARY))
+            .overflow_hidden()
+            .text_ellipsis()
+            .child(text)
+    };
+
+    let info_pane = div()
+        .flex()
+        .flex_col()
+        .gap_1()
+        .px_3()
+        .py_2()
+        .bg(rgb(MOVE_LIST_BG))
+        .border_1()
+        .border_color(rgb(BORDER_COLOR))
+        .rounded_md()
+        .child(
+            div()
+                .flex()
+                .items_center()
+                .justify_between()
+                .gap_2()
+                .child(
+                    div()
+                        .min_w_0()
+                        .overflow_hidden()
+                        .text_ellipsis()
+                        .font_weight(FontWeight::BOLD)
+                        .text_color(rgb(TEXT_PRIMARY))
+                        .child(format!("{} - {}", player("White"), player("Black"))),
+                )
+                .child(
+                    Button::new("edit-game-info")
+                        .label("Edit")
+                        .ghost()
+                        .compact()
+                        .on_click(|_, window, cx| {
+                            window.dispatch_action(Box::new(EditGameInfo), cx)
+                        }),
+                ),
+        )
+        .when(!event.is_empty(), |el| {
+            el.child(secondary(event.join(", ")))
+        })
+        .when(!details.is_empty(), |el| {
+            el.child(secondary(details.join(", ")))
+        });
+
+    div()
+        .flex_shrink_0()
+        .bg(rgb(PANEL_BG))
+        .px(px(BOARD_PADDING))
+        .pt(px(BOARD_PADDING))
+        .child(info_pane)
+}
+
+/// Text fields of the game info dialog
+struct TagInputs {
+    /// The roster, common and other existing tags, by name
+    tags: Vec<(String, Entity<InputState>)>,
+    new_name: Entity<InputState>,
+    new_value: Entity<InputState>,
+}
+
+/// Open the dialog for editing the game's PGN tags.
+/// Clearing a field removes the tag; a new tag can be added at the bottom.
+pub fn open_game_info_dialog(model: &Entity<GameModel>, window: &mut Window, cx: &mut App) {
+    let game = model.read(cx);
+    let headers = game.headers().clone();
+    let result = game.result_token().to_string();
+
+    let names = SEVEN_TAG_ROSTER
+        .into_iter()
+        .chain(COMMON_TAGS)
+        .map(str::to_string)
+        .chain(
+            headers
+                .extra_tags()
+                .map(|(name, _)| name.clone())
+                .filter(|name| !COMMON_TAGS.contains(&name.as_str())),
+        )
+        .collect::<Vec<_>>();
+    let tags = names
+        .into_iter()
+        .map(|name| {
+            let value = match name.as_str() {
+                "Result" => result.clone(),
+                _ => headers.get(&name).unwrap_or_default().to_string(),
+            };
+            let input = cx.new(|cx| InputState::new(window, cx).default_value(value));
+            (name, input)
+        })
+        .collect();
+    let inputs = Rc::new(TagInputs {
+        tags,
+        new_name: cx.new(|cx| InputState::new(window, cx).placeholder("New tag, e.g. WhiteElo")),
+        new_value: cx.new(|cx| InputState::new(window, cx).placeholder("Value")),
+    });
+    let error = cx.new(|_| None::<SharedString>);
+    let model = model.clone();
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let message = error.read(cx).clone();
+        let inputs = inputs.clone();
+        let error = error.clone();
+        let model = model.clone();
+
+        dialog
+            .title("Game Info")
+            .confirm()
+            .child(
+                v_flex()
+                    .gap_2()
+                    .children(
+                        inputs
+                            .tags
+                            .iter()
+                            .map(|(name, input)| render_tag_row(name.clone(), Input::new(input))),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap_2()
+                            .child(
+                                div()
+                                    .w(px(TAG_LABEL_WIDTH))
+                                    .child(Input::new(&inputs.new_name)),
+                            )
+                            .child(div().flex_1().child(Input::new(&inputs.new_value))),
+                    )
+                    .children(
+                        message.map(|message| {
+                            div().text_sm().text_color(rgb(ERROR_TEXT)).child(message)
+                        }),
+                    ),
+            )
+            .on_ok(move |_, window, cx| {
+                let headers = model.read(cx).headers().clone();
+                match read_headers(headers, &inputs, cx) {
+                    Ok(headers) => {
+                        model.update(cx, |game, cx| {
+                            game.set_headers(headers);
+                            cx.notify();
+                        });
+                        true
+                    }
+                    Err(e) => {
+                        error.update(cx, |error, _| *error = Some(e.into()));
+                        window.refresh();
+                        false
+                    }
+                }
+            })
+    });
+}
+
+/// Apply the dialog's fields to the game's tags
+fn read_headers(
+    mut headers: GameHeaders,
+    inputs: &TagInputs,
+    cx: &App,
+) -> Result<GameHeaders, String> {
+    for (name, input) in &inputs.tags {
+        headers.set(name, &input.read(cx).value());
+    }
+
+    let name = inputs.new_name.read(cx).value().trim().to_string();
+    if !name.is_empty() {
+        if !is_valid_tag_name(&name) {
+            return Err(format!(
+                "\"{}\" is not a valid tag name: use letters, digits and underscores",
+                name
+            ));
+        }
+        headers.set(&name, &inputs.new_value.read(cx).value());
+    }
+    Ok(headers)
+}
+
+/// Render a tag's name and its field
+fn render_tag_row(name: String, input: Input) -> Div {
+    div()
+        .flex()
+        .items_center()
+        .gap_2()
+        .child(
+            div()
+                .w(px(TAG_LABEL_WIDTH))
+                .flex_shrink_0()
+                .text_sm()
+                .text_color(rgb(TEXT_SECONDARY))
+                .child(name),
+        )
+        .child(div().flex_1().child(input))
+}
@@ -0,0 +1,292 @@
+//! Engine match dialog - pick two engines, the games, openings, limits and output.
+
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use gpui::{App, Corner, Div, Entity, SharedString, Window, div, prelude::*, rgb};
+use gpui_component::button::Button;
+use gpui_component::input::{Input, InputState};
+use gpui_component::menu::{DropdownMenu, PopupMenuItem};
+use gpui_component::{Selectable, Sizable, WindowExt, v_flex};
+
+use crate::domain::{MatchLimit, MatchSettings, Opening, Sprt, parse_openings};
+use crate::models::EngineRegistryModel;
+use crate::ui::theme::{ERROR_TEXT, TEXT_SECONDARY};
+
+/// Default number of games in a match
+const DEFAULT_GAMES: usize = 100;
+
+/// Default limit for match games
+const DEFAULT_LIMIT: MatchLimit = MatchLimit::MoveTime(100);
+
+/// A match to start, with the openings already read
+#[derive(Clone, Debug)]
+pub struct MatchSetup {
+    /// Names of the first and second engine profiles
+    pub engines: [String; 2],
+    pub openings: Vec<Opening>,
+    pub settings: MatchSettings,
+    /// File to append the games to
+    pub output_path: Option<PathBuf>,
+}
+
+/// The choices made with the dialog's buttons
+#[derive(Clone, Debug)]
+struct MatchChoices {
+    engines: [Option<String>; 2],
+    limit: MatchLimit,
+    use_sprt: bool,
+    error: Option<SharedString>,
+}
+
+/// Text fields of the dialog
+struct MatchInputs {
+    games: Entity<InputState>,
+    openings: Entity<InputState>,
+    output: Entity<InputState>,
+    elo0: Entity<InputState>,
+    elo1: Entity<InputState>,
+}
+
+/// Open the dialog for starting an engine match.
+/// `on_start` is called with the match when the user confirms.
+pub fn open_match_dialog(
+    engine_registry: &Entity<EngineRegistryModel>,
+    on_start: impl Fn(MatchSetup, &mut App) + 'static,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let registry = engine_registry.read(cx);
+    let first = registry.selected().map(|profile| profile.name.clone());
+    let second = registry
+        .engines()
+        .iter()
+        .map(|profile| profile.name.clone())
+        .find(|name| Some(name) != first.as_ref())
+        .or_else(|| first.clone());
+    let choices = cx.new(|_| MatchChoices {
+        engines: [first, second],
+        limit: DEFAULT_LIMIT,
+        use_sprt: false,
+        error: None,
+    });
+    let sprt = Sprt::default();
+    let inputs = Rc::new(MatchInputs {
+        games: cx.new(|cx| InputState::new(window, cx).default_value(DEFAULT_GAMES.to_string())),
+        openings: cx
+            .new(|cx| InputState::new(window, cx).placeholder("Optional: FEN, EPD or PGN file")),
+        output: cx.new(|cx| InputState::new(window, cx).placeholder("Optional: match.pgn")),
+        elo0: cx.new(|cx| InputState::new(window, cx).default_value(sprt.elo0.to_string())),
+        elo1: cx.new(|cx| InputState::new(window, cx).default_value(sprt.elo1.to_string())),
+    });
+    let engine_registry = engine_registry.clone();
+    let on_start = Rc::new(on_start);
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let current = choices.read(cx).clone();
+        let choices = choices.clone();
+        let inputs = inputs.clone();
+        let on_start = on_start.clone();
+
+        dialog
+            .title("Engine Match")
+            .confirm()
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(render_engines_section(&choices, &current, &engine_registry))
+                    .child(render_section("Games").child(Input::new(&inputs.games)))
+                    .child(render_limit_section(&choices, &current))
+                    .child(render_section("Openings").child(Input::new(&inputs.openings)))
+                    .child(render_section("Save games to").child(Input::new(&inputs.output)))
+                    .child(render_sprt_section(&choices, &current, &inputs))
+                    .children(
+                        current.error.map(|message| {
+                            div().text_sm().text_color(rgb(ERROR_TEXT)).child(message)
+                        }),
+                    ),
+            )
+            .on_ok(move |_, window, cx| {
+                let current = choices.read(cx).clone();
+                match read_setup(&current, &inputs, cx) {
+                    Ok(setup) => {
+                        on_start(setup, cx);
+                        true
+                    }
+                    Err(e) => {
+                        choices.update(cx, |choices, _| choices.error = Some(e.into()));
+                        window.refresh();
+                        false
+                    }
+                }
+            })
+    });
+}
+
+/// Check the dialog's fields and read the openings file
+fn read_setup(
+    choices: &MatchChoices,
+    inputs: &MatchInputs,
+    cx: &App,
+) -> Result<MatchSetup, String> {
+    let [Some(first), Some(second)] = choices.engines.clone() else {
+        return Err("Choose two engines".to_string());
+    };
+    let games = inputs
+        .games
+        .read(cx)
+        .value()
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|&games| games > 0)
+        .ok_or("The number of games must be a positive whole number")?;
+
+    let openings_path = inputs.openings.read(cx).value().trim().to_string();
+    let openings = if openings_path.is_empty() {
+        Vec::new()
+    } else {
+        let text = fs::read_to_string(&openings_path)
+            .map_err(|e| format!("Failed to read {}: {}", openings_path, e))?;
+        parse_openings(&text).map_err(|e| format!("{}: {}", openings_path, e))?
+    };
+
+    let sprt = if choices.use_sprt {
+        let bound = |input: &Entity<InputState>| input.read(cx).value().trim().parse::<f64>().ok();
+        let (Some(elo0), Some(elo1)) = (bound(&inputs.elo0), bound(&inputs.elo1)) else {
+            return Err("The SPRT bounds must be numbers".to_string());
+        };
+        if elo0 >= elo1 {
+            return Err("Elo0 must be less than Elo1".to_string());
+        }
+        Some(Sprt {
+            elo0,
+            elo1,
+            ..Sprt::default()
+        })
+    } else {
+        None
+    };
+
+    let output_path = inputs.output.read(cx).value().trim().to_string();
+    Ok(MatchSetup {
+        engines: [first, second],
+        openings,
+        settings: MatchSettings {
+            games,
+            limit: choices.limit.clone(),
+            sprt,
+        },
+        output_path: (!output_path.is_empty()).then(|| output_path.into()),
+    })
+}
+
+/// Render a labelled section of the dialog
+fn render_section(label: &'static str) -> Div {
+    v_flex()
+        .gap_1()
+        .child(div().text_xs().text_color(rgb(TEXT_SECONDARY)).child(label))
+}
+
+fn render_engines_section(
+    choices: &Entity<MatchChoices>,
+    current: &MatchChoices,
+    engine_registry: &Entity<EngineRegistryModel>,
+) -> Div {
+    let buttons = [("match-engine-1", 0), ("match-engine-2", 1)].map(|(id, slot)| {
+        let selected = current.engines[slot].clone();
+        let choices = choices.clone();
+        let engine_registry = engine_registry.clone();
+        Button::new(id)
+            .label(selected.clone().unwrap_or_else(|| "No Engine".to_string()))
+            .outline()
+            .small()
+            .dropdown_menu_with_anchor(Corner::TopLeft, move |menu, _window, cx| {
+                engine_registry
+                    .read(cx)
+                    .engines()
+                    .iter()
+                    .fold(menu, |menu, engine| {
+                        let choices = choices.clone();
+                        let name = engine.name.clone();
+                        menu.item(
+                            PopupMenuItem::new(name.clone())
+                                .checked(selected.as_ref() == Some(&name))
+                                .on_click(move |_, window, cx| {
+                                    choices.update(cx, |choices, _| {
+                                        choices.engines[slot] = Some(name.clone());
+                                    });
+                                    window.refresh();
+                                }),
+                        )
+                    })
+            })
+    });
+    let [first, second] = buttons;
+
+    render_section("Engines (the score is for the first)").child(
+        div()
+            .flex()
+            .items_center()
+            .gap_2()
+            .child(first)
+            .child(div().text_sm().text_color(rgb(TEXT_SECONDARY)).child("vs"))
+            .child(second),
+    )
+}
+
+fn render_limit_section(choices: &Entity<MatchChoices>, current: &MatchChoices) -> Div {
+    let buttons = MatchLimit::presets().into_iter().map(|limit| {
+        let choices = choices.clone();
+        let label = limit.label();
+        Button::new(SharedString::from(format!("match-limit-{label}")))
+            .label(label)
+            .small()
+            .selected(current.limit == limit)
+            .on_click(move |_, window, cx| {
+                choices.update(cx, |choices, _| choices.limit = limit.clone());
+                window.refresh();
+            })
+    });
+
+    render_section("Limit (clock, time per move or nodes per move)")
+        .child(div().flex().flex_wrap().gap_1().children(buttons))
+}
+
+fn render_sprt_section(
+    choices: &Entity<MatchChoices>,
+    current: &MatchChoices,
+    inputs: &MatchInputs,
+) -> Div {
+    let choices = choices.clone();
+    let toggle = Button::new("match-sprt")
+        .label("Stop early with an SPRT")
+        .small()
+        .selected(current.use_sprt)
+        .on_click(move |_, window, cx| {
+            choices.update(cx, |choices, _| choices.use_sprt = !choices.use_sprt);
+            window.refresh();
+        });
+    let bound = |label: &'static str, input: &Entity<InputState>| {
+        div()
+            .flex()
+            .flex_1()
+            .items_center()
+            .gap_1()
+            .child(div().text_sm().child(label))
+            .child(Input::new(input))
+    };
+
+    render_section("SPRT (H0: Elo0, H1: Elo1, α = β = 0.05)")
+        .child(div().flex().child(toggle))
+        .when(current.use_sprt, |el| {
+            el.child(
+                div()
+                    .flex()
+                    .gap_2()
+                    .child(bound("Elo0", &inputs.elo0))
+                    .child(bound("Elo1", &inputs.elo1)),
+            )
+        })
+}
@@ -0,0 +1,159 @@
+//! Match panel - progress and statistics of an engine-vs-engine match.
+
+use std::time::Instant;
+
+use gpui::{App, Div, Entity, FontWeight, div, prelude::*, px, rgb};
+use gpui_component::button::{Button, ButtonVariants};
+
+use super::{CloseMatch, StopMatch};
+use crate::domain::{PieceColor, SprtVerdict, format_clock};
+use crate::models::MatchModel;
+use crate::ui::theme::{
+    BOARD_PADDING, BORDER_COLOR, ERROR_TEXT, MOVE_LIST_BG, PANEL_BG, TEXT_PRIMARY, TEXT_SECONDARY,
+};
+
+/// Render the state of the current or last engine match.
+/// Nothing is shown if no match has been started.
+pub fn render_match_panel(match_model: &Entity<MatchModel>, cx: &App) -> Div {
+    let engine_match = match_model.read(cx);
+    let (Some(settings), [first, second]) = (engine_match.settings(), engine_match.names()) else {
+        return div();
+    };
+    let score = engine_match.score();
+    let played = engine_match.games_played();
+
+    let progress = if engine_match.is_running() {
+        format!(
+            "Game {} of {}",
+            (played + 1).min(settings.games),
+            settings.games
+        )
+    } else {
+        format!("Finished after {} of {} games", played, settings.games)
+    };
+    let score_line = match score.score() {
+        Some(points) => format!(
+            "+{} ={} -{}  ({:.1}%)",
+            score.wins,
+            score.draws,
+            score.losses,
+            points * 100.0
+        ),
+        None => "No games finished yet".to_string(),
+    };
+    let elo_line = match score.elo() {
+        Some(estimate) => format!("Elo {}", estimate),
+        None => "Elo not available yet".to_string(),
+    };
+    let sprt_line = settings.sprt.map(|sprt| {
+        let (lower, upper) = sprt.bounds();
+        let verdict = match sprt.verdict(&score) {
+            SprtVerdict::Continue => "",
+            SprtVerdict::AcceptH0 => " - H0 accepted",
+            SprtVerdict::AcceptH1 => " - H1 accepted",
+        };
+        format!(
+            "SPRT [{}, {}]: LLR {:.2} ({:.2}, {:.2}){}",
+            sprt.elo0,
+            sprt.elo1,
+            sprt.llr(&score),
+            lower,
+            upper,
+            verdict
+        )
+    });
+
+    let now = Instant::now();
+    let current_game = engine_match.game().map(|game| {
+        let player = |color: PieceColor| {
+            let name = engine_match.player(color).unwrap_or("?");
+            match game.clock() {
+                Some(clock) => format!("{} {}", name, format_clock(clock.remaining(color, now))),
+                None => name.to_string(),
+            }
+        };
+        format!(
+            "{} - {}",
+            player(PieceColor::White),
+            player(PieceColor::Black)
+        )
+    });
+    let last_game = engine_match
+        .last_outcome()
+        .map(|outcome| format!("Last game: {}", outcome));
+
+    let control = if engine_match.is_running() {
+        Button::new("stop-match")
+            .label("Stop")
+            .danger()
+            .compact()
+            .on_click(|_, window, cx| window.dispatch_action(Box::new(StopMatch), cx))
+    } else {
+        Button::new("close-match")
+            .label("Close")
+            .compact()
+            .on_click(|_, window, cx| window.dispatch_action(Box::new(CloseMatch), cx))
+    };
+
+    let secondary = |text: String| div().text_xs().text_color(rgb(TEXT_SECONDARY)).child(text);
+
+    let match_pane = div()
+        .flex()
+        .flex_col()
+        .gap_1()
+        .p_3()
+        .bg(rgb(MOVE_LIST_BG))
+        .border_1()
+        .border_color(rgb(BORDER_COLOR))
+        .rounded_md()
+        .child(
+            div()
+                .flex()
+                .items_center()
+                .justify_between()
+                .gap_2()
+                .child(
+                    div()
+                        .min_w_0()
+                        .overflow_hidden()
+                        .text_ellipsis()
+                        .font_weight(FontWeight::BOLD)
+                        .text_color(rgb(TEXT_PRIMARY))
+                        .child(format!("{} vs {}", first, second)),
+                )
+                .child(control),
+        )
+        .child(secondary(format!(
+            "{}, {}",
+            progress,
+            settings.limit.label()
+        )))
+        .children(current_game.map(secondary))
+        .child(
+            div()
+                .text_sm()
+                .text_color(rgb(TEXT_PRIMARY))
+                .child(score_line),
+        )
+        .child(
+            div()
+                .text_sm()
+                .text_color(rgb(TEXT_PRIMARY))
+                .child(elo_line),
+        )
+        .children(sprt_line.map(secondary))
+        .children(last_game.map(secondary))
+        .children(engine_match.error().map(|e| {
+            div()
+                .text_xs()
+                .text_color(rgb(ERROR_TEXT))
+                .child(e.to_string())
+        }));
+
+    div()
+        .flex_shrink_0()
+        .bg(rgb(PANEL_BG))
+        .px(px(BOARD_PADDING))
+        .pt(px(BOARD_PADDING))
+        .child(match_pane)
+}
@@ -1,10 +1,30 @@
 mod board_view;
+mod clock_dialog;
+mod engine_options;
 mod engine_pane;
+mod game_info;
+mod match_dialog;
+mod match_panel;
 mod move_list;
+mod play_dialog;
+mod play_panel;
+mod setup_panel;
 
 pub use board_view::{
-    ChessBoardView, DeleteMove, MoveBack, MoveForward, MoveToEnd, MoveToStart, PromoteToMainLine,
-    PromoteVariation,
+    AddEngine, AddEvaluationComment, CancelSetup, ChessBoardView, ClaimDraw, CloseMatch,
+    DeleteMove, EditComment, EditGameInfo, EditPosition, EngineArrows, EngineMatch, EngineOptions,
+    FinishSetup, FlipBoard, MoveBack, MoveForward, MoveToEnd, MoveToStart, NewGame, NewGameFromFen,
+    OfferDraw, OpenPgn, PlayEngine, PlayEngineLine, PromoteToMainLine, PromoteVariation, Resign,
+    SavePgn, SelectEngine, SetEngineArrows, StartClock, StopMatch, ToggleAutoFlip, ToggleClock,
+    ToggleCoordinates, ToggleNag,
 };
+pub use clock_dialog::open_clock_dialog;
+pub use engine_options::open_engine_options_dialog;
 pub use engine_pane::render_engine_pane;
+pub use game_info::{open_game_info_dialog, render_game_info_panel};
+pub use match_dialog::{MatchSetup, open_match_dialog};
+pub use match_panel::render_match_panel;
 pub use move_list::render_move_list_panel;
+pub use play_dialog::{PlaySetup, open_play_dialog};
+pub use play_panel::render_play_panel;
+pub use setup_panel::render_setup_panel;
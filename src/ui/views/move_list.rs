@@ -2,17 +2,24 @@
 //!
 //! Uses a hybrid display: main line inline, variations as expandable sections.
 
+use std::collections::HashSet;
 use std::mem;
 
-use gpui::{AnyElement, App, Div, Entity, SharedString, Window, div, prelude::*, px, rgb};
-use gpui_component::Icon;
-use gpui_component::menu::ContextMenuExt;
+use gpui::{AnyElement, App, Corner, Div, Entity, SharedString, Window, div, prelude::*, px, rgb};
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::menu::{ContextMenuExt, DropdownMenu, PopupMenu};
+use gpui_component::{Icon, Sizable};
 
-use super::board_view::MoveListState;
+use super::board_view::{BoardSettings, MoveListState, PreviewState};
 use super::{
-    DeleteMove, MoveBack, MoveForward, MoveToEnd, MoveToStart, PromoteToMainLine, PromoteVariation,
+    ClaimDraw, DeleteMove, EditComment, EditGameInfo, EditPosition, FlipBoard, MoveBack,
+    MoveForward, MoveToEnd, MoveToStart, NewGame, NewGameFromFen, OpenPgn, PlayEngine,
+    PromoteToMainLine, PromoteVariation, SavePgn, StartClock, ToggleAutoFlip, ToggleCoordinates,
+    ToggleNag,
+};
+use crate::domain::{
+    GameOutcome, MOVE_NAGS, MoveNodeId, POSITION_NAGS, is_move_nag, nag_description, nag_glyph,
 };
-use crate::domain::MoveNodeId;
 use crate::models::GameModel;
 use crate::ui::display::{get_sibling_sub_variations, get_sibling_variations, main_line_display};
 use crate::ui::theme::{
@@ -28,12 +35,16 @@ const NAV_BUTTON_HOVER_BG: u32 = 0x4a4a4a;
 const NAV_BUTTON_DISABLED: u32 = 0x555555;
 const VARIATION_BG: u32 = 0x252525;
 const VARIATION_BORDER: u32 = 0x3a3a3a;
+const COMMENT_COLOR: u32 = 0x9ec79e;
+const RESULT_BANNER_BG: u32 = 0x2d3b4f;
 
 /// Render the move list panel for a given game model.
 /// Returns a Div element that can be used as a child.
 pub fn render_move_list_panel(
     model: &Entity<GameModel>,
     move_list_state: &Entity<MoveListState>,
+    board_settings: &Entity<BoardSettings>,
+    preview_state: &Entity<PreviewState>,
     cx: &App,
 ) -> Div {
     let game = model.read(cx);
@@ -43,6 +54,8 @@ pub fn render_move_list_panel(
     let current_node_id = game.current_node_id();
 
     let collapsed_variations = &move_list_state.read(cx).collapsed_variations;
+    let result_banner = render_result_banner(game);
+    let game_comment = game.tree().get(0).and_then(|root| root.comment.clone());
 
     // Note: navigation is handled via actions (see MoveBack, MoveForward, etc.)
 
@@ -50,14 +63,15 @@ pub fn render_move_list_panel(
     let moves_content = if main_line.is_empty() {
         div().text_color(rgb(TEXT_SECONDARY)).child("No moves yet")
     } else {
-        render_main_line_with_variations(
+        let ctx = MoveListContext {
             model,
             move_list_state,
-            &main_line,
-            current_node_id,
+            preview_state,
             game,
+            current_node_id,
             collapsed_variations,
-        )
+        };
+        render_main_line_with_variations(&ctx, &main_line)
     };
 
     let move_list = div()
@@ -71,12 +85,41 @@ pub fn render_move_list_panel(
         // Header (fixed)
         .child(
             div()
+                .flex()
+                .items_center()
+                .justify_between()
                 .p_4()
                 .pb_2()
                 .text_color(rgb(TEXT_PRIMARY))
                 .border_b_1()
                 .border_color(rgb(BORDER_COLOR))
-                .child("Move History"),
+                .child("Move History")
+                .child(
+                    div()
+                        .flex()
+                        .gap_1()
+                        .child(render_view_menu(board_settings))
+                        .child(
+                            Button::new("game-menu")
+                                .label("Game")
+                                .ghost()
+                                .small()
+                                .dropdown_menu_with_anchor(
+                                    Corner::TopRight,
+                                    |menu, _window, _cx| {
+                                        menu.menu("New Game", Box::new(NewGame))
+                                            .menu("New Game from FEN...", Box::new(NewGameFromFen))
+                                            .menu("Set Up Position...", Box::new(EditPosition))
+                                            .menu("Play Against Engine...", Box::new(PlayEngine))
+                                            .menu("Start Clock...", Box::new(StartClock))
+                                            .separator()
+                                            .menu("Game Info...", Box::new(EditGameInfo))
+                                            .menu("Open PGN...", Box::new(OpenPgn))
+                                            .menu("Save PGN...", Box::new(SavePgn))
+                                    },
+                                ),
+                        ),
+                ),
         )
         // Scrollable moves content
         .child(
@@ -86,8 +129,13 @@ pub fn render_move_list_panel(
                 .overflow_y_scroll()
                 .p_4()
                 .pt_2()
+                .when_some(game_comment, |el, comment| {
+                    el.child(render_comment(comment).mb_2())
+                })
                 .child(moves_content),
         )
+        // Result of the game, or a draw that may be claimed
+        .children(result_banner)
         // Navigation buttons at bottom
         .child(
             div()
@@ -133,56 +181,154 @@ pub fn render_move_list_panel(
         .child(move_list)
 }
 
+/// Render the banner below the moves: the result once the game is over,
+/// or the draw that may be claimed at the end of the main line
+fn render_result_banner(game: &GameModel) -> Option<Div> {
+    let banner = div()
+        .flex()
+        .items_center()
+        .gap_2()
+        .px_4()
+        .py_2()
+        .border_t_1()
+        .border_color(rgb(BORDER_COLOR))
+        .bg(rgb(RESULT_BANNER_BG))
+        .text_sm()
+        .text_color(rgb(TEXT_PRIMARY));
+
+    if let Some(outcome) = game.outcome() {
+        return Some(
+            banner
+                .child(
+                    div()
+                        .font_weight(gpui::FontWeight::BOLD)
+                        .child(outcome.result.pgn_token()),
+                )
+                .child(outcome.to_string()),
+        );
+    }
+    let termination = game.claimable_draw()?;
+    Some(
+        banner
+            .child(
+                div()
+                    .flex_1()
+                    .child(format!("{} may be claimed", GameOutcome::draw(termination))),
+            )
+            .child(
+                Button::new("claim-draw")
+                    .label("Claim Draw")
+                    .small()
+                    .on_click(|_, window, cx| window.dispatch_action(Box::new(ClaimDraw), cx)),
+            ),
+    )
+}
+
+/// Render the "View" menu with board display settings
+fn render_view_menu(board_settings: &Entity<BoardSettings>) -> impl IntoElement {
+    let board_settings = board_settings.clone();
+    Button::new("view-menu")
+        .label("View")
+        .ghost()
+        .small()
+        .dropdown_menu_with_anchor(Corner::TopRight, move |menu, _window, cx| {
+            let settings = board_settings.read(cx);
+            menu.menu_with_check("Flip Board", settings.flipped, Box::new(FlipBoard))
+                .menu_with_check(
+                    "Auto-Flip When Playing Black",
+                    settings.auto_flip,
+                    Box::new(ToggleAutoFlip),
+                )
+                .separator()
+                .menu_with_check(
+                    "Show Coordinates",
+                    settings.show_coordinates,
+                    Box::new(ToggleCoordinates),
+                )
+        })
+}
+
+/// Game state and entities shared by the move list's rendering functions
+struct MoveListContext<'a> {
+    model: &'a Entity<GameModel>,
+    move_list_state: &'a Entity<MoveListState>,
+    preview_state: &'a Entity<PreviewState>,
+    game: &'a GameModel,
+    current_node_id: MoveNodeId,
+    collapsed_variations: &'a HashSet<MoveNodeId>,
+}
+
+impl MoveListContext<'_> {
+    /// Hover listener previewing the position after a move on the mini-board
+    fn preview_listener(
+        &self,
+        node_id: MoveNodeId,
+    ) -> impl Fn(&bool, &mut Window, &mut App) + 'static {
+        let position = self
+            .game
+            .tree()
+            .get(node_id)
+            .map(|node| node.position.clone())
+            .unwrap_or_default();
+        PreviewState::hover_listener(self.preview_state, move_node_id(node_id), position)
+    }
+}
+
 /// Render the main line with inline variations
 /// Uses a column layout where main line moves flow inline and variations are block-level
 fn render_main_line_with_variations(
-    model: &Entity<GameModel>,
-    move_list_state: &Entity<MoveListState>,
+    ctx: &MoveListContext,
     main_line: &[MainLineMoveDisplay],
-    current_node_id: MoveNodeId,
-    game: &GameModel,
-    collapsed_variations: &std::collections::HashSet<MoveNodeId>,
 ) -> Div {
     // Build segments: each segment is either inline moves or a variation block
     let mut segments: Vec<AnyElement> = Vec::new();
     let mut current_inline_moves: Vec<AnyElement> = Vec::new();
 
+    // Black moves need their number repeated after a comment ("3...")
+    let mut needs_number = false;
+
     for mv in main_line {
-        let model_move = model.clone();
+        let model_move = ctx.model.clone();
         let node_id = mv.node_id;
-        let is_selected = node_id == current_node_id;
+        let is_selected = node_id == ctx.current_node_id;
 
-        // Add move number for white moves
-        if !mv.is_black {
-            current_inline_moves.push(
-                div()
-                    .text_color(rgb(TEXT_SECONDARY))
-                    .child(format!("{}.", mv.move_num))
-                    .into_any_element(),
-            );
+        if let Some(pre_comment) = &mv.pre_comment {
+            current_inline_moves.push(render_comment(pre_comment.clone()).into_any_element());
+            needs_number = true;
+        }
+
+        // Add move number for white moves (and black moves after a comment)
+        if !mv.is_black || needs_number {
+            current_inline_moves
+                .push(render_move_number(mv.move_num, mv.is_black).into_any_element());
         }
 
         // The move itself (main line = variation_depth 0)
         current_inline_moves.push(
             render_clickable_move_node(
                 node_id,
-                mv.san.clone(),
+                format_move_text(&mv.san, mv.is_check, mv.is_checkmate, &mv.nags),
+                mv.nags.clone(),
                 is_selected,
-                mv.is_check,
-                mv.is_checkmate,
                 model_move,
                 0, // main line
+                ctx.preview_listener(node_id),
             )
             .into_any_element(),
         );
 
+        if let Some(comment) = &mv.comment {
+            current_inline_moves.push(render_comment(comment.clone()).into_any_element());
+        }
+        needs_number = mv.comment.is_some();
+
         // If this move has sibling variations, add collapse button and conditionally render variations
         if mv.has_sibling_variations {
-            let is_collapsed = collapsed_variations.contains(&node_id);
+            let is_collapsed = ctx.collapsed_variations.contains(&node_id);
 
             // Add collapse button after the move
             current_inline_moves.push(
-                render_collapse_button(node_id, is_collapsed, move_list_state.clone())
+                render_collapse_button(node_id, is_collapsed, ctx.move_list_state.clone())
                     .into_any_element(),
             );
 
@@ -200,16 +346,12 @@ fn render_main_line_with_variations(
                     );
                 }
 
-                let variations = get_sibling_variations(game, node_id);
+                let variations = get_sibling_variations(ctx.game, node_id);
                 if !variations.is_empty() {
                     segments.push(
                         render_variations_block(
-                            model,
-                            move_list_state,
+                            ctx,
                             &variations,
-                            current_node_id,
-                            game,
-                            collapsed_variations,
                             1, // first level of variation
                         )
                         .into_any_element(),
@@ -237,12 +379,8 @@ fn render_main_line_with_variations(
 
 /// Render a block of variations
 fn render_variations_block(
-    model: &Entity<GameModel>,
-    move_list_state: &Entity<MoveListState>,
+    ctx: &MoveListContext,
     variations: &[VariationDisplay],
-    current_node_id: MoveNodeId,
-    game: &GameModel,
-    collapsed_variations: &std::collections::HashSet<MoveNodeId>,
     variation_depth: usize,
 ) -> Div {
     div()
@@ -252,73 +390,66 @@ fn render_variations_block(
         .w_full()
         .mt_1()
         .mb_1()
-        .children(variations.iter().map(|var| {
-            render_variation_line(
-                model,
-                move_list_state,
-                var,
-                current_node_id,
-                game,
-                collapsed_variations,
-                variation_depth,
-            )
-        }))
+        .children(
+            variations
+                .iter()
+                .map(|var| render_variation_line(ctx, var, variation_depth)),
+        )
 }
 
 /// Render a single variation line
 fn render_variation_line(
-    model: &Entity<GameModel>,
-    move_list_state: &Entity<MoveListState>,
+    ctx: &MoveListContext,
     variation: &VariationDisplay,
-    current_node_id: MoveNodeId,
-    game: &GameModel,
-    collapsed_variations: &std::collections::HashSet<MoveNodeId>,
     variation_depth: usize,
 ) -> Div {
     // Build the content with proper segmentation for sub-variations
     let mut segments: Vec<AnyElement> = Vec::new();
     let mut current_inline: Vec<AnyElement> = Vec::new();
 
+    // Black moves need their number repeated after a comment ("3...")
+    let mut needs_number = false;
+
     for (i, mv) in variation.moves.iter().enumerate() {
-        let model_move = model.clone();
+        let model_move = ctx.model.clone();
         let node_id = mv.node_id;
-        let is_selected = node_id == current_node_id;
-
-        // Show move number for first move or white moves
-        if i == 0 || !mv.is_black {
-            let num_display = if mv.is_black {
-                format!("{}...", mv.move_num)
-            } else {
-                format!("{}.", mv.move_num)
-            };
-            current_inline.push(
-                div()
-                    .text_color(rgb(TEXT_SECONDARY))
-                    .child(num_display)
-                    .into_any_element(),
-            );
+        let is_selected = node_id == ctx.current_node_id;
+
+        if let Some(pre_comment) = &mv.pre_comment {
+            current_inline.push(render_comment(pre_comment.clone()).into_any_element());
+            needs_number = true;
+        }
+
+        // Show move number for first move, white moves and black moves after a comment
+        if i == 0 || !mv.is_black || needs_number {
+            current_inline.push(render_move_number(mv.move_num, mv.is_black).into_any_element());
         }
 
         current_inline.push(
             render_clickable_move_node(
                 node_id,
-                mv.san.clone(),
+                format_move_text(&mv.san, mv.is_check, mv.is_checkmate, &mv.nags),
+                mv.nags.clone(),
                 is_selected,
-                mv.is_check,
-                mv.is_checkmate,
                 model_move,
                 variation_depth,
+                ctx.preview_listener(node_id),
             )
             .into_any_element(),
         );
 
+        if let Some(comment) = &mv.comment {
+            current_inline.push(render_comment(comment.clone()).into_any_element());
+        }
+        needs_number = mv.comment.is_some();
+
         // Check for sibling sub-variations (alternatives to this move)
         if mv.has_sibling_sub_variations {
-            let is_collapsed = collapsed_variations.contains(&node_id);
+            let is_collapsed = ctx.collapsed_variations.contains(&node_id);
 
             // Add collapse button after the move
             current_inline.push(
-                render_collapse_button(node_id, is_collapsed, move_list_state.clone())
+                render_collapse_button(node_id, is_collapsed, ctx.move_list_state.clone())
                     .into_any_element(),
             );
 
@@ -336,16 +467,12 @@ fn render_variation_line(
                     );
                 }
 
-                let sub_vars = get_sibling_sub_variations(game, node_id);
+                let sub_vars = get_sibling_sub_variations(ctx.game, node_id);
                 if !sub_vars.is_empty() {
                     segments.push(
                         render_variations_block(
-                            model,
-                            move_list_state,
+                            ctx,
                             &sub_vars,
-                            current_node_id,
-                            game,
-                            collapsed_variations,
                             variation_depth + 1, // nested deeper
                         )
                         .into_any_element(),
@@ -381,27 +508,24 @@ fn render_variation_line(
         .children(segments)
 }
 
+/// Element id of a move in the list
+fn move_node_id(node_id: MoveNodeId) -> SharedString {
+    SharedString::from(format!("move-node-{node_id}"))
+}
+
 /// Render a clickable move that navigates to a specific node
 /// variation_depth: 0 = main line, 1+ = inside a variation
 fn render_clickable_move_node(
     node_id: MoveNodeId,
-    san: String,
+    display_text: String,
+    nags: Vec<u8>,
     is_selected: bool,
-    is_check: bool,
-    is_checkmate: bool,
     model: Entity<GameModel>,
     variation_depth: usize,
+    on_hover: impl Fn(&bool, &mut Window, &mut App) + 'static,
 ) -> impl IntoElement {
-    // Build the display text with check/checkmate symbols
-    let mut display_text = san;
-    if is_checkmate {
-        display_text.push('#');
-    } else if is_check {
-        display_text.push('+');
-    }
-
     div()
-        .id(SharedString::from(format!("move-node-{node_id}")))
+        .id(move_node_id(node_id))
         .px_1()
         .rounded(px(3.0))
         .cursor_pointer()
@@ -409,6 +533,7 @@ fn render_clickable_move_node(
         .overflow_hidden()
         .when(is_selected, |el| el.bg(rgb(MOVE_SELECTED_BG)))
         .when(!is_selected, |el| el.hover(|s| s.bg(rgb(MOVE_HOVER_BG))))
+        .on_hover(on_hover)
         .on_click({
             let model = model.clone();
             move |_ev, _window, cx| {
@@ -418,7 +543,7 @@ fn render_clickable_move_node(
                 });
             }
         })
-        .context_menu(move |menu, _window, _cx| {
+        .context_menu(move |menu, window, cx| {
             let mut menu = menu.menu("Delete Move", Box::new(DeleteMove { node_id }));
 
             if variation_depth >= 1 {
@@ -434,11 +559,85 @@ fn render_clickable_move_node(
                 );
             }
 
-            menu
+            // Glyph palette
+            let move_nags = nags.clone();
+            let position_nags = nags.clone();
+            menu.separator()
+                .submenu("Annotate Move", window, cx, move |menu, _, _| {
+                    render_nag_menu(menu, node_id, &MOVE_NAGS, &move_nags)
+                })
+                .submenu("Annotate Position", window, cx, move |menu, _, _| {
+                    render_nag_menu(menu, node_id, &POSITION_NAGS, &position_nags)
+                })
+                .menu("Edit Comment...", Box::new(EditComment { node_id }))
         })
         .child(display_text)
 }
 
+/// Add a checkable item per NAG to an annotation submenu
+fn render_nag_menu(
+    menu: PopupMenu,
+    node_id: MoveNodeId,
+    choices: &[u8],
+    current: &[u8],
+) -> PopupMenu {
+    choices.iter().fold(menu, |menu, &nag| {
+        let label = format!("{}  {}", nag_glyph(nag).unwrap_or(""), nag_description(nag));
+        menu.menu_with_check(
+            label,
+            current.contains(&nag),
+            Box::new(ToggleNag { node_id, nag }),
+        )
+    })
+}
+
+/// Build the display text for a move: SAN, check/checkmate symbol and annotation glyphs
+/// (move assessments attach directly, e.g. "Nf3!?"; position assessments follow, e.g. "Nf3 ±")
+fn format_move_text(san: &str, is_check: bool, is_checkmate: bool, nags: &[u8]) -> String {
+    let mut text = san.to_string();
+    if is_checkmate {
+        text.push('#');
+    } else if is_check {
+        text.push('+');
+    }
+
+    for &nag in nags.iter().filter(|&&nag| is_move_nag(nag)) {
+        if let Some(glyph) = nag_glyph(nag) {
+            text.push_str(glyph);
+        }
+    }
+    for &nag in nags.iter().filter(|&&nag| !is_move_nag(nag)) {
+        match nag_glyph(nag) {
+            Some(glyph) => {
+                text.push(' ');
+                text.push_str(glyph);
+            }
+            None => text.push_str(&format!(" ${}", nag)),
+        }
+    }
+
+    text
+}
+
+/// Render a move number ("12." for white, "12..." for black)
+fn render_move_number(move_num: usize, is_black: bool) -> Div {
+    let text = if is_black {
+        format!("{}...", move_num)
+    } else {
+        format!("{}.", move_num)
+    };
+    div().text_color(rgb(TEXT_SECONDARY)).child(text)
+}
+
+/// Render an inline text comment
+fn render_comment(comment: String) -> Div {
+    div()
+        .text_sm()
+        .italic()
+        .text_color(rgb(COMMENT_COLOR))
+        .child(comment)
+}
+
 /// Render a collapse/expand button for variations
 fn render_collapse_button(
     node_id: MoveNodeId,
@@ -0,0 +1,150 @@
+//! New engine game dialog - pick a side, an engine and a time control.
+
+use std::rc::Rc;
+
+use gpui::{App, Corner, Div, Entity, Window, div, prelude::*, rgb};
+use gpui_component::button::Button;
+use gpui_component::menu::{DropdownMenu, PopupMenuItem};
+use gpui_component::{Selectable, Sizable, WindowExt, v_flex};
+
+use super::clock_dialog::render_time_control_picker;
+use crate::domain::{PieceColor, TimeControl};
+use crate::models::EngineRegistryModel;
+use crate::ui::theme::{ERROR_TEXT, TEXT_SECONDARY};
+
+/// Default time control for a new engine game
+fn default_time_control() -> TimeControl {
+    TimeControl::minutes(5, 3)
+}
+
+/// The choices for a game against the engine
+#[derive(Clone, Debug)]
+pub struct PlaySetup {
+    /// The side the user plays
+    pub human: PieceColor,
+    /// Name of the engine profile to play against
+    pub engine: Option<String>,
+    pub time_control: TimeControl,
+}
+
+/// Open the dialog for starting a game against an engine.
+/// `on_start` is called with the choices when the user confirms.
+pub fn open_play_dialog(
+    engine_registry: &Entity<EngineRegistryModel>,
+    on_start: impl Fn(PlaySetup, &mut App) + 'static,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let engine = engine_registry
+        .read(cx)
+        .selected()
+        .map(|profile| profile.name.clone());
+    let setup = cx.new(|_| PlaySetup {
+        human: PieceColor::White,
+        engine,
+        time_control: default_time_control(),
+    });
+    let engine_registry = engine_registry.clone();
+    let on_start = Rc::new(on_start);
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let current = setup.read(cx).clone();
+        let no_engines = engine_registry.read(cx).engines().is_empty();
+        let setup = setup.clone();
+        let on_start = on_start.clone();
+
+        dialog
+            .title("Play Against Engine")
+            .confirm()
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(render_side_section(&setup, &current))
+                    .child(render_engine_section(&setup, &current, &engine_registry))
+                    .children(render_time_control_picker(&current.time_control, {
+                        let setup = setup.clone();
+                        move |time_control, cx| {
+                            setup.update(cx, |setup, _| setup.time_control = time_control);
+                        }
+                    }))
+                    .when(no_engines, |el| {
+                        el.child(
+                            div()
+                                .text_sm()
+                                .text_color(rgb(ERROR_TEXT))
+                                .child("Add an engine before starting a game"),
+                        )
+                    }),
+            )
+            .on_ok(move |_, _window, cx| {
+                let choices = setup.read(cx).clone();
+                if choices.engine.is_none() {
+                    return false;
+                }
+                on_start(choices, cx);
+                true
+            })
+    });
+}
+
+/// Render a labelled section of the dialog
+fn render_section(label: &'static str) -> Div {
+    v_flex()
+        .gap_1()
+        .child(div().text_xs().text_color(rgb(TEXT_SECONDARY)).child(label))
+}
+
+fn render_side_section(setup: &Entity<PlaySetup>, current: &PlaySetup) -> Div {
+    let buttons = [
+        ("play-white", "White", PieceColor::White),
+        ("play-black", "Black", PieceColor::Black),
+    ]
+    .into_iter()
+    .map(|(id, label, color)| {
+        let setup = setup.clone();
+        Button::new(id)
+            .label(label)
+            .small()
+            .selected(current.human == color)
+            .on_click(move |_, window, cx| {
+                setup.update(cx, |setup, _| setup.human = color);
+                window.refresh();
+            })
+    });
+
+    render_section("Play as").child(div().flex().gap_1().children(buttons))
+}
+
+fn render_engine_section(
+    setup: &Entity<PlaySetup>,
+    current: &PlaySetup,
+    engine_registry: &Entity<EngineRegistryModel>,
+) -> Div {
+    let selected = current.engine.clone();
+    let setup = setup.clone();
+    let engine_registry = engine_registry.clone();
+    let button = Button::new("play-engine")
+        .label(selected.clone().unwrap_or_else(|| "No Engine".to_string()))
+        .outline()
+        .small()
+        .dropdown_menu_with_anchor(Corner::TopLeft, move |menu, _window, cx| {
+            engine_registry
+                .read(cx)
+                .engines()
+                .iter()
+                .fold(menu, |menu, engine| {
+                    let setup = setup.clone();
+                    let name = engine.name.clone();
+                    menu.item(
+                        PopupMenuItem::new(name.clone())
+                            .checked(selected.as_ref() == Some(&name))
+                            .on_click(move |_, window, cx| {
+                                setup.update(cx, |setup, _| setup.engine = Some(name.clone()));
+                                window.refresh();
+                            }),
+                    )
+                })
+        });
+
+    render_section("Engine").child(div().flex().child(button))
+}
@@ -0,0 +1,191 @@
+//! Play panel - clocks, status and controls for a game played with a clock,
+//! over the board or against the engine.
+
+use std::time::Instant;
+
+use gpui::{App, Div, Entity, FontWeight, div, prelude::*, px, rgb};
+use gpui_component::Disableable;
+use gpui_component::button::{Button, ButtonVariants};
+
+use super::board_view::{PlayGame, PlayState};
+use super::{OfferDraw, Resign, ToggleClock};
+use crate::domain::{GameClock, GameHeaders, PieceColor, format_clock};
+use crate::models::GameModel;
+use crate::ui::theme::{
+    BOARD_PADDING, BORDER_COLOR, CLOCK_RUNNING_BG, ERROR_TEXT, MOVE_LIST_BG, PANEL_BG,
+    TEXT_PRIMARY, TEXT_SECONDARY,
+};
+
+/// Time left below which a clock is shown as running low
+const LOW_TIME_MS: u64 = 10_000;
+
+/// Render the clocks and controls of the current game, if it has a clock.
+/// The clocks are stacked like the board: the side at the top of the board first.
+pub fn render_play_panel(
+    play_state: &Entity<PlayState>,
+    model: &Entity<GameModel>,
+    flipped: bool,
+    cx: &App,
+) -> Div {
+    let game = model.read(cx);
+    let Some(clock) = game.clock() else {
+        return div();
+    };
+    let play = play_state.read(cx).game.as_ref();
+    let now = Instant::now();
+    let (top, bottom) = if flipped {
+        (PieceColor::White, PieceColor::Black)
+    } else {
+        (PieceColor::Black, PieceColor::White)
+    };
+    let running_side = clock.running_side();
+
+    let status = match (game.outcome(), play) {
+        (Some(outcome), _) => outcome.to_string(),
+        (None, Some(play)) if play.finished => "Game over".to_string(),
+        (None, Some(play)) if play.draw_declined => {
+            format!("{} declined the draw", play.engine_name)
+        }
+        (None, Some(play)) if running_side == Some(play.human) => "Your move".to_string(),
+        (None, Some(play)) => format!("{} is thinking...", play.engine_name),
+        (None, None) => match running_side {
+            Some(PieceColor::White) => "White to move".to_string(),
+            Some(PieceColor::Black) => "Black to move".to_string(),
+            None => "Clock paused".to_string(),
+        },
+    };
+
+    let controls = div().flex().items_center().justify_end().gap_2().child(
+        div()
+            .flex_1()
+            .text_xs()
+            .text_color(rgb(TEXT_SECONDARY))
+            .child(clock.time_control().label()),
+    );
+    let controls = match play {
+        Some(play) => {
+            let finished = play.finished;
+            controls
+                .child(
+                    Button::new("offer-draw")
+                        .label("Offer Draw")
+                        .compact()
+                        .disabled(finished)
+                        .on_click(|_, window, cx| window.dispatch_action(Box::new(OfferDraw), cx)),
+                )
+                .child(
+                    Button::new("resign")
+                        .label("Resign")
+                        .danger()
+                        .compact()
+                        .disabled(finished)
+                        .on_click(|_, window, cx| window.dispatch_action(Box::new(Resign), cx)),
+                )
+        }
+        None => controls.child(
+            Button::new("toggle-clock")
+                .label(if running_side.is_some() {
+                    "Pause"
+                } else {
+                    "Resume"
+                })
+                .compact()
+                .disabled(game.outcome().is_some())
+                .on_click(|_, window, cx| window.dispatch_action(Box::new(ToggleClock), cx)),
+        ),
+    };
+
+    let play_pane = div()
+        .flex()
+        .flex_col()
+        .gap_2()
+        .p_3()
+        .bg(rgb(MOVE_LIST_BG))
+        .border_1()
+        .border_color(rgb(BORDER_COLOR))
+        .rounded_md()
+        .child(render_clock(clock, play, game.headers(), top, now))
+        .child(render_clock(clock, play, game.headers(), bottom, now))
+        .child(
+            div()
+                .text_sm()
+                .text_color(rgb(TEXT_SECONDARY))
+                .child(status),
+        )
+        .children(
+            play.and_then(|play| play.error.clone())
+                .map(|error| div().text_sm().text_color(rgb(ERROR_TEXT)).child(error)),
+        )
+        .child(controls);
+
+    div()
+        .flex_shrink_0()
+        .bg(rgb(PANEL_BG))
+        .px(px(BOARD_PADDING))
+        .pt(px(BOARD_PADDING))
+        .child(play_pane)
+}
+
+/// Render one side's name and time left, highlighted while its time runs
+fn render_clock(
+    clock: &GameClock,
+    play: Option<&PlayGame>,
+    headers: &GameHeaders,
+    color: PieceColor,
+    now: Instant,
+) -> Div {
+    let remaining = clock.remaining(color, now);
+    let running = clock.running_side() == Some(color);
+    let side = match color {
+        PieceColor::White => "White",
+        PieceColor::Black => "Black",
+    };
+    // Over the board the players are named by the game's tags, if known
+    let (player, side) = match play {
+        Some(play) if color == play.human => ("You".to_string(), Some(side)),
+        Some(play) => (play.engine_name.clone(), Some(side)),
+        None => match headers.known(side) {
+            Some(name) => (name.to_string(), Some(side)),
+            None => (side.to_string(), None),
+        },
+    };
+    let time_color = if remaining < LOW_TIME_MS {
+        ERROR_TEXT
+    } else {
+        TEXT_PRIMARY
+    };
+
+    div()
+        .flex()
+        .items_center()
+        .justify_between()
+        .gap_2()
+        .px_2()
+        .py_1()
+        .rounded_sm()
+        .when(running, |el| el.bg(rgb(CLOCK_RUNNING_BG)))
+        .child(
+            div()
+                .flex()
+                .items_center()
+                .gap_2()
+                .min_w_0()
+                .child(
+                    div()
+                        .text_color(rgb(TEXT_PRIMARY))
+                        .overflow_hidden()
+                        .text_ellipsis()
+                        .child(player),
+                )
+                .children(
+                    side.map(|side| div().text_xs().text_color(rgb(TEXT_SECONDARY)).child(side)),
+                ),
+        )
+        .child(
+            div()
+                .text_xl()
+                .font_weight(FontWeight::BOLD)
+                .text_color(rgb(time_color))
+                .child(format_clock(remaining)),
+        )
+}
@@ -0,0 +1,322 @@
+//! Position setup panel - piece palette and position options for the board editor.
+//!
+//! Pieces are dragged from the palette onto the board; the drag itself is
+//! handled by the board view so it can reuse the normal drag machinery.
+
+use std::rc::Rc;
+
+use gpui::{
+    App, Div, Entity, MouseButton, Pixels, Point, SharedString, div, img, prelude::*, px, rgb,
+};
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::checkbox::Checkbox;
+use gpui_component::{Disableable, Selectable, Sizable};
+
+use super::board_view::SetupState;
+use super::{CancelSetup, FinishSetup};
+use crate::domain::{CastlingSide, Piece, PieceColor, PieceKind, PositionSetup, to_square};
+use crate::ui::assets::piece_svg_path;
+use crate::ui::theme::{
+    BOARD_PADDING, BORDER_COLOR, ERROR_TEXT, MOVE_LIST_BG, PANEL_BG, TEXT_PRIMARY, TEXT_SECONDARY,
+};
+
+const PALETTE_PIECE_SIZE: f32 = 40.0;
+const PALETTE_HOVER_BG: u32 = 0x3a3a3a;
+
+/// Palette order, left to right
+const PALETTE_KINDS: [PieceKind; 6] = [
+    PieceKind::King,
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+    PieceKind::Pawn,
+];
+
+/// Render the setup panel for the position being edited.
+/// `on_pick_piece` is called when a palette piece is pressed, to start dragging it.
+pub fn render_setup_panel(
+    setup_state: &Entity<SetupState>,
+    on_pick_piece: impl Fn(Piece, Point<Pixels>, &mut App) + 'static,
+    cx: &App,
+) -> Div {
+    let Some(setup) = setup_state.read(cx).setup.as_ref() else {
+        return div();
+    };
+    let on_pick_piece = Rc::new(on_pick_piece);
+    let error = setup.to_position().err();
+
+    let palette = div()
+        .flex()
+        .flex_col()
+        .gap_1()
+        .child(render_palette_row(PieceColor::White, &on_pick_piece))
+        .child(render_palette_row(PieceColor::Black, &on_pick_piece));
+
+    let options = div()
+        .flex()
+        .flex_col()
+        .gap_3()
+        .child(render_turn_section(setup_state, setup))
+        .child(render_castling_section(setup_state, setup))
+        .child(render_ep_section(setup_state, setup));
+
+    let setup_pane = div()
+        .flex_1()
+        .min_h_0()
+        .flex()
+        .flex_col()
+        .bg(rgb(MOVE_LIST_BG))
+        .border_1()
+        .border_color(rgb(BORDER_COLOR))
+        .rounded_md()
+        .overflow_hidden()
+        // Header
+        .child(
+            div()
+                .flex_shrink_0()
+                .flex()
+                .items_center()
+                .justify_between()
+                .p_4()
+                .pb_2()
+                .text_color(rgb(TEXT_PRIMARY))
+                .border_b_1()
+                .border_color(rgb(BORDER_COLOR))
+                .child("Set Up Position")
+                .child(
+                    div()
+                        .flex()
+                        .gap_1()
+                        .child(render_board_button(
+                            "setup-clear",
+                            "Clear",
+                            setup_state,
+                            PositionSetup::clear,
+                        ))
+                        .child(render_board_button(
+                            "setup-initial",
+                            "Initial",
+                            setup_state,
+                            |setup| *setup = PositionSetup::default(),
+                        )),
+                ),
+        )
+        // Palette and options (scrollable)
+        .child(
+            div()
+                .id("setup-scroll")
+                .flex_1()
+                .overflow_y_scroll()
+                .flex()
+                .flex_col()
+                .gap_4()
+                .p_4()
+                .child(palette)
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(TEXT_SECONDARY))
+                        .child("Drag pieces onto the board. Drag off or right-click to remove."),
+                )
+                .child(options),
+        )
+        // Validation status and confirm/cancel
+        .child(
+            div()
+                .flex_shrink_0()
+                .flex()
+                .flex_col()
+                .gap_2()
+                .p_3()
+                .border_t_1()
+                .border_color(rgb(BORDER_COLOR))
+                .when_some(error, |el, error| {
+                    el.child(
+                        div()
+                            .text_sm()
+                            .text_color(rgb(ERROR_TEXT))
+                            .child(format!("Illegal position: {}", error)),
+                    )
+                })
+                .child(
+                    div()
+                        .flex()
+                        .justify_end()
+                        .gap_2()
+                        .child(
+                            Button::new("setup-cancel")
+                                .label("Cancel")
+                                .compact()
+                                .on_click(|_, window, cx| {
+                                    window.dispatch_action(Box::new(CancelSetup), cx)
+                                }),
+                        )
+                        .child(
+                            Button::new("setup-done")
+                                .label("Start Game")
+                                .primary()
+                                .compact()
+                                .disabled(error.is_some())
+                                .on_click(|_, window, cx| {
+                                    window.dispatch_action(Box::new(FinishSetup), cx)
+                                }),
+                        ),
+                ),
+        );
+
+    div()
+        .size_full()
+        .flex()
+        .flex_col()
+        .overflow_hidden()
+        .bg(rgb(PANEL_BG))
+        .p(px(BOARD_PADDING))
+        .child(setup_pane)
+}
+
+/// Render one row of palette pieces for a color
+fn render_palette_row(
+    color: PieceColor,
+    on_pick_piece: &Rc<impl Fn(Piece, Point<Pixels>, &mut App) + 'static>,
+) -> Div {
+    div()
+        .flex()
+        .gap_1()
+        .children(PALETTE_KINDS.into_iter().map(|kind| {
+            let piece = Piece { kind, color };
+            let on_pick_piece = on_pick_piece.clone();
+            div()
+                .size(px(PALETTE_PIECE_SIZE))
+                .rounded_sm()
+                .cursor_pointer()
+                .hover(|s| s.bg(rgb(PALETTE_HOVER_BG)))
+                .on_mouse_down(MouseButton::Left, move |ev, _window, cx| {
+                    on_pick_piece(piece, ev.position, cx);
+                })
+                .child(img(piece_svg_path(&piece)).size(px(PALETTE_PIECE_SIZE)))
+        }))
+}
+
+/// Render a labelled option section
+fn render_section(label: &'static str) -> Div {
+    div()
+        .flex()
+        .flex_col()
+        .gap_1()
+        .child(div().text_xs().text_color(rgb(TEXT_SECONDARY)).child(label))
+}
+
+/// Render a small header button that edits the setup
+fn render_board_button(
+    id: &'static str,
+    label: &'static str,
+    setup_state: &Entity<SetupState>,
+    edit: impl Fn(&mut PositionSetup) + 'static,
+) -> Button {
+    let setup_state = setup_state.clone();
+    Button::new(id)
+        .label(label)
+        .ghost()
+        .small()
+        .on_click(move |_, _, cx| update_setup(&setup_state, cx, &edit))
+}
+
+fn render_turn_section(setup_state: &Entity<SetupState>, setup: &PositionSetup) -> Div {
+    let turn = setup.turn();
+    let buttons = [
+        ("turn-white", "White", PieceColor::White),
+        ("turn-black", "Black", PieceColor::Black),
+    ]
+    .into_iter()
+    .map(|(id, label, color)| {
+        let setup_state = setup_state.clone();
+        Button::new(id)
+            .label(label)
+            .small()
+            .selected(turn == color)
+            .on_click(move |_, _, cx| {
+                update_setup(&setup_state, cx, |setup| setup.set_turn(color));
+            })
+    });
+
+    render_section("Side to move").child(div().flex().gap_1().children(buttons))
+}
+
+fn render_castling_section(setup_state: &Entity<SetupState>, setup: &PositionSetup) -> Div {
+    let rights = [
+        (
+            "castle-wk",
+            "White O-O",
+            PieceColor::White,
+            CastlingSide::KingSide,
+        ),
+        (
+            "castle-wq",
+            "White O-O-O",
+            PieceColor::White,
+            CastlingSide::QueenSide,
+        ),
+        (
+            "castle-bk",
+            "Black O-O",
+            PieceColor::Black,
+            CastlingSide::KingSide,
+        ),
+        (
+            "castle-bq",
+            "Black O-O-O",
+            PieceColor::Black,
+            CastlingSide::QueenSide,
+        ),
+    ];
+    let checkboxes = rights.into_iter().map(|(id, label, color, side)| {
+        let setup_state = setup_state.clone();
+        Checkbox::new(id)
+            .label(label)
+            .checked(setup.can_castle(color, side))
+            .on_click(move |checked, _, cx| {
+                let checked = *checked;
+                update_setup(&setup_state, cx, |setup| {
+                    setup.set_castling(color, side, checked)
+                });
+            })
+    });
+
+    render_section("Castling").child(div().flex().flex_col().gap_1().children(checkboxes))
+}
+
+fn render_ep_section(setup_state: &Entity<SetupState>, setup: &PositionSetup) -> Div {
+    let current = setup.ep_square();
+    let choices = std::iter::once(None).chain(setup.ep_candidates().into_iter().map(Some));
+    let buttons = choices.map(|choice| {
+        let setup_state = setup_state.clone();
+        let label = match choice {
+            Some((row, col)) => to_square(row, col).to_string(),
+            None => "None".to_string(),
+        };
+        Button::new(SharedString::from(format!("ep-{label}")))
+            .label(label)
+            .small()
+            .selected(current == choice)
+            .on_click(move |_, _, cx| {
+                update_setup(&setup_state, cx, |setup| setup.set_ep_square(choice));
+            })
+    });
+
+    render_section("En passant").child(div().flex().flex_wrap().gap_1().children(buttons))
+}
+
+/// Apply an edit to the position being set up
+fn update_setup(
+    setup_state: &Entity<SetupState>,
+    cx: &mut App,
+    edit: impl FnOnce(&mut PositionSetup),
+) {
+    setup_state.update(cx, |state, cx| {
+        if let Some(setup) = state.setup.as_mut() {
+            edit(setup);
+            cx.notify();
+        }
+    });
+}